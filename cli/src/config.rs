@@ -0,0 +1,214 @@
+//! The configuration file, `config.toml` in the platform's configuration directory.
+//!
+//! Every setting is optional, and command-line flags take precedence over the file.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crossterm::style::Color;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::days::{DayStart, TimeZone};
+use crate::report;
+use crate::speech::ExternalCommand;
+use crate::ui::Keys;
+use crate::{OrderTolerance, Weights};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Config {
+    /// The weights to use for each knowledge level, like `--weights`.
+    pub(crate) weights: Option<Weights>,
+    /// The number of typos allowed in each part of an answer, like `--typo-tolerance`.
+    pub(crate) typo_tolerance: usize,
+    /// The fraction of pairs of steps that can be out of order, like `--order-tolerance`.
+    pub(crate) order_tolerance: OrderTolerance,
+    /// The colours used while learning.
+    pub(crate) theme: Theme,
+    /// The keys used while learning.
+    pub(crate) keys: Keys,
+    /// The number of questions in each round, like `--round-size`.
+    pub(crate) round_size: Option<usize>,
+    /// The knowledge database to use instead of the default one.
+    pub(crate) database: Option<PathBuf>,
+    /// The sets to learn when `revise learn` is given none.
+    pub(crate) sets: Vec<PathBuf>,
+    /// What to do when learning sets whose cards are all mastered already.
+    pub(crate) when_complete: WhenComplete,
+    /// The time at which a new study day starts, for streaks.
+    pub(crate) day_start: DayStart,
+    /// The time zone study days are in, instead of the system's.
+    pub(crate) time_zone: Option<TimeZone>,
+    /// How to show errors and warnings, like `--report-style`.
+    pub(crate) report_style: report::Style,
+    /// The command that records an answer spoken out loud and prints it as text.
+    pub(crate) speech_to_text: Option<ExternalCommand>,
+    /// The command that reads aloud the text given on its standard input, for `--speak`.
+    pub(crate) text_to_speech: Option<ExternalCommand>,
+    /// How to record clips of answers said out loud.
+    #[cfg(feature = "media")]
+    pub(crate) record_audio: Option<crate::media::RecordAudio>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum WhenComplete {
+    /// Move every card down a level so that there is something to learn.
+    #[default]
+    Demote,
+    /// Leave the cards as they are.
+    Keep,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Theme {
+    /// The number of cards on the lowest level.
+    pub(crate) unknown: Color,
+    /// The number of cards on the highest level.
+    pub(crate) learnt: Color,
+    /// The number of cards at risk of moving down a level.
+    pub(crate) at_risk: Color,
+    /// The expected answer after an incorrect one.
+    pub(crate) correct: Color,
+    /// The message shown when an answer is incorrect.
+    pub(crate) incorrect: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            unknown: Color::DarkRed,
+            learnt: Color::DarkGreen,
+            at_risk: Color::DarkYellow,
+            correct: Color::DarkGreen,
+            incorrect: Color::DarkRed,
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration file, or the default configuration if there isn't one.
+    ///
+    /// Relative paths in the file are resolved relative to the directory containing it.
+    pub(crate) fn load() -> Result<Self, LoadError> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// The path of the configuration file, whether or not it exists, or `None` if no home
+    /// directory could be found.
+    pub(crate) fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "revise").map(|dirs| dirs.config_dir().join("config.toml"))
+    }
+
+    fn load_from(path: &Path) -> Result<Self, LoadError> {
+        let error = |inner| LoadError {
+            path: path.to_owned(),
+            inner,
+        };
+
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(error(LoadErrorInner::Read(e))),
+        };
+        let mut config: Self =
+            toml::from_str(&text).map_err(|e| error(LoadErrorInner::Parse(e)))?;
+
+        let dir = path.parent().unwrap();
+        if let Some(database) = &mut config.database {
+            *database = dir.join(&*database);
+        }
+        for set in &mut config.sets {
+            *set = dir.join(&*set);
+        }
+
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("failed to load config file `{}`", path.display())]
+pub(crate) struct LoadError {
+    path: PathBuf,
+    #[source]
+    inner: LoadErrorInner,
+}
+
+#[derive(Debug, Error)]
+enum LoadErrorInner {
+    #[error(transparent)]
+    Read(io::Error),
+    #[error(transparent)]
+    Parse(toml::de::Error),
+}
+
+#[test]
+fn test_load() {
+    let dir = std::env::temp_dir().join(format!("revise-config-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+
+    let config = Config::load_from(&path).unwrap();
+    assert!(config.weights.is_none());
+    assert_eq!(config.typo_tolerance, 0);
+    assert!(config.sets.is_empty());
+    assert_eq!(config.when_complete, WhenComplete::Demote);
+
+    fs::write(
+        &path,
+        "weights = [10, 2, 0.5]\n\
+        typo-tolerance = 1\n\
+        order-tolerance = 0.25\n\
+        database = \"/data.sqlite3\"\n\
+        sets = [\"french.set\"]\n\
+        when-complete = \"keep\"\n\
+        day-start = \"04:30\"\n\
+        time-zone = \"Europe/Paris\"\n\
+        report-style = \"short\"\n\
+        \n\
+        [theme]\n\
+        learnt = \"blue\"\n\
+        \n\
+        [keys]\n\
+        quit = [\"esc\"]\n",
+    )
+    .unwrap();
+    let config = Config::load_from(&path).unwrap();
+    assert_eq!(config.weights.unwrap().0, [10.0, 2.0, 0.5]);
+    assert_eq!(config.typo_tolerance, 1);
+    assert!((config.order_tolerance.0 - 0.25).abs() < f64::EPSILON);
+    assert_eq!(config.database.unwrap(), Path::new("/data.sqlite3"));
+    assert_eq!(config.sets, [dir.join("french.set")]);
+    assert_eq!(config.when_complete, WhenComplete::Keep);
+    assert_eq!(
+        config.day_start,
+        DayStart::try_from("04:30".to_owned()).unwrap()
+    );
+    assert!(config.time_zone.is_some());
+    assert_eq!(config.report_style, report::Style::Short);
+    assert_eq!(config.theme.learnt, Color::Blue);
+    assert_eq!(config.theme.unknown, Color::DarkRed);
+    assert_eq!(config.keys.quit.to_string(), "esc");
+    assert_eq!(config.keys.hint.to_string(), "tab");
+    assert_eq!(config.keys.reveal.to_string(), "space");
+
+    fs::write(&path, "weights = [1, -1]\n").unwrap();
+    assert!(Config::load_from(&path).is_err());
+    fs::write(&path, "order-tolerance = 2\n").unwrap();
+    assert!(Config::load_from(&path).is_err());
+    fs::write(&path, "colour = \"red\"\n").unwrap();
+    assert!(Config::load_from(&path).is_err());
+    fs::write(&path, "[keys]\nsubmit = []\n").unwrap();
+    assert!(Config::load_from(&path).is_err());
+    fs::write(&path, "day-start = \"4am\"\n").unwrap();
+    assert!(Config::load_from(&path).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
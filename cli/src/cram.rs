@@ -0,0 +1,182 @@
+use std::io;
+
+use rand::seq::SliceRandom as _;
+
+use revise_engine::{Engine, KnowledgeStore};
+
+use crate::learn::DisplayAnswer;
+use crate::search::Matcher;
+
+/// The loaded cards to cram, as indices into [`Engine::cards`]: those matching `matcher`, if one
+/// is given, leaving out mastered cards unless `include_mastered` is set.
+///
+/// Cards are mastered at the engine's mastery level, or else at the highest level.
+pub fn cards_to_cram<S: KnowledgeStore>(
+    engine: &Engine<S>,
+    matcher: Option<&Matcher>,
+    include_mastered: bool,
+) -> anyhow::Result<Vec<usize>> {
+    let store = engine.store();
+    let mastery = engine.mastery().unwrap_or_else(|| store.levels().max());
+    let mut cards = Vec::new();
+    for (index, (card, key)) in engine.cards().zip(engine.card_keys()).enumerate() {
+        if matcher.is_some_and(|matcher| !matcher.matches_card(card)) {
+            continue;
+        }
+        if !include_mastered && store.knowledge(key)?.level >= mastery {
+            continue;
+        }
+        cards.push(index);
+    }
+    Ok(cards)
+}
+
+/// Ask about each of the given cards once in a random order, showing straight away whether each
+/// answer was right. If `repeat` is set, the cards answered incorrectly are asked again in more
+/// rounds until every card has been answered correctly.
+///
+/// Unlike learning, how well the cards are known doesn't decide which are asked, and answers are
+/// only recorded if `record` is set.
+pub fn cram(
+    engine: &mut Engine<impl KnowledgeStore>,
+    mut cards: Vec<usize>,
+    repeat: bool,
+    record: bool,
+    mut input: impl io::BufRead,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let total = cards.len();
+    let mut rng = rand::thread_rng();
+    let mut correct = 0;
+
+    writeln!(out, "{}", engine.title())?;
+    for round in 1.. {
+        if cards.is_empty() {
+            break;
+        }
+        writeln!(out)?;
+        writeln!(out, "Round {round}: {}", plural(cards.len()))?;
+        cards.shuffle(&mut rng);
+
+        let mut incorrect = Vec::new();
+        for &card in &cards {
+            let question = engine.ask(card)?;
+            writeln!(out)?;
+            writeln!(out, "{}", question.prompt)?;
+            if let Some(disambiguation) = question.disambiguation {
+                writeln!(out, "({disambiguation})")?;
+            }
+            if let Some(steps) = &question.steps {
+                writeln!(out, "Put in order: {}", steps.join(", "))?;
+            }
+            write!(out, "Term: ")?;
+            out.flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                writeln!(out)?;
+                return Ok(());
+            }
+
+            let is_correct = question.is_correct(answer.trim_end_matches(['\n', '\r']));
+            if is_correct {
+                writeln!(out, "Correct")?;
+                correct += 1;
+            } else {
+                writeln!(out, "Incorrect")?;
+                writeln!(out, "Answer: {}", DisplayAnswer(question.card))?;
+                incorrect.push(card);
+            }
+            if record {
+                engine.record_result(is_correct)?;
+            }
+        }
+
+        if !repeat {
+            writeln!(out)?;
+            writeln!(out, "{correct} of {} correct.", plural(total))?;
+            return Ok(());
+        }
+        cards = incorrect;
+        if cards.is_empty() {
+            writeln!(out)?;
+            let rounds = if round == 1 { "round" } else { "rounds" };
+            writeln!(out, "All {} correct after {round} {rounds}.", plural(total))?;
+        }
+    }
+
+    if total == 0 {
+        writeln!(out)?;
+        writeln!(out, "There are no cards to cram.")?;
+    }
+    Ok(())
+}
+
+fn plural(cards: usize) -> String {
+    format!("{cards} card{}", if cards == 1 { "" } else { "s" })
+}
+
+/// An engine with two colours loaded, and the indices of their cards.
+#[cfg(test)]
+fn colours() -> (Engine<revise_engine::MemoryStore>, usize, usize) {
+    let set = revise_parser::parse_set("Colours\n\nrouge - red\nbleu - blue\n").unwrap();
+    let mut engine = Engine::new(revise_engine::MemoryStore::new());
+    engine.load_set(set, false);
+    let index = |term| engine.cards().position(|card| card.terms.contains(term));
+    let (rouge, bleu) = (index("rouge").unwrap(), index("bleu").unwrap());
+    (engine, rouge, bleu)
+}
+
+#[test]
+fn test_cards_to_cram() {
+    let (mut engine, rouge, bleu) = colours();
+    assert_eq!(cards_to_cram(&engine, None, false).unwrap().len(), 2);
+
+    let matcher = Matcher::new("BL", false, false).unwrap();
+    assert_eq!(
+        cards_to_cram(&engine, Some(&matcher), false).unwrap(),
+        [bleu]
+    );
+
+    engine.set_mastery(Some(revise_engine::KnowledgeLevel::new(1).unwrap()));
+    engine.ask(rouge).unwrap();
+    engine.record_result(true).unwrap();
+    assert_eq!(cards_to_cram(&engine, None, false).unwrap(), [bleu]);
+    assert_eq!(cards_to_cram(&engine, None, true).unwrap().len(), 2);
+}
+
+#[test]
+fn test_cram() {
+    let (mut engine, rouge, _) = colours();
+    let mut cram = |repeat, record, input: &str| {
+        let mut out = Vec::new();
+        cram(
+            &mut engine,
+            vec![rouge],
+            repeat,
+            record,
+            input.as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    };
+
+    assert_eq!(
+        cram(false, false, "rouge\n"),
+        "Colours\n\
+        \nRound 1: 1 card\n\
+        \nrouge\nTerm: Incorrect\nAnswer: red\n\
+        \n0 of 1 card correct.\n",
+    );
+    assert_eq!(
+        cram(true, false, "x\nred\n"),
+        "Colours\n\
+        \nRound 1: 1 card\n\
+        \nrouge\nTerm: Incorrect\nAnswer: red\n\
+        \nRound 2: 1 card\n\
+        \nrouge\nTerm: Correct\n\
+        \nAll 1 card correct after 2 rounds.\n",
+    );
+    cram(false, true, "red\n");
+    assert_eq!(engine.snapshot().unwrap().level_distribution, [1, 1, 0, 0]);
+}
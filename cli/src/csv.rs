@@ -0,0 +1,104 @@
+//! Reading and writing CSV, as described in RFC 4180.
+
+use std::borrow::Cow;
+use std::io;
+
+use thiserror::Error;
+
+/// Write a row of fields, quoting them where necessary.
+pub fn write_row<'a>(
+    out: &mut impl io::Write,
+    fields: impl IntoIterator<Item = &'a str>,
+) -> io::Result<()> {
+    let fields = fields.into_iter().map(escape).collect::<Vec<_>>();
+    writeln!(out, "{}", fields.join(","))
+}
+
+/// Quote a field if it needs to be.
+fn escape(field: &str) -> Cow<'_, str> {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Parse CSV text into rows of fields. Lines may end in LF or CRLF, and blank lines are skipped.
+pub fn parse(text: &str) -> Result<Vec<Vec<String>>, ParseError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => {
+                let start_line = line;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => return Err(ParseError { line: start_line }),
+                    }
+                }
+            }
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                line += 1;
+                if !row.is_empty() || !field.is_empty() {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+            }
+            c => field.push(c),
+        }
+    }
+    if !row.is_empty() || !field.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// An error parsing CSV.
+#[derive(Debug, Error)]
+#[error("unclosed quote starting on line {line}")]
+pub struct ParseError {
+    line: usize,
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(escape("bonjour"), "bonjour");
+    assert_eq!(escape("hello, hi"), "\"hello, hi\"");
+    assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(escape("a\nb"), "\"a\nb\"");
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        parse("a,b\r\n\n\"c, \"\"d\"\"\",\n\"e\nf\",g").unwrap(),
+        [vec!["a", "b"], vec!["c, \"d\"", ""], vec!["e\nf", "g"],]
+    );
+    assert!(parse("a\n\"b").is_err());
+
+    let mut out = Vec::new();
+    write_row(&mut out, ["a\"", "b,c", "", "d\r\ne"]).unwrap();
+    assert_eq!(
+        parse(std::str::from_utf8(&out).unwrap()).unwrap(),
+        [["a\"", "b,c", "", "d\r\ne"]]
+    );
+}
@@ -0,0 +1,107 @@
+//! Dividing answer times into study days.
+
+use std::convert::TryFrom;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::config::Config;
+
+/// The time of day at which a new study day starts, written like `04:00`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct DayStart(NaiveTime);
+
+impl TryFrom<String> for DayStart {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        NaiveTime::parse_from_str(&s, "%H:%M")
+            .map(Self)
+            .map_err(|_| format!("`{s}` is not a time like `04:00`"))
+    }
+}
+
+/// A time zone from the IANA database, like `Europe/London`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct TimeZone(Tz);
+
+impl TryFrom<String> for TimeZone {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+            .map(Self)
+            .map_err(|_| format!("unknown time zone `{s}`"))
+    }
+}
+
+/// Converts times, which are stored in UTC, into the study days they belong to.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StudyDays {
+    start: DayStart,
+    time_zone: Option<TimeZone>,
+}
+
+impl StudyDays {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            start: config.day_start,
+            time_zone: config.time_zone,
+        }
+    }
+
+    /// The study day a time is in. Times before the start of the day count towards the day before.
+    pub(crate) fn day_of(&self, time: SystemTime) -> NaiveDate {
+        let utc = DateTime::<Utc>::from(time);
+        let local = match self.time_zone {
+            Some(TimeZone(tz)) => utc.with_timezone(&tz).naive_local(),
+            None => utc.with_timezone(&Local).naive_local(),
+        };
+        self.day_of_local(local)
+    }
+
+    fn day_of_local(&self, local: NaiveDateTime) -> NaiveDate {
+        (local - self.start.0.signed_duration_since(NaiveTime::MIN)).date()
+    }
+
+    /// The current study day.
+    pub(crate) fn today(&self) -> NaiveDate {
+        self.day_of(SystemTime::now())
+    }
+}
+
+#[test]
+fn test_study_days() {
+    let at = |time: &str| NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap();
+    let date = |date: &str| NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+    let days = |start: &str| StudyDays {
+        start: DayStart::try_from(start.to_owned()).unwrap(),
+        time_zone: None,
+    };
+
+    assert_eq!(
+        days("00:00").day_of_local(at("2024-03-01 00:30")),
+        date("2024-03-01")
+    );
+    assert_eq!(
+        days("04:00").day_of_local(at("2024-03-01 03:59")),
+        date("2024-02-29")
+    );
+    assert_eq!(
+        days("04:00").day_of_local(at("2024-03-01 04:00")),
+        date("2024-03-01")
+    );
+    assert!(DayStart::try_from("25:00".to_owned()).is_err());
+
+    let tokyo = StudyDays {
+        start: DayStart::default(),
+        time_zone: Some(TimeZone::try_from("Asia/Tokyo".to_owned()).unwrap()),
+    };
+    let time = at("2024-03-01 20:00").and_utc();
+    assert_eq!(tokyo.day_of(time.into()), date("2024-03-02"));
+    assert!(TimeZone::try_from("Mars/Olympus".to_owned()).is_err());
+}
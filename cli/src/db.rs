@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use revise_database::{CardKey, Database, MergeStrategy};
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Check the database for corruption, find cards in none of the given sets and vacuum it.
+    Doctor {
+        /// Remove the knowledge and history of every card that is in none of the sets, and of every
+        /// card whose key can't be read.
+        #[clap(long)]
+        prune: bool,
+
+        /// The sets whose cards to look for in the database.
+        sets: Vec<PathBuf>,
+    },
+
+    /// List the sets that have been learned, with how many cards each had when last loaded.
+    Sets,
+
+    /// Merge the knowledge and history in another database file into this one.
+    Merge {
+        /// How to combine the knowledge of cards that both databases know.
+        #[clap(long, arg_enum, default_value = "highest")]
+        strategy: Strategy,
+
+        /// The database to merge in.
+        other: PathBuf,
+    },
+}
+
+/// How to combine the knowledge of cards that both databases know.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+pub enum Strategy {
+    /// Take the higher level, with a safety net if either database has one.
+    Highest,
+    /// Keep this database's knowledge.
+    Ours,
+    /// Take the other database's knowledge.
+    Theirs,
+}
+
+/// Merge the database at `other` into `database`.
+pub fn merge(
+    database: &mut Database,
+    other: &Path,
+    strategy: Strategy,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(other.exists(), "{} does not exist", other.display());
+    let other = Database::open_read_only(other)?;
+    let strategy = match strategy {
+        Strategy::Highest => MergeStrategy::Highest,
+        Strategy::Ours => MergeStrategy::Ours,
+        Strategy::Theirs => MergeStrategy::Theirs,
+    };
+    let summary = database.merge(&other, strategy)?;
+    writeln!(
+        out,
+        "Merged the knowledge of {} and {} from the other database.",
+        plural(summary.cards, "card"),
+        plural(summary.answers, "answer"),
+    )?;
+    Ok(())
+}
+
+/// List the sets recorded in the database.
+pub fn sets(database: &Database, mut out: impl io::Write) -> anyhow::Result<()> {
+    let sets = database.sets()?;
+    if sets.is_empty() {
+        writeln!(out, "No sets have been learned yet.")?;
+    }
+    for set in sets {
+        writeln!(
+            out,
+            "{} ({}, last loaded {}): {}",
+            set.title,
+            plural(set.cards, "card"),
+            DateTime::<Local>::from(set.loaded_at).format("%Y-%m-%d %H:%M"),
+            set.path.display(),
+        )?;
+    }
+    Ok(())
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+/// Check the database, listing the cards whose keys can't be read and, if `known` is given, the
+/// cards it has records of that are not in it, and removing both if `prune` is set.
+pub fn doctor(
+    database: &mut Database,
+    known: Option<&HashSet<CardKey>>,
+    prune: bool,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let problems = database.integrity_check()?;
+    if !problems.is_empty() {
+        for problem in &problems {
+            writeln!(out, "{problem}")?;
+        }
+        anyhow::bail!("the database is damaged, so it has been left as it is");
+    }
+    writeln!(out, "No corruption found.")?;
+
+    let malformed = database.malformed_keys()?;
+    match malformed {
+        0 => {}
+        1 => writeln!(out, "1 card in the database has a key that can't be read.")?,
+        n => writeln!(
+            out,
+            "{n} cards in the database have keys that can't be read."
+        )?,
+    }
+    if malformed > 0 {
+        if prune {
+            database.prune_malformed_keys()?;
+            writeln!(out, "Removed them from the database.")?;
+        } else {
+            writeln!(out, "Pass `--prune` to remove them from the database.")?;
+        }
+    }
+
+    if let Some(known) = known {
+        let orphans = database.orphans(known)?;
+        match orphans.len() {
+            0 => writeln!(out, "Every card in the database is in one of the sets.")?,
+            1 => writeln!(out, "1 card in the database is in none of the sets:")?,
+            n => writeln!(out, "{n} cards in the database are in none of the sets:")?,
+        }
+        for card in &orphans {
+            if let Some(description) = database.describe(card)? {
+                writeln!(out, "  {description}")?;
+            }
+        }
+        if !orphans.is_empty() {
+            if prune {
+                database.prune(&orphans)?;
+                writeln!(out, "Removed them from the database.")?;
+            } else {
+                writeln!(out, "Pass `--prune` to remove them from the database.")?;
+            }
+        }
+    }
+
+    database.vacuum()?;
+    writeln!(out, "Vacuumed the database.")?;
+    Ok(())
+}
+
+#[test]
+fn test_doctor() {
+    use revise_database::KnowledgeStore as _;
+    use std::collections::BTreeSet;
+
+    let mut database = Database::open_in_memory().unwrap();
+    let cards = [
+        CardKey::new(&BTreeSet::from(["a"]), &BTreeSet::from(["b"])),
+        CardKey::new(&BTreeSet::from(["c", "d"]), &BTreeSet::from(["e"])),
+    ];
+    for card in &cards {
+        database.record_correct(card).unwrap();
+    }
+    let known = [cards[0].clone()].into_iter().collect::<HashSet<_>>();
+    let mut run = |known, prune| {
+        let mut out = Vec::new();
+        doctor(&mut database, known, prune, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+
+    assert_eq!(
+        run(None, false),
+        "No corruption found.\nVacuumed the database.\n"
+    );
+    assert_eq!(
+        run(Some(&known), false),
+        "No corruption found.\n\
+        1 card in the database is in none of the sets:\n  c, d - e\n\
+        Pass `--prune` to remove them from the database.\n\
+        Vacuumed the database.\n",
+    );
+    assert!(run(Some(&known), true).contains("Removed them from the database.\n"));
+    assert!(
+        run(Some(&known), false).contains("Every card in the database is in one of the sets.\n")
+    );
+}
@@ -0,0 +1,51 @@
+use std::io;
+use std::path::Path;
+
+use revise_parser::{Card, Set};
+
+/// Show the cards that are only in one of two sets, in the order they are in their files, and how
+/// many the sets share.
+pub fn diff(a: (&Path, &Set), b: (&Path, &Set), mut out: impl io::Write) -> io::Result<()> {
+    let (a_path, a) = a;
+    let (b_path, b) = b;
+
+    for (path, only) in [(a_path, a.difference(b)), (b_path, b.difference(a))] {
+        if only.cards.is_empty() {
+            continue;
+        }
+        writeln!(out, "Only in {}:", path.display())?;
+        let mut cards = only.cards.iter().collect::<Vec<_>>();
+        cards.sort_by_key(|card| card.line);
+        for card in cards {
+            writeln!(out, "  {}", format_card(card))?;
+        }
+        writeln!(out)?;
+    }
+
+    let shared = a.intersection(b).cards.len();
+    writeln!(
+        out,
+        "{shared} card{} in both.",
+        if shared == 1 { " is" } else { "s are" },
+    )
+}
+
+fn format_card(card: &Card) -> String {
+    revise_parser::format_card(card).expect("parsed cards can be formatted")
+}
+
+#[test]
+fn test_diff() {
+    let a = revise_parser::parse_set("A\n\nz - 3\nx - 1\ny - 2 !3\n").unwrap();
+    let b = revise_parser::parse_set("B\n\ny - 2\nz - three\n").unwrap();
+    let mut out = Vec::new();
+    diff((Path::new("a.set"), &a), (Path::new("b.set"), &b), &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Only in a.set:\n  z - 3\n  x - 1\n\nOnly in b.set:\n  z - three\n\n1 card is in both.\n",
+    );
+
+    let mut out = Vec::new();
+    diff((Path::new("a.set"), &a), (Path::new("a.set"), &a), &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "3 cards are in both.\n");
+}
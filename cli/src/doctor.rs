@@ -0,0 +1,329 @@
+//! `revise doctor`, which looks for problems with the environment `revise` runs in.
+
+use std::error::Error;
+use std::fs;
+use std::io::{self, IsTerminal as _};
+use std::path::{Path, PathBuf};
+
+use revise_database::Database;
+
+use crate::config::{Config, LoadError};
+
+/// The result of one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warning,
+    Problem,
+}
+
+#[derive(Debug)]
+struct Finding {
+    check: &'static str,
+    status: Status,
+    message: String,
+    /// What to do about a warning or problem.
+    help: Option<String>,
+}
+
+impl Finding {
+    fn ok(check: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Ok,
+            message: message.into(),
+            help: None,
+        }
+    }
+
+    fn warning(check: &'static str, message: impl Into<String>, help: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Warning,
+            message: message.into(),
+            help: Some(help.into()),
+        }
+    }
+
+    fn problem(check: &'static str, message: impl Into<String>, help: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Problem,
+            message: message.into(),
+            help: Some(help.into()),
+        }
+    }
+}
+
+/// Check the terminal, locale, config file, database and configured sets, printing what was found
+/// and how to fix any problems. Returns the number of problems, not counting warnings.
+///
+/// `config` is the configuration as loaded, or the default one if loading failed with
+/// `config_error`.
+pub fn doctor(
+    config: &Config,
+    config_error: Option<&LoadError>,
+    mut out: impl io::Write,
+) -> io::Result<usize> {
+    let findings = [
+        vec![terminal()],
+        vec![locale(|name| std::env::var(name).ok())],
+        vec![config_file(Config::path().as_deref(), config_error)],
+        vec![database(
+            config
+                .database
+                .clone()
+                .or_else(revise_database::default_path)
+                .as_deref(),
+        )],
+        sets(&config.sets),
+    ];
+
+    let mut problems = 0;
+    for finding in findings.iter().flatten() {
+        let status = match finding.status {
+            Status::Ok => "ok",
+            Status::Warning => "warning",
+            Status::Problem => {
+                problems += 1;
+                "problem"
+            }
+        };
+        writeln!(out, "{status:<8} {}: {}", finding.check, finding.message)?;
+        if let Some(help) = &finding.help {
+            writeln!(out, "{:<8} help: {help}", "")?;
+        }
+    }
+    Ok(problems)
+}
+
+fn terminal() -> Finding {
+    const CHECK: &str = "terminal";
+
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Finding::warning(
+            CHECK,
+            "not running in a terminal",
+            "run `revise` in a terminal, or pass `--no-tui` to read answers line by line",
+        );
+    }
+    if std::env::var("TERM").is_ok_and(|term| term == "dumb") {
+        return Finding::warning(
+            CHECK,
+            "`TERM` is `dumb`, so the full-screen interface may not draw properly",
+            "use a terminal emulator that supports colours and cursor movement, or pass `--no-tui`",
+        );
+    }
+    match crossterm::terminal::size() {
+        Ok((width, height)) if width < 40 || height < 10 => Finding::warning(
+            CHECK,
+            format!("the terminal is only {width}x{height}"),
+            "make the window bigger, since questions and answers need at least 40x10",
+        ),
+        Ok((width, height)) => Finding::ok(CHECK, format!("{width}x{height}")),
+        Err(e) => Finding::problem(
+            CHECK,
+            format!("couldn't get the size of the terminal: {e}"),
+            "pass `--no-tui` to read answers line by line instead",
+        ),
+    }
+}
+
+/// Check that the locale uses UTF-8, given a way to read environment variables.
+fn locale(var: impl Fn(&str) -> Option<String>) -> Finding {
+    const CHECK: &str = "locale";
+    const HELP: &str = "set `LANG` to a UTF-8 locale like `en_US.UTF-8`, so that accented \
+        letters are read and shown properly";
+
+    // The first of these that is set decides the character encoding.
+    let Some((name, value)) = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|name| Some((name, var(name).filter(|value| !value.is_empty())?)))
+    else {
+        return if cfg!(windows) {
+            Finding::ok(CHECK, "using the Windows console's encoding")
+        } else {
+            Finding::warning(CHECK, "no locale is set", HELP)
+        };
+    };
+
+    let lowercase = value.to_lowercase();
+    if lowercase.contains("utf-8") || lowercase.contains("utf8") {
+        Finding::ok(CHECK, format!("{name} is `{value}`"))
+    } else {
+        Finding::warning(
+            CHECK,
+            format!("{name} is `{value}`, which doesn't use UTF-8"),
+            HELP,
+        )
+    }
+}
+
+fn config_file(path: Option<&Path>, error: Option<&LoadError>) -> Finding {
+    const CHECK: &str = "config";
+
+    if let Some(error) = error {
+        return Finding::problem(
+            CHECK,
+            error_chain(error),
+            "fix the config file, or move it away to use the defaults",
+        );
+    }
+    match path {
+        Some(path) if path.exists() => Finding::ok(CHECK, format!("loaded `{}`", path.display())),
+        Some(path) => Finding::ok(
+            CHECK,
+            format!(
+                "using the defaults, since `{}` doesn't exist",
+                path.display()
+            ),
+        ),
+        None => Finding::warning(
+            CHECK,
+            "couldn't find a home directory to look for a config file in",
+            "set `HOME` to your home directory",
+        ),
+    }
+}
+
+fn database(path: Option<&Path>) -> Finding {
+    const CHECK: &str = "database";
+
+    let Some(path) = path else {
+        return Finding::problem(
+            CHECK,
+            "couldn't find a home directory to keep the database in",
+            "set `HOME`, or give a database path with `--database` or in the config file",
+        );
+    };
+    if !path.exists() {
+        return Finding::ok(
+            CHECK,
+            format!("`{}` will be created when you first learn", path.display()),
+        );
+    }
+    if fs::metadata(path).is_ok_and(|metadata| metadata.permissions().readonly()) {
+        return Finding::problem(
+            CHECK,
+            format!("`{}` is read-only", path.display()),
+            "make the file writable so that your progress can be saved",
+        );
+    }
+
+    let database = match Database::open(path) {
+        Ok(database) => database,
+        Err(e) => {
+            let help = revise_database::help(&e)
+                .unwrap_or("check that the file is a `revise` database and can be read");
+            return Finding::problem(CHECK, error_chain(&e), help);
+        }
+    };
+    match database.integrity_check() {
+        Ok(problems) if problems.is_empty() => {
+            Finding::ok(CHECK, format!("`{}` is intact", path.display()))
+        }
+        Ok(problems) => Finding::problem(
+            CHECK,
+            format!("`{}` is corrupt: {}", path.display(), problems.join("; ")),
+            "restore it from a backup, or run `revise db doctor` for details",
+        ),
+        Err(e) => Finding::problem(CHECK, error_chain(&e), "run `revise db doctor` for details"),
+    }
+}
+
+/// Check that each of the sets in the config file can be read and parsed.
+fn sets(sets: &[PathBuf]) -> Vec<Finding> {
+    const CHECK: &str = "sets";
+
+    if sets.is_empty() {
+        return vec![Finding::ok(CHECK, "none are configured")];
+    }
+    sets.iter()
+        .map(|path| {
+            let text = match fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(e) => {
+                    return Finding::problem(
+                        CHECK,
+                        format!("couldn't read `{}`: {e}", path.display()),
+                        "fix the path in the `sets` of the config file",
+                    )
+                }
+            };
+            match revise_parser::parse_set(&text) {
+                Ok(set) => Finding::ok(
+                    CHECK,
+                    format!(
+                        "`{}` has {}",
+                        path.display(),
+                        count(set.cards.len(), "card")
+                    ),
+                ),
+                Err(errors) => Finding::problem(
+                    CHECK,
+                    format!("`{}` has {}", path.display(), count(errors.len(), "error")),
+                    format!("run `revise check {}` to see them", path.display()),
+                ),
+            }
+        })
+        .collect()
+}
+
+fn count(n: usize, noun: &str) -> String {
+    format!("{n} {noun}{}", if n == 1 { "" } else { "s" })
+}
+
+/// An error followed by each of its causes, separated by colons.
+fn error_chain(error: &(dyn Error + 'static)) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message = format!("{message}: {error}");
+        source = error.source();
+    }
+    message
+}
+
+#[test]
+fn test_locale() {
+    let locale = |vars: &[(&str, &str)]| {
+        locale(|name| {
+            let value = vars.iter().find(|(var, _)| *var == name)?.1;
+            Some(value.to_owned())
+        })
+    };
+
+    assert_eq!(locale(&[("LANG", "en_GB.UTF-8")]).status, Status::Ok);
+    assert_eq!(locale(&[("LC_ALL", "C.utf8")]).status, Status::Ok);
+    let finding = locale(&[("LC_ALL", "C"), ("LANG", "en_GB.UTF-8")]);
+    assert_eq!(finding.status, Status::Warning);
+    assert_eq!(finding.message, "LC_ALL is `C`, which doesn't use UTF-8");
+    assert_eq!(
+        locale(&[("LC_ALL", ""), ("LANG", "fr_FR.UTF-8")]).status,
+        Status::Ok
+    );
+}
+
+#[test]
+fn test_database_and_sets() {
+    let dir = std::env::temp_dir().join(format!("revise-doctor-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("data.sqlite3");
+    assert_eq!(database(Some(&path)).status, Status::Ok);
+    Database::open(&path).unwrap();
+    assert_eq!(database(Some(&path)).status, Status::Ok);
+    fs::write(&path, "not a database").unwrap();
+    assert_eq!(database(Some(&path)).status, Status::Problem);
+
+    let good = dir.join("good.set");
+    fs::write(&good, "Title\na - b\n").unwrap();
+    let bad = dir.join("bad.set");
+    fs::write(&bad, "Title\na -\n").unwrap();
+    let findings = sets(&[good, bad, dir.join("missing.set")]);
+    let statuses = findings.iter().map(|f| f.status).collect::<Vec<_>>();
+    assert_eq!(statuses, [Status::Ok, Status::Problem, Status::Problem]);
+    assert!(findings[0].message.ends_with("has 1 card"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
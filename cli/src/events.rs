@@ -0,0 +1,162 @@
+//! Events describing a learning session as it happens, written as JSON lines for programs that
+//! follow along, like stream overlays and tutoring dashboards.
+
+use std::io;
+
+use serde::Serialize;
+
+use revise_engine::{Card, Grade, Question, Statement};
+
+/// Where events are written, if anywhere.
+pub(crate) struct Events {
+    out: Option<Box<dyn io::Write>>,
+}
+
+impl Events {
+    /// Write no events.
+    pub(crate) fn none() -> Self {
+        Self { out: None }
+    }
+
+    /// Write events to the given output, one JSON object per line.
+    pub(crate) fn to(out: impl io::Write + 'static) -> Self {
+        Self {
+            out: Some(Box::new(out)),
+        }
+    }
+
+    /// Write an event and flush it, so that it is seen straight away.
+    pub(crate) fn emit(&mut self, event: &Event<'_>) -> io::Result<()> {
+        let Some(out) = &mut self.out else {
+            return Ok(());
+        };
+        serde_json::to_writer(&mut *out, event)?;
+        writeln!(out)?;
+        out.flush()
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub(crate) enum Event<'a> {
+    /// The session started.
+    Start { title: &'a str },
+    /// A question was asked.
+    Question {
+        prompt: &'a str,
+        /// The right answer: the card's steps in order if it has them, or else its definitions.
+        answer: Vec<&'a str>,
+        /// The number of loaded cards at each knowledge level.
+        levels: &'a [usize],
+    },
+    /// A true or false statement was shown.
+    Statement {
+        prompt: &'a str,
+        /// The answer shown with the prompt, which may belong to another card.
+        shown: Vec<&'a str>,
+        /// Whether the answer shown is the answer to the prompt.
+        matches: bool,
+        levels: &'a [usize],
+    },
+    /// An answer was typed.
+    Answer { answer: &'a str },
+    /// The answer to the question or statement was recorded.
+    Result {
+        correct: bool,
+        /// How well the answer was known, for questions.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        grade: Option<&'static str>,
+    },
+    /// The session ended.
+    End,
+}
+
+impl<'a> Event<'a> {
+    /// The event for a question being asked.
+    pub(crate) fn question(question: &'a Question<'a>) -> Self {
+        Self::Question {
+            prompt: question.prompt,
+            answer: answer(question.card),
+            levels: &question.level_distribution,
+        }
+    }
+
+    /// The event for a statement being shown.
+    pub(crate) fn statement(statement: &'a Statement<'a>) -> Self {
+        Self::Statement {
+            prompt: statement.prompt,
+            shown: answer(statement.shown),
+            matches: statement.is_true(),
+            levels: &statement.level_distribution,
+        }
+    }
+
+    /// The result of a question, from the grade it was given.
+    pub(crate) fn graded(grade: Grade) -> Self {
+        Self::Result {
+            correct: grade != Grade::Again,
+            grade: Some(match grade {
+                Grade::Again => "again",
+                Grade::Hard => "hard",
+                Grade::Good => "good",
+                Grade::Easy => "easy",
+            }),
+        }
+    }
+}
+
+/// The answer to a card: its steps in order if it has them, or else its definitions.
+fn answer(card: &Card) -> Vec<&str> {
+    match &card.steps {
+        Some(steps) => steps.iter().map(String::as_str).collect(),
+        None => card.definitions.iter().map(String::as_str).collect(),
+    }
+}
+
+#[test]
+fn test_events() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+    impl io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let shared = Shared::default();
+    let mut events = Events::to(shared.clone());
+    events.emit(&Event::Start { title: "French" }).unwrap();
+    events
+        .emit(&Event::Question {
+            prompt: "rouge",
+            answer: vec!["red"],
+            levels: &[1, 0],
+        })
+        .unwrap();
+    events.emit(&Event::Answer { answer: "red" }).unwrap();
+    events.emit(&Event::graded(Grade::Hard)).unwrap();
+    events
+        .emit(&Event::Result {
+            correct: false,
+            grade: None,
+        })
+        .unwrap();
+    events.emit(&Event::End).unwrap();
+    Events::none().emit(&Event::End).unwrap();
+
+    assert_eq!(
+        String::from_utf8(shared.0.take()).unwrap(),
+        "{\"event\":\"start\",\"title\":\"French\"}\n\
+        {\"event\":\"question\",\"prompt\":\"rouge\",\"answer\":[\"red\"],\"levels\":[1,0]}\n\
+        {\"event\":\"answer\",\"answer\":\"red\"}\n\
+        {\"event\":\"result\",\"correct\":true,\"grade\":\"hard\"}\n\
+        {\"event\":\"result\",\"correct\":false}\n\
+        {\"event\":\"end\"}\n",
+    );
+}
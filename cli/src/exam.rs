@@ -0,0 +1,150 @@
+use std::io;
+
+use rand::seq::SliceRandom as _;
+
+use revise_engine::{Engine, KnowledgeStore};
+
+use crate::learn::DisplayAnswer;
+
+/// How one question of an exam went.
+struct Marked {
+    prompt: String,
+    answer: String,
+    correct: bool,
+    expected: String,
+}
+
+/// Sit an exam of `questions` questions, reading answers line by line from `input`.
+///
+/// Cards are asked in a random order that doesn't depend on how well they are known, and each is
+/// asked once before any is asked again. Nothing is revealed until the end, when the grade and
+/// the result of each question are printed. Answers are only recorded if `record` is set.
+pub fn exam(
+    engine: &mut Engine<impl KnowledgeStore>,
+    questions: usize,
+    record: bool,
+    mut input: impl io::BufRead,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let mut rng = rand::thread_rng();
+    let mut order = Vec::new();
+    let mut previous = None;
+    let mut marked = Vec::new();
+
+    writeln!(out, "{}", engine.title())?;
+    for number in 1..=questions {
+        if order.is_empty() {
+            order = (0..engine.cards().len()).collect();
+            order.shuffle(&mut rng);
+            // Don't ask the same card twice in a row when starting over.
+            if order.len() > 1 && order.last() == previous.as_ref() {
+                let last = order.len() - 1;
+                order.swap(0, last);
+            }
+        }
+        let card = order.pop().unwrap();
+        previous = Some(card);
+        let question = engine.ask(card)?;
+
+        writeln!(out)?;
+        writeln!(out, "Question {number} of {questions}")?;
+        writeln!(out, "{}", question.prompt)?;
+        if let Some(disambiguation) = question.disambiguation {
+            writeln!(out, "({disambiguation})")?;
+        }
+        if let Some(steps) = &question.steps {
+            writeln!(out, "Put in order: {}", steps.join(", "))?;
+        }
+        write!(out, "Term: ")?;
+        out.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            writeln!(out)?;
+            break;
+        }
+        let answer = answer.trim_end_matches(['\n', '\r']).to_owned();
+
+        let correct = question.is_correct(&answer);
+        marked.push(Marked {
+            prompt: question.prompt.to_owned(),
+            answer,
+            correct,
+            expected: DisplayAnswer(question.card).to_string(),
+        });
+        if record {
+            engine.record_result(correct)?;
+        }
+    }
+
+    writeln!(out)?;
+    write_results(&marked, out)?;
+    Ok(())
+}
+
+fn write_results(marked: &[Marked], mut out: impl io::Write) -> io::Result<()> {
+    if marked.is_empty() {
+        return writeln!(out, "No questions were answered.");
+    }
+
+    let correct = marked.iter().filter(|marked| marked.correct).count();
+    #[allow(clippy::cast_precision_loss)]
+    let percentage = correct as f64 / marked.len() as f64 * 100.0;
+    writeln!(
+        out,
+        "Grade: {percentage:.0}% ({correct} of {} correct)",
+        marked.len(),
+    )?;
+    writeln!(out)?;
+    for marked in marked {
+        if marked.correct {
+            writeln!(out, "  correct    {}: {}", marked.prompt, marked.expected)?;
+        } else {
+            writeln!(
+                out,
+                "  incorrect  {}: {}, not “{}”",
+                marked.prompt, marked.expected, marked.answer,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_exam() {
+    use revise_engine::MemoryStore;
+
+    let set = revise_parser::parse_set("Colours\n\nrouge - red\n").unwrap();
+    let mut engine = Engine::new(MemoryStore::new());
+    engine.load_set(set, false);
+
+    let mut out = Vec::new();
+    exam(
+        &mut engine,
+        3,
+        false,
+        "red\nblue\nred\n".as_bytes(),
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Colours\n\
+        \nQuestion 1 of 3\nrouge\nTerm: \
+        \nQuestion 2 of 3\nrouge\nTerm: \
+        \nQuestion 3 of 3\nrouge\nTerm: \
+        \nGrade: 67% (2 of 3 correct)\n\
+        \n  correct    rouge: red\
+        \n  incorrect  rouge: red, not “blue”\
+        \n  correct    rouge: red\n",
+    );
+    assert_eq!(engine.snapshot().unwrap().level_distribution, [1, 0, 0, 0]);
+
+    exam(&mut engine, 2, true, "red\n".as_bytes(), io::sink()).unwrap();
+    assert_eq!(engine.snapshot().unwrap().level_distribution, [0, 1, 0, 0]);
+
+    let mut out = Vec::new();
+    exam(&mut engine, 2, false, "".as_bytes(), &mut out).unwrap();
+    assert!(String::from_utf8(out)
+        .unwrap()
+        .ends_with("Term: \n\nNo questions were answered.\n"));
+}
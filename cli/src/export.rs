@@ -0,0 +1,215 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::mem;
+
+use revise_parser::{Card, Set, SetMetadata};
+
+use crate::csv;
+
+/// The format to export sets in.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+pub enum Format {
+    /// Moodle's GIFT quiz format.
+    Gift,
+    /// A table with the columns `terms`, `definitions` and `priority`.
+    Csv,
+}
+
+/// Convert the sets to the given format.
+///
+/// Returns a description of each kind of information in the sets that the format can't hold.
+pub fn export(
+    sets: Vec<Set>,
+    format: Format,
+    invert: bool,
+    out: impl io::Write,
+) -> io::Result<Vec<String>> {
+    let mut lost = Vec::new();
+    if sets
+        .iter()
+        .any(|set| set.metadata != SetMetadata::default())
+    {
+        lost.push("set metadata is not exported".to_owned());
+    }
+    if sets
+        .iter()
+        .flat_map(|set| &set.cards)
+        .any(|card| card.table.is_some())
+    {
+        lost.push("tables are not exported, only the answers taken from them".to_owned());
+    }
+
+    let sets = sets
+        .into_iter()
+        .map(|set| {
+            let mut cards = set.cards.into_iter().collect::<Vec<_>>();
+            cards.sort_by_key(|card| card.line);
+            if invert {
+                for card in &mut cards {
+                    mem::swap(&mut card.terms, &mut card.definitions);
+                    card.steps = None;
+                    card.table = None;
+                }
+            }
+            (set.title, cards)
+        })
+        .collect::<Vec<_>>();
+
+    if sets
+        .iter()
+        .flat_map(|(_, cards)| cards)
+        .any(|card| card.steps.is_some())
+    {
+        lost.push(
+            "the order of steps is not exported, so they are accepted in any order".to_owned(),
+        );
+    }
+
+    match format {
+        Format::Gift => {
+            let cards = sets.iter().flat_map(|(_, cards)| cards);
+            if cards.clone().any(|card| card.priority != 1) {
+                lost.push("GIFT has no card priorities".to_owned());
+            }
+            gift(&sets, out)?;
+        }
+        Format::Csv => {
+            if sets.len() > 1 {
+                lost.push("the sets are merged into one table without their titles".to_owned());
+            }
+            self::csv(&sets, out)?;
+        }
+    }
+
+    Ok(lost)
+}
+
+/// Write the sets as GIFT short answer questions, one category per set. Every spelling of every
+/// definition is accepted as an answer.
+///
+/// Moodle reads the rest of a `$CATEGORY:` line as the category name without unescaping it, so
+/// titles are written as they are, with only newlines taken out.
+fn gift(sets: &[(String, Vec<Card>)], mut out: impl io::Write) -> io::Result<()> {
+    for (title, cards) in sets {
+        writeln!(out, "$CATEGORY: {}", title.replace(['\r', '\n'], ""))?;
+        writeln!(out)?;
+
+        for card in cards {
+            writeln!(out, "{}", gift_question(card))?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the cards of the sets as CSV. Terms and definitions are written as they would be in a set
+/// file, which can be read back by [`revise_parser::parse_guess_synonyms`].
+fn csv(sets: &[(String, Vec<Card>)], mut out: impl io::Write) -> io::Result<()> {
+    csv::write_row(&mut out, ["terms", "definitions", "priority"])?;
+    for card in sets.iter().flat_map(|(_, cards)| cards) {
+        csv::write_row(
+            &mut out,
+            [
+                &*revise_parser::format_card_options(card, &card.terms).unwrap(),
+                &*revise_parser::format_card_options(card, &card.definitions).unwrap(),
+                &*card.priority.to_string(),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn gift_question(card: &Card) -> String {
+    let answers = card
+        .definitions
+        .iter()
+        .flat_map(|definition| card.spellings(definition))
+        .map(|spelling| format!("={}", gift_escape(spelling)));
+    format!(
+        "{} {{{}}}",
+        gift_escape(&join(&card.terms)),
+        answers.collect::<Vec<_>>().join(" "),
+    )
+}
+
+fn join(set: &BTreeSet<String>) -> String {
+    set.iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Escape the characters that have a special meaning in GIFT.
+fn gift_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '~' | '=' | '#' | '{' | '}' | ':' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[test]
+fn test_gift() {
+    let source = "Maths: basics\n\ntwo, 2 - deux\nx = 1 - \"a{b}\", c\n";
+    let set = || revise_parser::parse_set(source).unwrap();
+    let mut out = Vec::new();
+    assert!(export(vec![set()], Format::Gift, false, &mut out)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "$CATEGORY: Maths: basics\n\
+        \n\
+        2, two {=deux}\n\
+        \n\
+        x \\= 1 {=a\\{b\\} =c}\n\
+        \n",
+    );
+
+    let mut out = Vec::new();
+    export(vec![set()], Format::Gift, true, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("\ndeux {=2 =two}\n"), "{out}");
+
+    let set = revise_parser::parse_set("T\n\nhue - colour / color, tint\n").unwrap();
+    let mut out = Vec::new();
+    export(vec![set], Format::Gift, false, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("\nhue {=colour =color =tint}\n"), "{out}");
+
+    let mut out = Vec::new();
+    gift(&[("A {set}\r\n".to_owned(), Vec::new())], &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "$CATEGORY: A {set}\n\n");
+}
+
+#[test]
+fn test_table() {
+    let set = revise_parser::parse_set("T\n\nêtre -| \"je | suis; tu | es\"\n").unwrap();
+    let mut out = Vec::new();
+    let lost = export(vec![set], Format::Csv, false, &mut out).unwrap();
+    assert_eq!(
+        lost,
+        ["tables are not exported, only the answers taken from them"]
+    );
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "terms,definitions,priority\nêtre,\"je suis, tu es\",1\n"
+    );
+}
+
+#[test]
+fn test_csv() {
+    let set = revise_parser::parse_set("T\n#! author: me\n\nx, \"y, z\" - a !2\nb - c\n").unwrap();
+    let mut out = Vec::new();
+    let lost = export(vec![set], Format::Csv, false, &mut out).unwrap();
+    assert_eq!(lost, ["set metadata is not exported"]);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "terms,definitions,priority\n\
+        \"x, \"\"y, z\"\"\",a,2\n\
+        b,c,1\n"
+    );
+}
@@ -0,0 +1,253 @@
+use std::collections::HashSet;
+
+use revise_parser::{Card, SetMetadata};
+
+use crate::csv;
+
+/// The format to import a set from.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+pub enum Format {
+    /// A table with the columns `terms`, `definitions` and optionally `priority`, as written by
+    /// `revise export --format csv`.
+    Csv,
+}
+
+/// A set converted from another format.
+#[derive(Debug)]
+pub struct Imported {
+    /// The source of the set file.
+    pub source: String,
+    /// Descriptions of the parts of the input that were changed or left out.
+    pub warnings: Vec<String>,
+}
+
+pub fn import(text: &str, format: Format, title: &str) -> anyhow::Result<Imported> {
+    let mut warnings = Vec::new();
+
+    let cards = match format {
+        Format::Csv => csv_cards(text, &mut warnings)?,
+    };
+
+    let clean_title = sanitize(title).replace('#', "");
+    let clean_title = match clean_title.trim() {
+        "" => "Imported set",
+        trimmed => trimmed,
+    };
+    if clean_title != title {
+        warnings.push(format!("the title was changed to `{clean_title}`"));
+    }
+
+    let source = revise_parser::format_set(clean_title, &SetMetadata::default(), &cards).unwrap();
+    Ok(Imported { source, warnings })
+}
+
+/// Read the cards from CSV. Each row is numbered from 1 after the header.
+fn csv_cards(text: &str, warnings: &mut Vec<String>) -> anyhow::Result<Vec<Card>> {
+    let mut rows = csv::parse(text)?.into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("the CSV file is empty"))?;
+
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| column.trim().eq_ignore_ascii_case(name))
+    };
+    let terms_column =
+        column("terms").ok_or_else(|| anyhow::anyhow!("the CSV file has no `terms` column"))?;
+    let definitions_column = column("definitions")
+        .ok_or_else(|| anyhow::anyhow!("the CSV file has no `definitions` column"))?;
+    let priority_column = column("priority");
+
+    for (i, name) in header.iter().enumerate() {
+        if ![
+            Some(terms_column),
+            Some(definitions_column),
+            priority_column,
+        ]
+        .contains(&Some(i))
+        {
+            warnings.push(format!("the column `{name}` is not imported"));
+        }
+    }
+
+    let mut cards = Vec::new();
+    let mut seen = HashSet::new();
+    for (i, row) in rows.enumerate() {
+        let row_number = i + 1;
+        let cell = |column: usize| row.get(column).map_or("", String::as_str);
+
+        let mut options = |column: usize| {
+            let original = cell(column);
+            let sanitized = sanitize(original);
+            if sanitized != original {
+                warnings.push(format!(
+                    "row {row_number}: control characters were replaced with spaces"
+                ));
+            }
+            revise_parser::parse_guess_synonyms(&sanitized)
+        };
+        let (terms, mut synonyms) = options(terms_column);
+        let (definitions, definition_synonyms) = options(definitions_column);
+        synonyms.extend(definition_synonyms);
+
+        if terms.is_empty() || definitions.is_empty() {
+            warnings.push(format!(
+                "row {row_number} is skipped because it has no {}",
+                if terms.is_empty() {
+                    "terms"
+                } else {
+                    "definitions"
+                },
+            ));
+            continue;
+        }
+
+        let priority = match priority_column.map(cell).map(str::trim) {
+            None | Some("") => 1,
+            Some(priority) => match priority.parse::<u32>() {
+                Ok(priority) if priority > 0 => priority,
+                _ => anyhow::bail!("row {row_number} has an invalid priority `{priority}`"),
+            },
+        };
+
+        if !seen.insert((terms.clone(), definitions.clone())) {
+            warnings.push(format!(
+                "row {row_number} is skipped because it is a duplicate"
+            ));
+            continue;
+        }
+
+        cards.push(Card {
+            terms,
+            definitions,
+            synonyms,
+            steps: None,
+            table: None,
+            priority,
+            line: row_number,
+        });
+    }
+
+    Ok(cards)
+}
+
+/// Replace control characters, which can't appear in set files, with spaces.
+fn sanitize(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+#[test]
+fn test_csv() {
+    let imported = import(
+        "Terms,definitions,notes\n\
+        \"a, \"\"b, c\"\"\",d,x\n\
+        e,\n\
+        \"f\tg\",h\n\
+        \"a, \"\"b, c\"\"\",d,y\n",
+        Format::Csv,
+        "Title #1",
+    )
+    .unwrap();
+    assert_eq!(imported.source, "Title 1\n\na, \"b, c\" - d\nf g - h\n");
+    assert_eq!(
+        imported.warnings,
+        [
+            "the column `notes` is not imported",
+            "row 2 is skipped because it has no definitions",
+            "row 3: control characters were replaced with spaces",
+            "row 4 is skipped because it is a duplicate",
+            "the title was changed to `Title 1`",
+        ]
+    );
+
+    assert!(import("terms,definitions,priority\na,b,0\n", Format::Csv, "T").is_err());
+    assert!(import("terms\na\n", Format::Csv, "T").is_err());
+    assert!(import("", Format::Csv, "T").is_err());
+}
+
+#[cfg(test)]
+mod round_trip {
+    use std::collections::{BTreeSet, HashSet};
+
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom as _;
+    use rand::{Rng as _, SeedableRng as _};
+    use revise_parser::{Card, SetMetadata};
+
+    use super::{import, Format};
+
+    const ALPHABET: &[char] = &[
+        'a', 'b', 'Z', 'é', '7', ' ', ' ', '\u{a0}', ',', '-', '"', '\\', '#', '!', '\'', '(', ':',
+        '/', ';',
+    ];
+
+    fn option(rng: &mut StdRng) -> String {
+        loop {
+            let len = rng.gen_range(1..8);
+            let option = (0..len)
+                .map(|_| *ALPHABET.choose(rng).unwrap())
+                .collect::<String>();
+            if revise_parser::is_valid_option(&option) {
+                return option;
+            }
+        }
+    }
+
+    fn options(rng: &mut StdRng) -> BTreeSet<String> {
+        (0..rng.gen_range(1..4)).map(|_| option(rng)).collect()
+    }
+
+    /// Every set of the cards CSV can hold survives being exported and imported again.
+    #[test]
+    fn sets() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let mut cards = Vec::<Card>::new();
+            let mut keys = HashSet::new();
+            for _ in 0..rng.gen_range(1..10) {
+                let mut definitions = options(&mut rng);
+                let mut synonyms = BTreeSet::new();
+                if rng.gen_bool(0.2) {
+                    let option = format!("{}/Zé b", rng.gen_range(0..100));
+                    definitions.insert(option.clone());
+                    synonyms.insert(option);
+                }
+                let terms = options(&mut rng);
+                if !keys.insert((terms.clone(), definitions.clone())) {
+                    continue;
+                }
+                cards.push(Card {
+                    terms,
+                    definitions,
+                    synonyms,
+                    steps: None,
+                    table: None,
+                    priority: *[1, 1, 2, 10].choose(&mut rng).unwrap(),
+                    line: cards.len() + 3,
+                });
+            }
+
+            let source =
+                revise_parser::format_set("Words", &SetMetadata::default(), &cards).unwrap();
+            let set = revise_parser::parse_set(&source).unwrap();
+
+            let mut csv = Vec::new();
+            let lost =
+                crate::export::export(vec![set], crate::export::Format::Csv, false, &mut csv)
+                    .unwrap();
+            assert!(lost.is_empty(), "{source}\n{lost:?}");
+
+            let csv = std::str::from_utf8(&csv).unwrap();
+            let imported = import(csv, Format::Csv, "Words").unwrap();
+            assert!(
+                imported.warnings.is_empty(),
+                "{csv}\n{:?}",
+                imported.warnings
+            );
+            assert_eq!(imported.source, source, "{csv}");
+        }
+    }
+}
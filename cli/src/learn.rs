@@ -1,344 +1,1246 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::BTreeSet;
 use std::fmt::{self, Display, Formatter};
 use std::io;
-use std::marker::PhantomData;
 use std::panic;
 
 use crossterm::{
     cursor,
     event::KeyCode,
     execute, queue,
-    style::{style, Stylize},
+    style::{style, Color, Stylize},
     terminal::{self, ClearType},
 };
 
-use rand::distributions::Distribution as _;
-use rand::seq::IteratorRandom as _;
-use rand::Rng;
+use unicode_width::UnicodeWidthStr as _;
 
-use revise_database::{CardKey, Database};
-use revise_parser::Card;
+use revise_engine::{Card, Engine, FlushError, Grade, KnowledgeStore, Question, Statement};
+use revise_parser::Table;
 
+use crate::config::Config;
+use crate::events::{Event, Events};
+use crate::speech::Speaker;
+use crate::ui::{Input, Keys, Requests, COMPACT_WIDTH};
+
+/// How questions are answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum Mode {
+    /// Type out the answer, which is checked.
+    Typed,
+    /// Reveal the answer and grade how well you knew it yourself.
+    Flashcard,
+    /// Say whether the answer shown with each term is right, which counts for less than the other
+    /// modes.
+    TrueFalse,
+}
+
+/// The number of questions in each round when no round size is configured.
+pub const DEFAULT_ROUND_SIZE: usize = 7;
+
+/// The number of hints that can be asked for in each question.
+const MAX_HINTS: u8 = 2;
+
+/// The number of answers recorded between saving them to the store. Answers are also saved when
+/// learning ends, so at most this many are lost if the process is killed.
+const FLUSH_INTERVAL: usize = 20;
+
+/// Count an answer that has just been recorded, saving the answers so far every
+/// [`FLUSH_INTERVAL`] answers.
+fn answered(
+    engine: &mut Engine<impl KnowledgeStore>,
+    answers: &mut usize,
+) -> Result<(), FlushError> {
+    *answers += 1;
+    if answers.is_multiple_of(FLUSH_INTERVAL) {
+        engine.store_mut().flush()?;
+    }
+    Ok(())
+}
+
+/// What is drawn at the top of the screen above each question.
+struct Heading<'a> {
+    title: &'a str,
+    config: &'a Config,
+    simple: bool,
+    /// How far through the current round the session is, if it is split into rounds.
+    progress: Option<Progress>,
+}
+
+/// How far through a round the session is.
+#[derive(Debug, Clone, Copy)]
+struct Progress {
+    round: usize,
+    answered: usize,
+    size: usize,
+}
+
+/// A question answered during a round, for the summary at the end of it.
+struct Answered {
+    prompt: String,
+    correct: bool,
+}
+
+/// Learn in the terminal. In simple mode, output scrolls instead of using the alternate screen and
+/// only ASCII is drawn, for terminals that support neither.
+///
+/// Questions are asked in rounds of `round_size`, each followed by a summary of how it went, or
+/// one after another without end if it is 0. Every question of a round is chosen in the usual
+/// weighted way.
+///
+/// Narrow terminals get a compact layout with wrapped text, and default to flashcards so that
+/// nothing has to be typed.
+#[allow(clippy::too_many_arguments)]
 pub fn learn(
-    database: &mut Database,
-    title: &str,
-    cards: &HashMap<CardKey, Card>,
-    knowledge_weights: [f64; 4],
+    engine: &mut Engine<impl KnowledgeStore>,
+    config: &Config,
+    mode: Option<Mode>,
+    simple: bool,
+    speak: bool,
+    round_size: usize,
     mut out: impl io::Write,
+    events: &mut Events,
 ) -> anyhow::Result<()> {
-    let mut rng = rand::thread_rng();
+    let mut speaker = (config.text_to_speech.as_ref())
+        .filter(|_| speak)
+        .map(Speaker::new);
+    let _raw_guard = enter_raw(simple)?;
+
+    let title = engine.title().to_owned();
+    events.emit(&Event::Start { title: &title })?;
+    let mode = mode.unwrap_or(if terminal::size()?.0 < COMPACT_WIDTH {
+        Mode::Flashcard
+    } else {
+        Mode::Typed
+    });
+    let mut answers = 0;
+    let mut round = 1;
+    let mut results = Vec::new();
+
+    loop {
+        let heading = Heading {
+            title: &title,
+            config,
+            simple,
+            progress: (round_size != 0).then_some(Progress {
+                round,
+                answered: results.len(),
+                size: round_size,
+            }),
+        };
+        let result = if mode == Mode::TrueFalse {
+            statement(&mut out, engine, &heading, speaker.as_mut(), events)?
+        } else {
+            question(&mut out, engine, mode, &heading, speaker.as_mut(), events)?
+        };
+        let Some(result) = result else {
+            break;
+        };
+        answered(engine, &mut answers)?;
+        results.push(result);
+
+        // Recognising statements never changes levels, so it can't complete the session.
+        let complete = mode != Mode::TrueFalse
+            && engine.mastery().is_some()
+            && engine.snapshot()?.is_complete();
+        if round_size != 0 && (results.len() == round_size || complete) {
+            if !round_summary(&mut out, &heading, round, &results, complete)? {
+                break;
+            }
+            round += 1;
+            results.clear();
+        }
+        if complete {
+            break;
+        }
+    }
+
+    events.emit(&Event::End)?;
+    Ok(())
+}
+
+/// Ask the next question as a true or false statement and record the answer. Returns `None` if
+/// the user quit.
+fn statement(
+    mut out: impl io::Write,
+    engine: &mut Engine<impl KnowledgeStore>,
+    heading: &Heading<'_>,
+    speaker: Option<&mut Speaker<'_>>,
+    events: &mut Events,
+) -> anyhow::Result<Option<Answered>> {
+    let statement = engine.next_statement()?;
+    events.emit(&Event::statement(&statement))?;
+    let width = header(
+        &mut out,
+        heading,
+        &statement.level_distribution,
+        statement.at_risk,
+    )?;
+    write!(out, "{}\r\n\r\n", wrapped(statement.prompt, width))?;
+    if let Some(speaker) = speaker {
+        speaker.speak(statement.prompt, statement.prompt_language)?;
+    }
+    let prompt = statement.prompt.to_owned();
+    let Some(correct) = true_false(&mut out, &statement, heading.config, width)? else {
+        return Ok(None);
+    };
+    engine.record_recognised(correct)?;
+    events.emit(&Event::Result {
+        correct,
+        grade: None,
+    })?;
+    Ok(Some(Answered { prompt, correct }))
+}
 
-    let _raw_guard = enter_raw()?;
+/// Ask the next question in the given mode and record the answer. Returns `None` if the user
+/// quit.
+fn question(
+    mut out: impl io::Write,
+    engine: &mut Engine<impl KnowledgeStore>,
+    mode: Mode,
+    heading: &Heading<'_>,
+    speaker: Option<&mut Speaker<'_>>,
+    events: &mut Events,
+) -> anyhow::Result<Option<Answered>> {
+    let Heading { config, simple, .. } = *heading;
+    let question = engine.next_question()?;
+    events.emit(&Event::question(&question))?;
+    let card = question.card;
+    let width = header(
+        &mut out,
+        heading,
+        &question.level_distribution,
+        question.at_risk,
+    )?;
 
-    let mut session = Session::new();
+    write!(out, "{}\r\n", wrapped(question.prompt, width))?;
+    if let Some(disambiguation) = question.disambiguation {
+        write!(out, "{}\r\n", format!("({disambiguation})").dim())?;
+    }
+    write!(out, "\r\n")?;
+    if let Some(speaker) = speaker {
+        speaker.speak(question.prompt, question.prompt_language)?;
+    }
 
+    let grade = match mode {
+        Mode::Typed => typed(&mut out, &question, config, simple, width, events)?,
+        Mode::Flashcard => flashcard(&mut out, card, config, width)?,
+        Mode::TrueFalse => unreachable!(),
+    };
+    let prompt = question.prompt.to_owned();
+    let Some(grade) = grade else {
+        return Ok(None);
+    };
+    engine.record_grade(grade)?;
+    events.emit(&Event::graded(grade))?;
+    Ok(Some(Answered {
+        prompt,
+        correct: grade != Grade::Again,
+    }))
+}
+
+/// Show how a round went, with whether each question in it was answered correctly, and wait for
+/// the user to go on. Returns whether they chose to go on rather than quit.
+fn round_summary(
+    mut out: impl io::Write,
+    heading: &Heading<'_>,
+    round: usize,
+    results: &[Answered],
+    complete: bool,
+) -> io::Result<bool> {
+    let Heading { config, simple, .. } = *heading;
+    let Config { theme, keys, .. } = config;
+    let width = clear(&mut out, heading)?;
+
+    let correct = results.iter().filter(|result| result.correct).count();
+    let message = format!(
+        "Round {round} complete: {correct} of {} correct",
+        results.len(),
+    );
+    write!(out, "{}\r\n", wrapped(&message, width).bold())?;
+    separator(&mut out, simple, width)?;
+    for result in results {
+        let mark = match (result.correct, simple) {
+            (true, false) => "✓".with(theme.correct),
+            (false, false) => "✗".with(theme.incorrect),
+            (true, true) => "+".with(theme.correct),
+            (false, true) => "x".with(theme.incorrect),
+        };
+        write!(out, " {mark} {}\r\n", result.prompt)?;
+    }
+
+    let next = if complete {
+        "finish"
+    } else {
+        "start the next round"
+    };
+    let message = format!("Press {} to {next}", keys.submit);
+    write!(out, "\r\n{}", wrapped(&message, width).dim())?;
+    out.flush()?;
     loop {
-        let question =
-            session.generate_question(database, cards.keys(), knowledge_weights, &mut rng)?;
-        let card = &cards[question.card_key()];
+        let Some(key) = crate::ui::read_key(keys)? else {
+            return Ok(false);
+        };
+        if keys.submit.matches(&key) {
+            return Ok(true);
+        }
+    }
+}
+
+/// Clear the screen and draw the title. Returns the width to wrap text to, if the compact layout
+/// is in use.
+fn clear(mut out: impl io::Write, heading: &Heading<'_>) -> io::Result<Option<usize>> {
+    let columns = terminal::size()?.0;
+    let width = (columns < COMPACT_WIDTH).then_some(usize::from(columns));
 
+    if heading.simple {
+        write!(out, "\r\n")?;
+    } else {
         queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
-        write!(out, "{}\r\n", title.bold())?;
+    }
+    write!(out, "{}\r\n", wrapped(heading.title, width).bold())?;
+    Ok(width)
+}
 
-        let distribution = question.level_distribution();
+/// Draw a separator across the terminal.
+fn separator(mut out: impl io::Write, simple: bool, width: Option<usize>) -> io::Result<()> {
+    let separator = match (simple, width.is_some()) {
+        (true, false) => "-",
+        (true, true) => "=",
+        (false, false) => "─",
+        (false, true) => "━",
+    }
+    .dim();
+    for _ in 0..terminal::size()?.0 {
+        write!(out, "{separator}")?;
+    }
+    write!(out, "\r\n\r\n")?;
+    if width.is_some() {
+        write!(out, "\r\n")?;
+    }
+    Ok(())
+}
+
+/// Clear the screen and draw the title, the number of cards at each level, the progress through
+/// the round and a separator. Returns the width to wrap text to, if the compact layout is in use.
+fn header(
+    mut out: impl io::Write,
+    heading: &Heading<'_>,
+    distribution: &[usize],
+    at_risk: usize,
+) -> io::Result<Option<usize>> {
+    let theme = heading.config.theme;
+    let width = clear(&mut out, heading)?;
+
+    let (&first, rest) = distribution.split_first().unwrap();
+    let (&last, middle) = rest.split_last().unwrap();
+    write!(out, "{}", style(first).with(theme.unknown))?;
+    for count in middle {
+        write!(out, " {count}")?;
+    }
+    write!(out, " {}", style(last).with(theme.learnt))?;
+    if at_risk != 0 {
         write!(
             out,
-            "{} {} {} {}\r\n",
-            style(distribution[0]).dark_red(),
-            distribution[1],
-            distribution[2],
-            style(distribution[3]).dark_green(),
+            "  {}",
+            format!("{at_risk} at risk").with(theme.at_risk),
         )?;
-        let separator = "─".dim();
-        for _ in 0..terminal::size()?.0 {
-            write!(out, "{separator}")?;
+    }
+    write!(out, "\r\n")?;
+    if let Some(progress) = heading.progress {
+        write!(out, "{}\r\n", progress_bar(progress, heading.simple))?;
+    }
+    separator(&mut out, heading.simple, width)?;
+
+    Ok(width)
+}
+
+/// The round number, a bar filled in as far as the round has got and how many of its questions
+/// have been answered, like `Round 2 ███░░░░ 3/7`.
+fn progress_bar(progress: Progress, simple: bool) -> String {
+    let (done, left) = if simple { ('#', '.') } else { ('█', '░') };
+    let Progress {
+        round,
+        answered,
+        size,
+    } = progress;
+    let bar = std::iter::repeat_n(done, answered)
+        .chain(std::iter::repeat_n(left, size - answered))
+        .collect::<String>();
+    format!("Round {round} {bar} {answered}/{size}")
+}
+
+#[test]
+fn test_progress_bar() {
+    let progress = Progress {
+        round: 2,
+        answered: 3,
+        size: 7,
+    };
+    assert_eq!(progress_bar(progress, false), "Round 2 ███░░░░ 3/7");
+    assert_eq!(progress_bar(progress, true), "Round 2 ###.... 3/7");
+}
+
+/// Show the answer of a statement and ask whether it is right with a single key. Returns whether
+/// the user was correct.
+fn true_false(
+    mut out: impl io::Write,
+    statement: &Statement<'_>,
+    config: &Config,
+    width: Option<usize>,
+) -> io::Result<Option<bool>> {
+    let Config { theme, keys, .. } = config;
+
+    write!(
+        out,
+        "{}{}\r\n\r\n",
+        "Answer: ".dim(),
+        wrapped(&DisplayAnswer(statement.shown).to_string(), width),
+    )?;
+    let message = format!(
+        "Is it right? {} for true, {} for false",
+        keys.answer_true, keys.answer_false,
+    );
+    write!(out, "{}", wrapped(&message, width).dim())?;
+    out.flush()?;
+    let said_true = loop {
+        let Some(key) = crate::ui::read_key(keys)? else {
+            return Ok(None);
+        };
+        if keys.answer_true.matches(&key) {
+            break true;
         }
-        write!(out, "\r\n\r\n")?;
+        if keys.answer_false.matches(&key) {
+            break false;
+        }
+    };
 
+    let correct = said_true == statement.is_true();
+    if !correct {
+        write!(out, "\r\n\r\n")?;
+        write!(out, " {}\r\n\r\n", "Incorrect".with(theme.incorrect).bold())?;
         write!(
             out,
-            "{}\r\n\r\n",
-            card.terms.iter().choose(&mut rng).unwrap()
+            "{}{}\r\n\r\n",
+            "Answer: ".dim(),
+            style(wrapped(&DisplayAnswer(statement.card).to_string(), width)).with(theme.correct),
         )?;
+        write!(out, "{}", "Press any key to continue".dim())?;
+        out.flush()?;
+        if crate::ui::read_key(keys)?.is_none() {
+            return Ok(None);
+        }
+    }
+    Ok(Some(correct))
+}
 
+/// Ask for the answer to be typed, and grade it.
+fn typed(
+    mut out: impl io::Write,
+    question: &Question<'_>,
+    config: &Config,
+    simple: bool,
+    width: Option<usize>,
+    events: &mut Events,
+) -> io::Result<Option<Grade>> {
+    let Config { theme, keys, .. } = config;
+    let card = question.card;
+
+    let answer = if let Some(steps) = &question.steps {
+        arrange(&mut out, steps, keys, width)?.map(|answer| (answer, 0))
+    } else {
         write!(out, "{}", "Term: ".dim())?;
         out.flush()?;
-        let answer = match crate::ui::read_line(&mut out)? {
-            Some(line) => revise_parser::parse_guess(&line),
-            None => break,
+        read_answer(&mut out, config, simple, card)?
+    };
+    let Some((mut answer, hints)) = answer else {
+        return Ok(None);
+    };
+
+    // An answer that is right as far as it goes can be finished off for partial credit.
+    let mut partial = false;
+    while let Some(missing) = question.missing(&answer) {
+        partial = true;
+        write!(out, "\r\n\r\n{}", partly_right(missing.len()).dim())?;
+        out.flush()?;
+        match crate::ui::read_line(&mut out, keys, simple)? {
+            Some(more) if !more.trim().is_empty() => answer = format!("{answer}, {more}"),
+            Some(_) => break,
+            None => return Ok(None),
+        }
+    }
+    events.emit(&Event::Answer { answer: &answer })?;
+
+    let correct = if question.is_correct(&answer) {
+        true
+    } else {
+        write!(out, "\r\n\r\n")?;
+        write!(out, " {}\r\n\r\n", "Incorrect".with(theme.incorrect).bold())?;
+        write_answer(&mut out, card, config, width)?;
+        write!(out, "\r\n\r\n")?;
+        let message = format!(
+            "Press {} to override as correct, or any other key to continue: ",
+            keys.override_correct,
+        );
+        write!(out, "{}", wrapped(&message, width))?;
+        out.flush()?;
+
+        let Some(key) = crate::ui::read_key(keys)? else {
+            return Ok(None);
         };
 
-        let correct = if card.definitions == answer {
+        if keys.override_correct.matches(&key) {
             true
         } else {
-            write!(out, "\r\n\r\n")?;
-            write!(out, " {}\r\n\r\n", "Incorrect".dark_red().bold())?;
-            write!(
-                out,
-                "{}{}\r\n\r\n",
-                "Answer: ".dim(),
-                style(DisplayAnswer(&card.definitions)).dark_green(),
-            )?;
-            write!(out, "Override (c)orrect or continue: ")?;
-            out.flush()?;
+            writeln!(out, "\r\n")?;
+            loop {
+                write!(
+                    out,
+                    "\r{}{}",
+                    terminal::Clear(ClearType::UntilNewLine),
+                    "Type it out: ".dim(),
+                )?;
+                out.flush()?;
+                match crate::ui::read_line(&mut out, keys, simple)? {
+                    Some(line) if is_exact(card, &line) => break,
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+            false
+        }
+    };
 
-            let Some(key) = crate::ui::read_key()? else {
-                break;
-            };
+    Ok(Some(match (correct, hints, partial) {
+        (false, _, _) => Grade::Again,
+        (true, 0, false) => Grade::Good,
+        (true, _, _) => Grade::Hard,
+    }))
+}
 
-            if key.code == KeyCode::Char('c') {
-                true
+/// The prompt for the rest of an answer that is right but incomplete.
+fn partly_right(missing: usize) -> String {
+    let definitions = if missing == 1 {
+        "definition"
+    } else {
+        "definitions"
+    };
+    format!("Partly right, {missing} more {definitions}: ")
+}
+
+/// Reveal the answer when asked to, and let the user grade how well they knew it.
+fn flashcard(
+    mut out: impl io::Write,
+    card: &Card,
+    config: &Config,
+    width: Option<usize>,
+) -> io::Result<Option<Grade>> {
+    let Config { keys, .. } = config;
+
+    write!(
+        out,
+        "{}",
+        format!("Press {} to show the answer", keys.reveal).dim()
+    )?;
+    out.flush()?;
+    loop {
+        let Some(key) = crate::ui::read_key(keys)? else {
+            return Ok(None);
+        };
+        if keys.reveal.matches(&key) {
+            break;
+        }
+    }
+
+    write!(out, "\r{}", terminal::Clear(ClearType::UntilNewLine))?;
+    write_answer(&mut out, card, config, width)?;
+    write!(out, "\r\n\r\n{}", wrapped(GRADE_PROMPT, width).dim())?;
+    out.flush()?;
+    loop {
+        let Some(key) = crate::ui::read_key(keys)? else {
+            return Ok(None);
+        };
+        if let KeyCode::Char(c) = key.code {
+            if let Some(grade) = parse_grade(&c.to_string()) {
+                return Ok(Some(grade));
+            }
+        }
+    }
+}
+
+/// Let the user put the shuffled steps of a card in order, choosing a step with the arrow keys and
+/// moving it with the move keys. Returns the steps in the chosen order, written as an answer.
+fn arrange(
+    mut out: impl io::Write,
+    steps: &[&str],
+    keys: &Keys,
+    width: Option<usize>,
+) -> io::Result<Option<String>> {
+    let mut steps = steps.to_vec();
+    let mut selected = 0;
+    let message = format!(
+        "Put the steps in order: up and down choose a step, {} and {} move it, {} finishes",
+        keys.move_up, keys.move_down, keys.submit,
+    );
+    write!(out, "{}\r\n\r\n", wrapped(&message, width).dim())?;
+
+    loop {
+        let columns = usize::from(terminal::size()?.0.max(1));
+        let mut rows = 0;
+        for (i, step) in steps.iter().enumerate() {
+            let line = format!("{}. {step}", i + 1);
+            let line = wrapped(&line, width);
+            rows += line
+                .split("\r\n")
+                .map(|line| line.width().div_ceil(columns).max(1))
+                .sum::<usize>();
+            if i == selected {
+                write!(out, "{}\r\n", line.bold())?;
             } else {
-                writeln!(out, "\r\n")?;
-                loop {
-                    write!(
-                        out,
-                        "\r{}{}",
-                        terminal::Clear(ClearType::UntilNewLine),
-                        "Type it out: ".dim(),
-                    )?;
-                    out.flush()?;
-                    let answer = match crate::ui::read_line(&mut out)? {
-                        Some(line) => revise_parser::parse_guess(&line),
-                        None => break,
-                    };
-
-                    if card.definitions == answer {
-                        break;
-                    }
-                }
-                false
+                write!(out, "{}\r\n", line.dim())?;
             }
-        };
+        }
+        out.flush()?;
 
-        question.record_result(correct)?;
+        let Some(key) = crate::ui::read_key(keys)? else {
+            return Ok(None);
+        };
+        if keys.submit.matches(&key) {
+            break;
+        }
+        if keys.move_up.matches(&key) && selected > 0 {
+            steps.swap(selected, selected - 1);
+            selected -= 1;
+        } else if keys.move_down.matches(&key) && selected + 1 < steps.len() {
+            steps.swap(selected, selected + 1);
+            selected += 1;
+        } else if key.code == KeyCode::Up {
+            selected = selected.saturating_sub(1);
+        } else if key.code == KeyCode::Down {
+            selected = (selected + 1).min(steps.len() - 1);
+        }
+        queue!(
+            out,
+            cursor::MoveToPreviousLine(u16::try_from(rows).unwrap_or(u16::MAX)),
+            terminal::Clear(ClearType::FromCursorDown),
+        )?;
     }
 
-    Ok(())
+    Ok(Some(revise_parser::format_options(&steps).unwrap()))
 }
 
-fn enter_raw() -> io::Result<impl Drop> {
-    fn exit() {
-        drop(execute!(io::stdout(), terminal::LeaveAlternateScreen));
-        drop(terminal::disable_raw_mode());
+/// Whether a typed answer is exactly the answer to a card, with any steps in order. Any one
+/// spelling of a definition with synonyms is exact.
+fn is_exact(card: &Card, answer: &str) -> bool {
+    if let Some(steps) = &card.steps {
+        return revise_parser::parse_guess_steps(answer) == *steps;
     }
+    let guess = revise_parser::parse_guess(answer);
+    guess.len() == card.definitions.len()
+        && card.definitions.iter().all(|definition| {
+            guess.contains(definition)
+                || (card.spellings(definition).iter()).any(|spelling| guess.contains(*spelling))
+        })
+}
 
-    execute!(
-        io::stdout(),
-        terminal::EnterAlternateScreen,
-        terminal::Clear(ClearType::All)
-    )?;
-    terminal::enable_raw_mode()?;
+/// Write the answer to a card after `Answer: `. If the card has a table, it is written underneath
+/// with its columns lined up: in the colour of answers if the whole table is the answer, or else
+/// as context for the marked cell.
+fn write_answer(
+    mut out: impl io::Write,
+    card: &Card,
+    config: &Config,
+    width: Option<usize>,
+) -> io::Result<()> {
+    let theme = config.theme;
+    let answer = style(wrapped(&DisplayAnswer(card).to_string(), width)).with(theme.correct);
+    let Some(table) = &card.table else {
+        return write!(out, "{}{answer}", "Answer: ".dim());
+    };
+    let colour = if table.answer.is_some() {
+        write!(out, "{}{answer}\r\n", "Answer: ".dim())?;
+        Color::Reset
+    } else {
+        write!(out, "{}\r\n", "Answer:".dim())?;
+        theme.correct
+    };
+    for line in table_lines(table) {
+        write!(out, "\r\n  {}", style(line).with(colour))?;
+    }
+    Ok(())
+}
 
-    // Panic hook so that raw mode is exited before the error message is printed
-    let old_hook = panic::take_hook();
-    panic::set_hook(Box::new(move |info| {
-        exit();
-        old_hook(info);
-    }));
+/// The rows of a table with their cells padded to line up in columns.
+fn table_lines(table: &Table) -> Vec<String> {
+    let columns = table.rows.first().map_or(0, Vec::len);
+    let widths = (0..columns)
+        .map(|column| {
+            table
+                .rows
+                .iter()
+                .map(|row| row[column].width())
+                .max()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    table
+        .rows
+        .iter()
+        .map(|row| {
+            let mut line = String::new();
+            for (column, (cell, width)) in row.iter().zip(&widths).enumerate() {
+                if column != 0 {
+                    line.push_str(" | ");
+                }
+                line.push_str(cell);
+                if column + 1 != columns {
+                    line.extend(std::iter::repeat_n(' ', width - cell.width()));
+                }
+            }
+            line
+        })
+        .collect()
+}
 
-    // Don't exit raw mode twice; only call this when not panicking.
-    Ok(scopeguard::guard_on_success((), |()| {
-        exit();
-        drop(panic::take_hook());
-    }))
+#[test]
+fn test_table_lines() {
+    let table = "ich | bin; du | bist; er, sie, es | ist".parse().unwrap();
+    assert_eq!(
+        table_lines(&table),
+        [
+            "ich         | bin",
+            "du          | bist",
+            "er, sie, es | ist"
+        ],
+    );
 }
 
-struct DisplayAnswer<'a>(&'a BTreeSet<String>);
-impl Display for DisplayAnswer<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut answers = self.0.iter();
-        f.write_str(answers.next().unwrap())?;
-        for answer in answers {
-            write!(f, ", {answer}")?;
-        }
-        Ok(())
+/// Wrap text to the width of the compact layout, if it is in use.
+fn wrapped(text: &str, width: Option<usize>) -> String {
+    match width {
+        Some(width) => crate::ui::wrap(text, width).join("\r\n"),
+        None => text.to_owned(),
     }
 }
 
-/// All state stored in a learning session.
-struct Session<'cards> {
-    /// The previous card that was asked.
-    /// This is used to avoid asking the same card twice in a row.
-    previous_card: Option<&'cards CardKey>,
+const GRADE_PROMPT: &str = "How well did you know it? 1 again, 2 hard, 3 good, 4 easy: ";
+
+fn parse_grade(s: &str) -> Option<Grade> {
+    match s {
+        "1" => Some(Grade::Again),
+        "2" => Some(Grade::Hard),
+        "3" => Some(Grade::Good),
+        "4" => Some(Grade::Easy),
+        _ => None,
+    }
 }
-impl<'cards> Session<'cards> {
-    fn new() -> Self {
-        Self {
-            previous_card: None,
+
+/// Read an answer, giving hints towards the definitions when tab is pressed, adding what the
+/// speech-to-text command hears when the listen key is pressed and recording a clip when the
+/// record key is pressed. Returns the answer and the number of hints given.
+fn read_answer(
+    mut out: impl io::Write,
+    config: &Config,
+    simple: bool,
+    card: &Card,
+) -> io::Result<Option<(String, u8)>> {
+    let keys = &config.keys;
+    let speech_to_text = config.speech_to_text.as_ref();
+    let mut hints = 0;
+    let mut typed = String::new();
+    loop {
+        let requests = Requests {
+            hint: hints < MAX_HINTS,
+            listen: speech_to_text.is_some(),
+            #[cfg(feature = "media")]
+            record: config.record_audio.is_some(),
+        };
+        match crate::ui::read_input(&mut out, keys, simple, typed, requests)? {
+            None => return Ok(None),
+            Some(Input::Line(line)) => return Ok(Some((line, hints))),
+            Some(Input::Hint(line)) => {
+                hints += 1;
+                typed = line;
+                write!(
+                    out,
+                    "\r\n{}{}\r\n\r\n{}",
+                    "Hint: ".dim(),
+                    hint(&card.definitions, hints),
+                    "Term: ".dim(),
+                )?;
+                out.flush()?;
+            }
+            Some(Input::Listen(line)) => {
+                typed = line;
+                write!(out, "\r\n{}", "Listening...".dim())?;
+                out.flush()?;
+                match crate::speech::transcribe(speech_to_text.unwrap()) {
+                    Ok(heard) => {
+                        if !typed.is_empty() && !typed.ends_with(' ') && !heard.is_empty() {
+                            typed.push(' ');
+                        }
+                        typed.push_str(&heard);
+                    }
+                    Err(e) => write!(out, " {}", e.to_string().dim())?,
+                }
+                write!(out, "\r\n\r\n{}", "Term: ".dim())?;
+                out.flush()?;
+            }
+            #[cfg(feature = "media")]
+            Some(Input::Record(line)) => {
+                typed = line;
+                write!(out, "\r\n{}", "Recording...".dim())?;
+                out.flush()?;
+                let recorded = crate::media::recordings_dir(config)
+                    .ok_or_else(|| io::Error::other("couldn't find home directory"))
+                    .and_then(|recordings| {
+                        let record_audio = config.record_audio.as_ref().unwrap();
+                        crate::media::record(&recordings, record_audio, card)
+                    });
+                let message = match recorded {
+                    Ok(path) => format!("saved to {}", path.display()),
+                    Err(e) => e.to_string(),
+                };
+                write!(out, " {}\r\n\r\n{}", message.dim(), "Term: ".dim())?;
+                out.flush()?;
+            }
         }
     }
+}
 
-    fn generate_question<'database, C, R>(
-        &mut self,
-        database: &'database mut Database,
-        cards: C,
-        knowledge_weights: [f64; 4],
-        rng: &mut R,
-    ) -> anyhow::Result<Question<'_, 'database, 'cards>>
-    where
-        C: IntoIterator<Item = &'cards CardKey>,
-        C::IntoIter: 'cards + Clone + ExactSizeIterator,
-        R: Rng,
-    {
-        let cards = cards.into_iter();
-
-        match cards.len() {
-            0 => panic!("no cards given to `generate_question`"),
-            1 => self.previous_card = None,
-            _ => {}
+/// A hint towards the definitions of a card. The first hint shows the first letter of each
+/// definition, and the second shows the first letter of every word with the rest blanked out.
+fn hint(definitions: &BTreeSet<String>, hints: u8) -> String {
+    let hints = definitions.iter().map(|definition| {
+        if hints <= 1 {
+            let first = definition.chars().next().unwrap();
+            return format!("{first}...");
         }
-
-        let card_knowledges = database
-            .knowledge_all(cards)?
-            .map(|(card, knowledge)| (card, usize::from(knowledge.level.get())));
-
-        let mut level_distribution = [0; 4];
-        let mut choosable_distribution = [0; 4];
-        for (card, knowledge) in card_knowledges.clone() {
-            level_distribution[knowledge] += 1;
-            if self.previous_card != Some(card) {
-                choosable_distribution[knowledge] += 1;
+        let mut previous = ' ';
+        let skeleton = definition.chars().map(|c| {
+            let blank = c.is_alphanumeric() && previous.is_alphanumeric();
+            previous = c;
+            if blank {
+                '_'
+            } else {
+                c
             }
-        }
-
-        #[allow(clippy::cast_precision_loss)]
-        let weights = choosable_distribution
-            .into_iter()
-            .zip(knowledge_weights)
-            .map(|(weight, multiplier)| (weight as f64) * multiplier);
-        let card_level = rand::distributions::WeightedIndex::new(weights)
-            .unwrap()
-            .sample(rng);
-        let card_number = rng.gen_range(0..choosable_distribution[card_level]);
-
-        let (_card_index, (card_key, _)) = card_knowledges
-            .enumerate()
-            .filter(|&(_, (card_key, knowledge))| {
-                Some(card_key) != self.previous_card && knowledge == card_level
-            })
-            .nth(card_number)
-            .unwrap();
-
-        self.previous_card = Some(card_key);
-
-        #[allow(clippy::used_underscore_binding)]
-        Ok(Question {
-            _session: PhantomData,
-            database,
-            #[cfg(test)]
-            card_index: _card_index,
-            card_key,
-            level_distribution,
-        })
-    }
+        });
+        skeleton.collect()
+    });
+    hints.collect::<Vec<_>>().join(", ")
 }
 
-struct Question<'session, 'database, 'cards> {
-    // a session should only support one question at once
-    _session: PhantomData<&'session mut Session<'cards>>,
-    database: &'database mut Database,
-    #[cfg(test)]
-    card_index: usize,
-    card_key: &'cards CardKey,
-    level_distribution: [usize; 4],
+#[test]
+fn test_hint() {
+    let definitions = ["to eat".to_owned(), "food".to_owned(), "l'été".to_owned()].into();
+    assert_eq!(hint(&definitions, 1), "f..., l..., t...");
+    assert_eq!(hint(&definitions, 2), "f___, l'é__, t_ e__");
 }
 
-impl<'session, 'database, 'cards> Question<'session, 'database, 'cards> {
-    fn card_key(&self) -> &'cards CardKey {
-        self.card_key
+/// Write the number of cards at each level before a question in plain text.
+fn plain_header(mut out: impl io::Write, distribution: &[usize], at_risk: usize) -> io::Result<()> {
+    writeln!(out)?;
+    let distribution = distribution.iter().map(usize::to_string);
+    write!(out, "{}", distribution.collect::<Vec<_>>().join(" "))?;
+    if at_risk != 0 {
+        write!(out, "  {at_risk} at risk")?;
     }
+    writeln!(out)
+}
 
-    fn level_distribution(&self) -> [usize; 4] {
-        self.level_distribution
+/// Read an answer, then ask for the rest of it while it is right but incomplete, until it is
+/// complete or wrong or the user gives up with an empty line. Returns the whole answer and whether
+/// more was asked for, or `None` at the end of the input.
+fn read_answer_plain(
+    question: &Question<'_>,
+    read_line: &mut impl FnMut() -> io::Result<Option<String>>,
+    mut out: impl io::Write,
+) -> io::Result<Option<(String, bool)>> {
+    let Some(mut answer) = read_line()? else {
+        return Ok(None);
+    };
+    let mut partial = false;
+    while let Some(missing) = question.missing(&answer) {
+        partial = true;
+        write!(out, "{}", partly_right(missing.len()))?;
+        out.flush()?;
+        let Some(rest) = read_line()? else {
+            return Ok(None);
+        };
+        if rest.trim().is_empty() {
+            break;
+        }
+        answer = format!("{answer}, {rest}");
     }
+    Ok(Some((answer, partial)))
+}
 
-    fn record_result(self, correct: bool) -> anyhow::Result<()> {
-        if correct {
-            self.database.record_correct(self.card_key)?;
-        } else {
-            self.database.record_incorrect(self.card_key)?;
+/// Show a statement in plain text and read whether it is true. Returns whether the user was
+/// correct.
+fn true_false_plain(
+    statement: &Statement<'_>,
+    read_line: &mut impl FnMut() -> io::Result<Option<String>>,
+    mut out: impl io::Write,
+) -> io::Result<Option<bool>> {
+    plain_header(&mut out, &statement.level_distribution, statement.at_risk)?;
+    writeln!(out, "{}", statement.prompt)?;
+    writeln!(out, "Answer: {}", DisplayAnswer(statement.shown))?;
+    let said_true = loop {
+        write!(out, "Is it right? (t)rue or (f)alse: ")?;
+        out.flush()?;
+        let Some(line) = read_line()? else {
+            return Ok(None);
+        };
+        match line.trim() {
+            "t" => break true,
+            "f" => break false,
+            _ => {}
         }
-        Ok(())
+    };
+
+    let correct = said_true == statement.is_true();
+    if correct {
+        writeln!(out, "Correct")?;
+    } else {
+        writeln!(out, "Incorrect")?;
+        writeln!(out, "Answer: {}", DisplayAnswer(statement.card))?;
     }
+    Ok(Some(correct))
 }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::btree_set::BTreeSet;
+/// Learn without taking over the terminal, reading answers line by line from `input` and writing
+/// plain text. This lets the learning flow be scripted and used where there is no terminal.
+///
+/// The session ends at the end of the input.
+pub fn learn_plain(
+    engine: &mut Engine<impl KnowledgeStore>,
+    mode: Mode,
+    mut input: impl io::BufRead,
+    mut out: impl io::Write,
+    events: &mut Events,
+) -> anyhow::Result<()> {
+    let mut read_line = || -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim_end_matches(['\n', '\r']).to_owned()))
+    };
 
-    use rand::Rng;
+    writeln!(out, "{}", engine.title())?;
+    events.emit(&Event::Start {
+        title: engine.title(),
+    })?;
+    let mut answers = 0;
 
-    use revise_database::{CardKey, Database};
+    'questions: loop {
+        if mode == Mode::TrueFalse {
+            let statement = engine.next_statement()?;
+            events.emit(&Event::statement(&statement))?;
+            let Some(correct) = true_false_plain(&statement, &mut read_line, &mut out)? else {
+                break;
+            };
+            engine.record_recognised(correct)?;
+            events.emit(&Event::Result {
+                correct,
+                grade: None,
+            })?;
+            answered(engine, &mut answers)?;
+            continue;
+        }
 
-    use super::Session;
+        let question = engine.next_question()?;
+        let card = question.card;
+        events.emit(&Event::question(&question))?;
 
-    fn btreeset<I, S>(iter: I) -> BTreeSet<S>
-    where
-        I: IntoIterator<Item = S>,
-        S: Ord + AsRef<[u8]>,
-    {
-        iter.into_iter().collect()
-    }
+        plain_header(&mut out, &question.level_distribution, question.at_risk)?;
+        writeln!(out, "{}", question.prompt)?;
+        if let Some(disambiguation) = question.disambiguation {
+            writeln!(out, "({disambiguation})")?;
+        }
 
-    fn cards(n: usize) -> Vec<CardKey> {
-        (0_u8..=255)
-            .map(|b| {
-                let set = btreeset([[b]]);
-                CardKey::new(&set, &set)
-            })
-            .take(n)
-            .collect()
+        let grade = if mode == Mode::Flashcard {
+            write!(out, "Press enter to show the answer")?;
+            out.flush()?;
+            if read_line()?.is_none() {
+                break;
+            }
+            writeln!(out, "Answer: {}", DisplayAnswer(card))?;
+            loop {
+                write!(out, "{GRADE_PROMPT}")?;
+                out.flush()?;
+                let Some(line) = read_line()? else {
+                    break 'questions;
+                };
+                if let Some(grade) = parse_grade(line.trim()) {
+                    break grade;
+                }
+            }
+        } else {
+            let Some(grade) = typed_plain(&question, &mut read_line, &mut out, events)? else {
+                break;
+            };
+            grade
+        };
+
+        engine.record_grade(grade)?;
+        events.emit(&Event::graded(grade))?;
+        answered(engine, &mut answers)?;
+
+        if engine.mastery().is_some() && engine.snapshot()?.is_complete() {
+            break;
+        }
     }
 
-    #[test]
-    fn no_duplicates() {
-        let mut database = Database::open_in_memory().unwrap();
-        let mut rng = rand::thread_rng();
-        let mut session = Session::new();
+    events.emit(&Event::End)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Ask for a typed answer to a question, letting the user override it as correct or else type
+/// out the right answer if it is wrong. Returns `None` at the end of the input.
+fn typed_plain(
+    question: &Question<'_>,
+    read_line: &mut impl FnMut() -> io::Result<Option<String>>,
+    mut out: impl io::Write,
+    events: &mut Events,
+) -> io::Result<Option<Grade>> {
+    let card = question.card;
+    if let Some(steps) = &question.steps {
+        writeln!(out, "Put in order: {}", steps.join(", "))?;
+    }
+    write!(out, "Term: ")?;
+    out.flush()?;
+    let Some((answer, partial)) = read_answer_plain(question, read_line, &mut out)? else {
+        return Ok(None);
+    };
+    events.emit(&Event::Answer { answer: &answer })?;
 
-        let cards = cards(2);
+    let correct = if question.is_correct(&answer) {
+        writeln!(out, "Correct")?;
+        true
+    } else {
+        writeln!(out, "Incorrect")?;
+        writeln!(out, "Answer: {}", DisplayAnswer(card))?;
+        write!(out, "Override (c)orrect or continue: ")?;
+        out.flush()?;
+        let Some(key) = read_line()? else {
+            return Ok(None);
+        };
 
-        let mut previous = None;
-        for _ in 0..1000 {
-            let question = session
-                .generate_question(&mut database, &cards, [1.0; 4], &mut rng)
-                .unwrap();
-            if let Some(previous) = previous {
-                assert_ne!(question.card_index, previous);
+        if key.trim() == "c" {
+            true
+        } else {
+            loop {
+                write!(out, "Type it out: ")?;
+                out.flush()?;
+                let Some(line) = read_line()? else {
+                    return Ok(None);
+                };
+                if is_exact(card, &line) {
+                    break;
+                }
             }
-            previous = Some(question.card_index);
+            false
+        }
+    };
 
-            question.record_result(rng.gen()).unwrap();
+    Ok(Some(match (correct, partial) {
+        (false, _) => Grade::Again,
+        (true, false) => Grade::Good,
+        (true, true) => Grade::Hard,
+    }))
+}
+
+fn enter_raw(simple: bool) -> io::Result<impl Drop> {
+    fn exit(simple: bool) {
+        if simple {
+            drop(execute!(io::stdout(), cursor::MoveToNextLine(1)));
+        } else {
+            drop(execute!(io::stdout(), terminal::LeaveAlternateScreen));
         }
+        drop(terminal::disable_raw_mode());
+    }
 
-        assert!(previous.is_some());
+    if !simple {
+        execute!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            terminal::Clear(ClearType::All)
+        )?;
     }
+    terminal::enable_raw_mode()?;
 
-    #[test]
-    fn equal_distribution() {
-        let mut database = Database::open_in_memory().unwrap();
-        let mut rng = rand::thread_rng();
-        let mut session = Session::new();
+    // Panic hook so that raw mode is exited before the error message is printed
+    let old_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        exit(simple);
+        old_hook(info);
+    }));
 
-        let cards = cards(5);
-        let mut occurrences = (0..cards.len()).map(|_| 0).collect::<Vec<_>>();
+    // Don't exit raw mode twice; only call this when not panicking.
+    Ok(scopeguard::guard_on_success((), move |()| {
+        exit(simple);
+        drop(panic::take_hook());
+    }))
+}
 
-        const ITERATIONS: usize = 1000;
-        for _ in 0..ITERATIONS {
-            let question = session
-                .generate_question(&mut database, &cards, [1.0; 4], &mut rng)
-                .unwrap();
-            occurrences[question.card_index] += 1;
-            question.record_result(true).unwrap();
+/// The answer to a card: its steps in order, the answers from its table in table order, or else
+/// its definitions.
+pub struct DisplayAnswer<'a>(pub &'a Card);
+impl Display for DisplayAnswer<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(table) = &self.0.table {
+            return f.write_str(&table.answers().join(", "));
         }
-
-        let average = ITERATIONS / cards.len();
-        for occurrences in occurrences {
-            assert!(
-                ((average - 50)..(average + 50)).contains(&occurrences),
-                "{occurrences} is too far off {average}"
-            );
+        let mut answers: Box<dyn Iterator<Item = &String>> = match &self.0.steps {
+            Some(steps) => Box::new(steps.iter()),
+            None => Box::new(self.0.definitions.iter()),
+        };
+        f.write_str(answers.next().unwrap())?;
+        for answer in answers {
+            write!(f, ", {answer}")?;
         }
+        Ok(())
     }
 }
+
+#[test]
+fn test_learn_plain_flashcard() {
+    use revise_engine::MemoryStore;
+
+    let set = revise_parser::parse_set("Colours\n\nred - rouge\n").unwrap();
+    let mut engine = Engine::new(MemoryStore::new());
+    engine.load_set(set, false);
+
+    let input = "\n4\n\n5\n1\n";
+    let mut out = Vec::new();
+    learn_plain(
+        &mut engine,
+        Mode::Flashcard,
+        input.as_bytes(),
+        &mut out,
+        &mut Events::none(),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!(
+            "Colours\n\
+            \n\
+            1 0 0 0\n\
+            red\n\
+            Press enter to show the answerAnswer: rouge\n\
+            {GRADE_PROMPT}\n\
+            0 0 1 0\n\
+            red\n\
+            Press enter to show the answerAnswer: rouge\n\
+            {GRADE_PROMPT}{GRADE_PROMPT}\n\
+            0 0 1 0  1 at risk\n\
+            red\n\
+            Press enter to show the answer\n"
+        )
+    );
+}
+
+#[test]
+fn test_learn_plain() {
+    use revise_engine::MemoryStore;
+
+    let set = revise_parser::parse_set("Colours\n\nred - rouge\n").unwrap();
+    let mut engine = Engine::new(MemoryStore::new());
+    engine.load_set(set, false);
+
+    let input = "rouge\nbleu\n\nrouje\nrouge\nbleu\nc\n";
+    let mut out = Vec::new();
+    learn_plain(
+        &mut engine,
+        Mode::Typed,
+        input.as_bytes(),
+        &mut out,
+        &mut Events::none(),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Colours\n\
+        \n\
+        1 0 0 0\n\
+        red\n\
+        Term: Correct\n\
+        \n\
+        0 1 0 0\n\
+        red\n\
+        Term: Incorrect\n\
+        Answer: rouge\n\
+        Override (c)orrect or continue: Type it out: Type it out: \n\
+        0 1 0 0  1 at risk\n\
+        red\n\
+        Term: Incorrect\n\
+        Answer: rouge\n\
+        Override (c)orrect or continue: \n\
+        0 0 1 0\n\
+        red\n\
+        Term: \n",
+    );
+}
+
+#[test]
+fn test_learn_plain_steps() {
+    use revise_engine::MemoryStore;
+
+    let set = revise_parser::parse_set("Tea\n\nmake tea -> boil, brew, pour\n").unwrap();
+    let mut engine = Engine::new(MemoryStore::new());
+    engine.load_set(set, false);
+
+    let input = "boil, brew, pour\npour, brew, boil\n\nboil, pour, brew\nboil, brew, pour\n";
+    let mut out = Vec::new();
+    learn_plain(
+        &mut engine,
+        Mode::Typed,
+        input.as_bytes(),
+        &mut out,
+        &mut Events::none(),
+    )
+    .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("make tea\nPut in order: "), "{out}");
+    assert!(out.contains("Term: Correct\n"), "{out}");
+    assert!(
+        out.contains(
+            "Term: Incorrect\n\
+            Answer: boil, brew, pour\n\
+            Override (c)orrect or continue: Type it out: Type it out: \n"
+        ),
+        "{out}"
+    );
+}
+
+#[test]
+fn test_learn_plain_true_false() {
+    use revise_engine::MemoryStore;
+
+    let set = revise_parser::parse_set("Colours\n\nrouge - red\n").unwrap();
+    let mut engine = Engine::new(MemoryStore::new());
+    engine.load_set(set, false);
+
+    let input = "t\nf\nmaybe\nt\n";
+    let mut out = Vec::new();
+    learn_plain(
+        &mut engine,
+        Mode::TrueFalse,
+        input.as_bytes(),
+        &mut out,
+        &mut Events::none(),
+    )
+    .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(
+        out,
+        "Colours\n\
+        \n1 0 0 0\nrouge\nAnswer: red\nIs it right? (t)rue or (f)alse: Correct\n\
+        \n1 0 0 0\nrouge\nAnswer: red\nIs it right? (t)rue or (f)alse: Incorrect\nAnswer: red\n\
+        \n1 0 0 0\nrouge\nAnswer: red\nIs it right? (t)rue or (f)alse: \
+        Is it right? (t)rue or (f)alse: Correct\n\
+        \n1 0 0 0\nrouge\nAnswer: red\nIs it right? (t)rue or (f)alse: \n",
+    );
+}
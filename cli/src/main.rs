@@ -1,55 +1,158 @@
 #![warn(clippy::all, clippy::pedantic)]
 #![allow(clippy::non_ascii_literal, clippy::items_after_statements)]
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
+use std::env;
 use std::error::Error;
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal as _, Write};
 use std::mem;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use anyhow::Context as _;
+use chrono::{DateTime, Local};
 use clap::Parser as _;
-use directories::ProjectDirs;
+use serde::Deserialize;
 use thiserror::Error;
 
-use revise_database::{CardKey, Database, Knowledge, KnowledgeLevel};
-use revise_parser::Set;
+use revise_database::{CardKey, Database, Knowledge, KnowledgeLevel, KnowledgeStore, Levels};
+use revise_engine::{Engine, MemoryStore};
+use revise_parser::{BorrowedSet, Set};
 
 mod ui;
 
+mod config;
+use config::{Config, WhenComplete};
+
 mod learn;
 
+mod streak;
+
+mod days;
+
+mod search;
+
+mod stats;
+
+mod export;
+
+mod plan;
+
+mod import;
+
+mod csv;
+
+mod profile;
+use profile::ProfileName;
+
+mod db;
+
+mod doctor;
+
+mod events;
+
+mod meta;
+
+mod diff;
+
+mod speech;
+
+#[cfg(feature = "media")]
+mod media;
+
+mod exam;
+
+mod cram;
+
+mod merge;
+
 mod report;
 use report::{Report, Source};
 
 mod report_parse_error;
 
 #[derive(clap::Parser)]
-enum Args {
+struct Args {
+    /// The knowledge database to use [default: `REVISE_DATABASE`, then the `database` in the config
+    /// file, or else the standard location].
+    #[clap(long, global = true)]
+    database: Option<PathBuf>,
+
+    /// The profile whose knowledge database to use, instead of the shared one, `REVISE_DATABASE` or
+    /// the `database` in the config file.
+    #[clap(long, global = true)]
+    profile: Option<ProfileName>,
+
+    /// How to show errors and warnings [default: the `report-style` in the config file, or else
+    /// annotated].
+    #[clap(long, global = true, arg_enum)]
+    report_style: Option<report::Style>,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
     /// Learn all the cards in one or more sets.
-    Learn {
-        /// The sets to learn.
+    Learn(LearnArgs),
+
+    /// Test yourself on a fixed number of questions and get a grade, without showing any answers
+    /// until the end.
+    Exam(ExamArgs),
+
+    /// Go through every card that isn't mastered yet once, or until each is answered correctly,
+    /// without the questions adapting to what you know.
+    Cram(CramArgs),
+
+    /// Check one or more sets syntactically, but don't learn anything.
+    Check {
+        /// Also warn about cards that are allowed but probably a mistake, like a card that is
+        /// another card with its terms and definitions swapped.
+        #[clap(long)]
+        lint: bool,
+
+        /// The sets to check.
         #[clap(required = true)]
         sets: Vec<PathBuf>,
+    },
 
-        /// Whether to invert the terms and definitions.
-        #[clap(short, long)]
-        invert: bool,
+    /// Combine several sets into one file, leaving out cards that are in more than one.
+    Merge {
+        /// The title of the merged set [default: the first set's title].
+        #[clap(long)]
+        title: Option<String>,
 
-        /// Whether to clear all recorded knowledge before starting.
-        #[clap(short, long)]
-        clear: bool,
+        /// The file to write the merged set to.
+        output: PathBuf,
 
-        /// The weights to use for each knowledge category.
-        #[clap(short, long, default_value = "10,5,1,0.1")]
-        weights: Weights,
+        /// The sets to merge.
+        #[clap(required = true, min_values = 2)]
+        sets: Vec<PathBuf>,
     },
 
-    /// Check one or more sets syntactically, but don't learn anything.
-    Check {
-        /// The sets to check.
+    /// List the clips recorded of the answers to the cards in one or more sets.
+    #[cfg(feature = "media")]
+    Recordings {
+        /// The sets whose recordings to list [default: the `sets` in the config file].
+        sets: Vec<PathBuf>,
+    },
+
+    /// Show the cards that are only in one of two sets, and how many they share.
+    Diff {
+        /// The first set.
+        a: PathBuf,
+        /// The second set.
+        b: PathBuf,
+    },
+
+    /// Format one or more sets in place, keeping their comments and blank lines.
+    Fmt {
+        /// Don't write anything, but fail if any of the sets are not formatted.
+        #[clap(long)]
+        check: bool,
+
+        /// The sets to format.
         #[clap(required = true)]
         sets: Vec<PathBuf>,
     },
@@ -64,27 +167,291 @@ enum Args {
         #[clap(required = true)]
         sets: Vec<PathBuf>,
     },
+
+    /// Restore the knowledge of the cards in the most recent `revise clear` or `learn --clear`.
+    Unclear,
+
+    /// Search the terms and definitions of sets, showing how well you know each matching card.
+    Search(SearchArgs),
+
+    /// Convert one or more sets to another format, printing the result.
+    Export {
+        /// The format to convert the sets to.
+        #[clap(long, arg_enum)]
+        format: export::Format,
+
+        /// Whether to invert the terms and definitions.
+        #[clap(short, long)]
+        invert: bool,
+
+        /// The sets to convert.
+        #[clap(required = true)]
+        sets: Vec<PathBuf>,
+    },
+
+    /// Convert a file in another format to a set, printing the result.
+    Import {
+        /// The format of the file.
+        #[clap(long, arg_enum)]
+        format: import::Format,
+
+        /// The title of the set [default: the file's name].
+        #[clap(long)]
+        title: Option<String>,
+
+        /// Write the set to this file instead of printing it.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+
+        /// The file to convert.
+        file: PathBuf,
+    },
+
+    /// Export statistics about every card you have studied, or every study session.
+    Stats {
+        /// The format to export the statistics in.
+        #[clap(long, arg_enum, default_value = "csv")]
+        format: stats::Format,
+
+        /// Export one row per study session instead of one per card.
+        #[clap(long)]
+        sessions: bool,
+    },
+
+    /// Manage profiles, which each keep their own recorded knowledge.
+    #[clap(subcommand)]
+    Profile(profile::Command),
+
+    /// Maintain the knowledge database.
+    #[clap(subcommand)]
+    Db(db::Command),
+
+    /// Look for problems with the terminal, locale, config file, database and configured sets.
+    Doctor,
+
+    /// Show the settings that other commands use, resolved from the flags and the config file.
+    Meta {
+        /// Output JSON, for editor plugins and other programs.
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Show how many days in a row you have studied, and a calendar of recent activity.
+    Streak {
+        /// The number of months to show in the calendar, at most 1200.
+        #[clap(long, default_value = "1", parse(try_from_str = parse_months))]
+        months: u32,
+    },
+}
+
+#[derive(clap::Args)]
+#[allow(clippy::struct_excessive_bools)]
+struct LearnArgs {
+    /// The sets to learn [default: the `sets` in the config file].
+    sets: Vec<PathBuf>,
+
+    /// Whether to invert the terms and definitions.
+    #[clap(short, long)]
+    invert: bool,
+
+    /// Learn the cards both ways round, alternating between them. A card only reaches the third
+    /// level once it has been answered correctly both ways.
+    #[clap(long, conflicts_with = "invert")]
+    both_directions: bool,
+
+    /// Leave out the cards that are also in this set. Can be given more than once.
+    #[clap(long, value_name = "SET", multiple_occurrences = true)]
+    except: Vec<PathBuf>,
+
+    /// Only learn the cards that are also in this set. If given more than once, cards must be in
+    /// every one of them.
+    #[clap(long, value_name = "SET", multiple_occurrences = true)]
+    only_in: Vec<PathBuf>,
+
+    /// Whether to clear all recorded knowledge before starting.
+    #[clap(short, long)]
+    clear: bool,
+
+    /// The weights to use for each knowledge category, one for each level [default: 10,5,1,0.1
+    /// with 4 levels].
+    #[clap(short, long)]
+    weights: Option<Weights>,
+
+    /// The number of knowledge levels cards move between, from 2 to 16. This is remembered
+    /// for future sessions.
+    #[clap(long)]
+    levels: Option<Levels>,
+
+    /// How many times more often to ask cards that would move down a level if answered
+    /// incorrectly.
+    #[clap(long, default_value = "2", parse(try_from_str = parse_weight))]
+    at_risk_weight: f64,
+
+    /// The knowledge level at which cards are mastered and stop being asked. The session ends
+    /// once every card is mastered [default: cards are asked at every level].
+    #[clap(long)]
+    mastery: Option<KnowledgeLevel>,
+
+    /// The probability, from 0 to 1, of asking a mastered card anyway.
+    #[clap(long, default_value = "0", requires = "mastery", parse(try_from_str = parse_probability))]
+    overlearn: f64,
+
+    /// Once every card is mastered, go through the cards answered incorrectly again until each
+    /// has been answered correctly twice in a row.
+    #[clap(long, requires = "mastery")]
+    redrill: bool,
+
+    /// When a prompt is shared by several cards, accept the answer of any of them.
+    #[clap(long)]
+    accept_ambiguous: bool,
+
+    /// The number of typos, counted as inserted, deleted or changed characters, to allow in each
+    /// part of an answer [default: 0].
+    #[clap(long)]
+    typo_tolerance: Option<usize>,
+
+    /// The fraction of pairs of steps, from 0 to 1, that can be given in the wrong order relative
+    /// to each other in cards with steps [default: 0].
+    #[clap(long)]
+    order_tolerance: Option<OrderTolerance>,
+
+    /// How to answer questions: type out the answer to have it checked, reveal it like a
+    /// flashcard and grade yourself with 1 (again), 2 (hard), 3 (good) or 4 (easy), or say
+    /// whether the answer shown is true or false [default: flashcard in terminals narrower than
+    /// 60 columns, typed otherwise].
+    #[clap(long, arg_enum)]
+    mode: Option<learn::Mode>,
+
+    /// The number of questions in each round, after which a summary of the round is shown, or 0
+    /// to ask questions without rounds [default: 7].
+    #[clap(long)]
+    round_size: Option<usize>,
+
+    /// Read answers line by line from standard input and print plain text, instead of using the
+    /// full-screen interface. This is the default when standard output is not a terminal.
+    #[clap(long)]
+    no_tui: bool,
+
+    /// Draw only ASCII and let output scroll instead of using the whole screen, for terminals that
+    /// support neither. This is the default in terminals that appear not to.
+    #[clap(long)]
+    simple_ui: bool,
+
+    /// Read each prompt aloud with the `text-to-speech` command in the config file.
+    #[clap(long)]
+    speak: bool,
+
+    /// Write each question, answer and result to a file as a line of JSON while learning, for
+    /// other programs to follow. Give `/dev/fd/3` to write them to file descriptor 3.
+    #[clap(long, value_name = "FILE")]
+    events_json: Option<PathBuf>,
+
+    /// Instead of learning, show how likely each card is to be asked next under the current
+    /// settings, and how many questions it would take to master every card.
+    #[clap(long, conflicts_with = "clear")]
+    plan: bool,
+
+    /// Show the plan as JSON.
+    #[clap(long, requires = "plan")]
+    json: bool,
+}
+
+#[derive(clap::Args)]
+struct ExamArgs {
+    /// The sets to be examined on [default: the `sets` in the config file].
+    sets: Vec<PathBuf>,
+
+    /// The number of questions to ask.
+    #[clap(short, long, default_value = "20")]
+    questions: usize,
+
+    /// Whether to invert the terms and definitions.
+    #[clap(short, long)]
+    invert: bool,
+
+    /// Record the answers in the knowledge database, as when learning.
+    #[clap(long)]
+    record: bool,
+}
+
+#[derive(clap::Args)]
+#[allow(clippy::struct_excessive_bools)]
+struct CramArgs {
+    /// The sets to cram [default: the `sets` in the config file].
+    sets: Vec<PathBuf>,
+
+    /// Only cram the cards with a term or definition containing this text, ignoring case.
+    #[clap(long, value_name = "TEXT")]
+    matching: Option<String>,
+
+    /// Cram the cards at the highest level too.
+    #[clap(long)]
+    include_mastered: bool,
+
+    /// Keep asking the cards answered incorrectly in more rounds, until every card has been
+    /// answered correctly.
+    #[clap(long)]
+    repeat: bool,
+
+    /// Whether to invert the terms and definitions.
+    #[clap(short, long)]
+    invert: bool,
+
+    /// Record the answers in the knowledge database, as when learning.
+    #[clap(long)]
+    record: bool,
+}
+
+#[derive(clap::Args)]
+struct SearchArgs {
+    /// The text to search for, ignoring case.
+    pattern: String,
+
+    /// The sets to search [default: every set in `--dir`].
+    sets: Vec<PathBuf>,
+
+    /// The directory whose sets are searched when no sets are given.
+    #[clap(long, default_value = ".")]
+    dir: PathBuf,
+
+    /// Treat the pattern as a regular expression.
+    #[clap(long, conflicts_with = "fuzzy")]
+    regex: bool,
+
+    /// Match text containing the pattern's characters in order, with anything between them.
+    #[clap(long)]
+    fuzzy: bool,
+
+    /// Print the results as JSON.
+    #[clap(long)]
+    json: bool,
 }
 
 fn main() {
     struct StderrReporter<'a> {
         first_report: bool,
+        style: report::Style,
         stderr: io::StderrLock<'a>,
     }
     impl Reporter for StderrReporter<'_> {
         fn report(&mut self, report: Report<'_>) {
-            drop(if self.first_report {
+            let report = report.styled(self.style);
+            drop(if self.first_report || self.style == report::Style::Short {
                 self.first_report = false;
                 write!(self.stderr, "{report}")
             } else {
                 write!(self.stderr, "\n{report}")
             });
         }
+        fn set_style(&mut self, style: report::Style) {
+            self.style = style;
+        }
     }
 
     let stderr = io::stderr();
     let mut reporter = StderrReporter {
         first_report: true,
+        style: report::Style::default(),
         stderr: stderr.lock(),
     };
 
@@ -97,108 +464,901 @@ fn main() {
 
 trait Reporter {
     fn report(&mut self, report: Report<'_>);
+    fn set_style(&mut self, style: report::Style);
     fn error_chain(&mut self, error: impl Error) {
         self.report(Report::error_chain(error));
     }
 }
 
 fn try_main(reporter: &mut impl Reporter) -> Result<(), ()> {
-    match Args::parse() {
-        Args::Learn {
+    let Args {
+        database,
+        profile,
+        report_style,
+        command,
+    } = Args::parse();
+    if let Some(style) = report_style {
+        reporter.set_style(style);
+    }
+    let (mut config, config_error) = load_config(&command, reporter)?;
+    config.report_style = report_style.unwrap_or(config.report_style);
+    reporter.set_style(config.report_style);
+    // `REVISE_DATABASE` is read here rather than by clap so that an explicit `--profile` can
+    // override it, while still conflicting with an explicit `--database`.
+    if let Some(profile) = &profile {
+        if database.is_some() {
+            reporter.report(report::error!(
+                "a profile cannot be used together with `--database`"
+            ));
+            return Err(());
+        }
+        config.database = Some(profile_database(profile, reporter)?);
+    } else if let Some(database) =
+        database.or_else(|| env::var_os("REVISE_DATABASE").map(PathBuf::from))
+    {
+        config.database = Some(database);
+    }
+
+    match command {
+        Command::Learn(args) => learn(args, &config, reporter)?,
+        Command::Exam(args) => exam(args, &config, reporter)?,
+        Command::Cram(args) => cram(args, &config, reporter)?,
+        Command::Check { lint, sets } => check(lint, sets, reporter)?,
+        Command::Diff { a, b } => diff(a, b, reporter)?,
+        #[cfg(feature = "media")]
+        Command::Recordings { sets } => recordings(sets, &config, reporter)?,
+        Command::Merge {
+            title,
+            output,
             sets,
+        } => merge(title.as_deref(), &output, sets, reporter)?,
+        Command::Fmt { check, sets } => fmt(check, sets, reporter)?,
+        Command::Clear { level, sets } => clear(level, sets, &config, reporter)?,
+        Command::Unclear => unclear(&config, reporter)?,
+        Command::Search(args) => search(args, &config, reporter)?,
+        Command::Export {
+            format,
             invert,
-            clear,
-            weights,
-        } => {
-            let mut result = Ok(());
-
-            let sets: Vec<_> = sets
-                .into_iter()
-                .filter_map(|path| record_err(read_set_file(path, reporter), &mut result))
-                .collect();
-
-            result?;
-
-            let mut title = String::new();
-            let mut cards = HashMap::new();
-
-            for set in sets {
-                if title.is_empty() {
-                    title = set.title;
-                } else {
-                    title.push_str(" + ");
-                    title.push_str(&set.title);
-                }
-
-                cards.extend(set.cards.into_iter().map(|mut card| {
-                    if invert {
-                        mem::swap(&mut card.terms, &mut card.definitions);
-                    }
-                    (CardKey::new(&card.terms, &card.definitions), card)
-                }));
-            }
-
-            let mut database = open_database().map_err(|e| reporter.error_chain(e))?;
-            if clear {
-                database
-                    .set_knowledge_all(cards.keys(), Knowledge::default())
-                    .map_err(|e| reporter.error_chain(&e))?;
-            }
-            learn::learn(
-                &mut database,
-                &title,
-                &cards,
-                weights.0,
+            sets,
+        } => export(format, invert, sets, reporter)?,
+        Command::Import {
+            format,
+            title,
+            output,
+            file,
+        } => import(format, title, output, &file, reporter)?,
+        Command::Stats { format, sessions } => {
+            let database = open_database(&config).map_err(|e| reporter.error_chain(e))?;
+            stats::stats(&database, format, sessions, &mut io::stdout().lock())
+                .map_err(|e| reporter.error_chain(&*e))?;
+        }
+        Command::Profile(command) => {
+            let profiles_dir = profiles_dir(reporter)?;
+            profile::profile(command, &profiles_dir, &mut io::stdout().lock())
+                .map_err(|e| reporter.error_chain(&*e))?;
+        }
+        Command::Db(db::Command::Doctor { prune, sets }) => {
+            db_doctor(prune, sets, &config, reporter)?;
+        }
+        Command::Doctor => doctor(&config, config_error.as_ref(), reporter)?,
+        Command::Db(db::Command::Sets) => {
+            let database = open_database(&config).map_err(|e| reporter.error_chain(e))?;
+            db::sets(&database, &mut io::stdout().lock()).map_err(|e| reporter.error_chain(&*e))?;
+        }
+        Command::Db(db::Command::Merge { strategy, other }) => {
+            let mut database = open_database(&config).map_err(|e| reporter.error_chain(e))?;
+            db::merge(&mut database, &other, strategy, &mut io::stdout().lock())
+                .map_err(|e| reporter.error_chain(&*e))?;
+        }
+        Command::Meta { json } => {
+            meta::meta(
+                &config,
+                Config::path(),
+                profile.as_ref(),
+                json,
                 &mut io::stdout().lock(),
             )
             .map_err(|e| reporter.error_chain(&*e))?;
         }
-        Args::Check { sets } => {
-            let mut result = Ok(());
+        Command::Streak { months } => {
+            let database = open_database(&config).map_err(|e| reporter.error_chain(e))?;
+            let days = days::StudyDays::new(&config);
+            streak::streak(&database, days, months, &mut io::stdout().lock())
+                .map_err(|e| reporter.error_chain(&*e))?;
+        }
+    }
 
-            for set in sets {
-                record_err(read_set_file(set, reporter), &mut result);
-            }
+    Ok(())
+}
 
-            result?;
+fn doctor(
+    config: &Config,
+    config_error: Option<&config::LoadError>,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    let problems = doctor::doctor(config, config_error, io::stdout().lock())
+        .map_err(|e| reporter.error_chain(e))?;
+    if problems > 0 {
+        reporter.report(report::error!(
+            "found {problems} problem{}",
+            if problems == 1 { "" } else { "s" }
+        ));
+        return Err(());
+    }
+    Ok(())
+}
+
+/// The database of an existing profile.
+fn profile_database(profile: &ProfileName, reporter: &mut impl Reporter) -> Result<PathBuf, ()> {
+    let profiles_dir = profiles_dir(reporter)?;
+    let path = profile.path(&profiles_dir);
+    if !path.exists() {
+        reporter.report(
+            report::error!("profile `{profile}` does not exist").with_footer(
+                report::Annotation::help(format!(
+                    "create it with `revise profile create {profile}`"
+                )),
+            ),
+        );
+        return Err(());
+    }
+    Ok(path)
+}
+
+/// Load the config file. A broken config file is one of the things `revise doctor` reports, so
+/// for it the default config is used along with the error.
+fn load_config(
+    command: &Command,
+    reporter: &mut impl Reporter,
+) -> Result<(Config, Option<config::LoadError>), ()> {
+    match Config::load() {
+        Ok(config) => Ok((config, None)),
+        Err(e) if matches!(command, Command::Doctor) => Ok((Config::default(), Some(e))),
+        Err(e) => {
+            reporter.error_chain(e);
+            Err(())
         }
-        Args::Clear { level, sets } => {
-            let mut result = Ok(());
-
-            let cards = sets
-                .into_iter()
-                .filter_map(|set| record_err(read_set_file(set, reporter), &mut result))
-                .flat_map(|set| {
-                    set.cards.into_iter().flat_map(|card| {
-                        [
-                            CardKey::new(&card.terms, &card.definitions),
-                            CardKey::new(&card.definitions, &card.terms),
-                        ]
-                    })
-                })
-                .collect::<HashSet<_>>();
-
-            result?;
-
-            open_database()
-                .map_err(|e| reporter.error_chain(e))?
-                .set_knowledge_all(
-                    &cards,
-                    Knowledge {
-                        level,
-                        safety_net: false,
-                    },
-                )
-                .map_err(|e| reporter.error_chain(e))?;
+    }
+}
+
+fn export(
+    format: export::Format,
+    invert: bool,
+    sets: Vec<PathBuf>,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    let sets = read_set_files(sets, reporter)?;
+    let sets = sets.into_iter().map(|(_, set)| set).collect();
+    let lost = export::export(sets, format, invert, &mut io::stdout().lock())
+        .map_err(|e| reporter.error_chain(e))?;
+    for lost in lost {
+        reporter.report(report::warning!("information is lost: {lost}"));
+    }
+
+    Ok(())
+}
+
+fn import(
+    format: import::Format,
+    title: Option<String>,
+    output: Option<PathBuf>,
+    file: &Path,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    let text = fs::read_to_string(file).map_err(|e| {
+        reporter.report(report::error!("couldn't read {}: {}", file.display(), e));
+    })?;
+    let title = title.unwrap_or_else(|| {
+        let stem = file.file_stem().unwrap_or_default();
+        stem.to_string_lossy().into_owned()
+    });
+    let imported = import::import(&text, format, &title).map_err(|e| reporter.error_chain(&*e))?;
+    for warning in imported.warnings {
+        reporter.report(report::warning!("{warning}"));
+    }
+    match output {
+        Some(output) => fs::write(&output, imported.source).map_err(|e| {
+            reporter.report(report::error!(
+                "couldn't write to {}: {}",
+                output.display(),
+                e
+            ));
+        })?,
+        None => print!("{}", imported.source),
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "media")]
+fn recordings(sets: Vec<PathBuf>, config: &Config, reporter: &mut impl Reporter) -> Result<(), ()> {
+    let sets = learn_sets(sets, Vec::new(), Vec::new(), config, reporter)?;
+    let recordings = media::recordings_dir(config).ok_or_else(|| {
+        reporter.report(report::error!("couldn't find home directory"));
+    })?;
+    media::list(&recordings, &sets, io::stdout().lock()).map_err(|e| reporter.error_chain(&e))
+}
+
+fn diff(a: PathBuf, b: PathBuf, reporter: &mut impl Reporter) -> Result<(), ()> {
+    let sets = read_set_files(vec![a, b], reporter)?;
+    let (a, b) = (&sets[0], &sets[1]);
+    diff::diff((&a.0, &a.1), (&b.0, &b.1), io::stdout().lock())
+        .map_err(|e| reporter.error_chain(&e))
+}
+
+fn merge(
+    title: Option<&str>,
+    output: &Path,
+    sets: Vec<PathBuf>,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    let sets = read_set_files(sets, reporter)?;
+    let merged = merge::merge(&sets, title).map_err(|e| reporter.error_chain(&*e))?;
+    for warning in merged.warnings {
+        reporter.report(report::warning!("{warning}"));
+    }
+    fs::write(output, merged.source).map_err(|e| {
+        reporter.report(report::error!(
+            "couldn't write to {}: {}",
+            output.display(),
+            e
+        ));
+    })
+}
+
+fn fmt(check: bool, paths: Vec<PathBuf>, reporter: &mut impl Reporter) -> Result<(), ()> {
+    let mut result = Ok(());
+
+    for path in paths {
+        let formatted = format_set_file(&path, reporter);
+        let Some(formatted) = record_err(formatted, &mut result).flatten() else {
+            continue;
+        };
+
+        if check {
+            reporter.report(report::error!("{} is not formatted", path.display()));
+            result = Err(());
+        } else {
+            let written = fs::write(&path, formatted).map_err(|e| {
+                reporter.report(report::error!(
+                    "couldn't write to {}: {}",
+                    path.display(),
+                    e
+                ));
+            });
+            record_err(written, &mut result);
         }
     }
 
+    result
+}
+
+fn clear(
+    level: KnowledgeLevel,
+    sets: Vec<PathBuf>,
+    config: &Config,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    let cards = card_keys(read_set_files(sets, reporter)?);
+
+    let mut database = open_database(config).map_err(|e| reporter.error_chain(e))?;
+    let max_level = database.levels().max();
+    if level > max_level {
+        reporter.report(report::error!(
+            "level {} is above the highest level, {}",
+            level.get(),
+            max_level.get(),
+        ));
+        return Err(());
+    }
+    database
+        .clear(
+            &cards,
+            Knowledge {
+                level,
+                safety_net: false,
+            },
+        )
+        .map_err(|e| reporter.error_chain(e))
+}
+
+fn unclear(config: &Config, reporter: &mut impl Reporter) -> Result<(), ()> {
+    let mut database = open_database(config).map_err(|e| reporter.error_chain(e))?;
+    let Some(unclear) = database.unclear().map_err(|e| reporter.error_chain(e))? else {
+        reporter.report(report::error!("there is no clear to undo"));
+        return Err(());
+    };
+    println!(
+        "Restored the knowledge of {} card{} cleared at {}.",
+        unclear.cards,
+        if unclear.cards == 1 { "" } else { "s" },
+        DateTime::<Local>::from(unclear.cleared_at).format("%Y-%m-%d %H:%M"),
+    );
+    Ok(())
+}
+
+fn db_doctor(
+    prune: bool,
+    sets: Vec<PathBuf>,
+    config: &Config,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    let known = (!sets.is_empty())
+        .then(|| read_set_files(sets, reporter).map(card_keys))
+        .transpose()?;
+    let mut database = open_database(config).map_err(|e| reporter.error_chain(e))?;
+    db::doctor(
+        &mut database,
+        known.as_ref(),
+        prune,
+        &mut io::stdout().lock(),
+    )
+    .map_err(|e| reporter.error_chain(&*e))
+}
+
+/// Open the file that events are written to while learning, if one is given.
+fn open_events(path: Option<PathBuf>, reporter: &mut impl Reporter) -> Result<events::Events, ()> {
+    let Some(path) = path else {
+        return Ok(events::Events::none());
+    };
+    let file = fs::File::create(&path).map_err(|e| {
+        reporter.report(report::error!(
+            "couldn't write to {}: {}",
+            path.display(),
+            e
+        ));
+    })?;
+    Ok(events::Events::to(io::BufWriter::new(file)))
+}
+
+/// Set the mastery level, which must not be above the highest level.
+fn set_mastery(
+    engine: &mut Engine<impl KnowledgeStore>,
+    mastery: Option<KnowledgeLevel>,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    if let Some(mastery) = mastery {
+        let max_level = engine.store().levels().max();
+        if mastery > max_level {
+            reporter.report(report::error!(
+                "mastery level {} is above the highest level, {}",
+                mastery.get(),
+                max_level.get(),
+            ));
+            return Err(());
+        }
+    }
+    engine.set_mastery(mastery);
+    Ok(())
+}
+
+fn learn(mut args: LearnArgs, config: &Config, reporter: &mut impl Reporter) -> Result<(), ()> {
+    check_speak(args.speak, config, reporter)?;
+    let whole_sets = args.except.is_empty() && args.only_in.is_empty();
+    let sets = learn_sets(
+        mem::take(&mut args.sets),
+        mem::take(&mut args.except),
+        mem::take(&mut args.only_in),
+        config,
+        reporter,
+    )?;
+
+    let mut database = open_database(config).map_err(|e| reporter.error_chain(e))?;
+    if args.plan {
+        return plan_learn(
+            &database,
+            sets,
+            &args,
+            config,
+            &mut io::stdout().lock(),
+            reporter,
+        );
+    }
+    if whole_sets {
+        record_sets(&mut database, &sets, reporter)?;
+    }
+    // Clear through the database rather than the engine, so that `revise unclear` can undo it.
+    if args.clear {
+        database
+            .clear(&learn_card_keys(&sets, &args), Knowledge::default())
+            .map_err(|e| reporter.error_chain(e))?;
+    }
+    let session = database.session().map_err(|e| reporter.error_chain(&e))?;
+    let mut engine = Engine::new(session);
+    load_learn_sets(&mut engine, sets, &args);
+    configure_learn_engine(&mut engine, &args, config, reporter)?;
+
+    if config.when_complete == WhenComplete::Demote {
+        demote_if_complete(&mut engine, reporter)?;
+    }
+    let mut events = open_events(args.events_json, reporter)?;
+    let result = if args.no_tui || !io::stdout().is_terminal() {
+        learn::learn_plain(
+            &mut engine,
+            args.mode.unwrap_or(learn::Mode::Typed),
+            io::stdin().lock(),
+            &mut io::stdout().lock(),
+            &mut events,
+        )
+    } else {
+        let simple =
+            args.simple_ui || ui::is_limited_terminal(cfg!(windows), |name| env::var(name).ok());
+        learn::learn(
+            &mut engine,
+            config,
+            args.mode,
+            simple,
+            args.speak,
+            args.round_size
+                .or(config.round_size)
+                .unwrap_or(learn::DEFAULT_ROUND_SIZE),
+            &mut io::stdout().lock(),
+            &mut events,
+        )
+    };
+    let flushed = engine.store_mut().flush();
+    result.map_err(|e| reporter.error_chain(&*e))?;
+    flushed.map_err(|e| reporter.error_chain(&e))?;
+
+    Ok(())
+}
+
+/// Show how likely each card is to be asked next without changing the database: the knowledge of
+/// the cards is copied into memory first, so that `--levels` only changes the copy.
+fn plan_learn(
+    database: &Database,
+    sets: Vec<(PathBuf, Set)>,
+    args: &LearnArgs,
+    config: &Config,
+    out: &mut impl Write,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    let mut engine = Engine::new(MemoryStore::new());
+    load_learn_sets(&mut engine, sets, args);
+    let knowledge = database
+        .knowledge_all(engine.card_keys())
+        .map_err(|e| reporter.error_chain(&e))?
+        .map(|(card, knowledge)| (card.clone(), knowledge))
+        .collect::<Vec<_>>();
+    let store = engine.store_mut();
+    store
+        .set_levels(database.levels())
+        .map_err(|e| reporter.error_chain(&e))?;
+    for (card, knowledge) in knowledge {
+        store
+            .set_knowledge(&card, knowledge)
+            .map_err(|e| reporter.error_chain(&e))?;
+    }
+    configure_learn_engine(&mut engine, args, config, reporter)?;
+    plan::plan(&engine, args.json, out).map_err(|e| reporter.error_chain(&*e))
+}
+
+fn load_learn_sets<S: KnowledgeStore>(
+    engine: &mut Engine<S>,
+    sets: Vec<(PathBuf, Set)>,
+    args: &LearnArgs,
+) {
+    for (_, set) in sets {
+        if args.both_directions {
+            engine.load_set(set.clone(), false);
+        }
+        engine.load_set(set, args.invert || args.both_directions);
+    }
+}
+
+/// The keys of the cards [`load_learn_sets`] loads, the way round they are learned.
+fn learn_card_keys(sets: &[(PathBuf, Set)], args: &LearnArgs) -> HashSet<CardKey> {
+    let mut keys = HashSet::new();
+    for card in sets.iter().flat_map(|(_, set)| &set.cards) {
+        if !args.invert || args.both_directions {
+            keys.insert(CardKey::new(&card.terms, &card.definitions));
+        }
+        if args.invert || args.both_directions {
+            keys.insert(CardKey::new(&card.definitions, &card.terms));
+        }
+    }
+    keys
+}
+
+/// Apply the settings given to `revise learn` to an engine with its sets loaded.
+fn configure_learn_engine<S: KnowledgeStore>(
+    engine: &mut Engine<S>,
+    args: &LearnArgs,
+    config: &Config,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    set_levels_and_weights(engine, args.levels, args.weights.clone(), config, reporter)?;
+    if args.both_directions {
+        let max_level = engine.store().levels().max();
+        engine.set_both_directions(KnowledgeLevel::new(2).map(|level| level.min(max_level)));
+    }
+    engine.set_at_risk_weight(args.at_risk_weight);
+    set_mastery(engine, args.mastery, reporter)?;
+    engine.set_overlearn(args.overlearn);
+    engine.set_redrill(args.redrill);
+    engine.set_accept_ambiguous(args.accept_ambiguous);
+    engine.set_typo_tolerance(args.typo_tolerance.unwrap_or(config.typo_tolerance));
+    engine.set_order_tolerance(args.order_tolerance.unwrap_or(config.order_tolerance).0);
+    report_ambiguous_prompts(engine, args.accept_ambiguous, reporter);
+    Ok(())
+}
+
+/// Check that there is a command to speak with, if asked to speak.
+fn check_speak(speak: bool, config: &Config, reporter: &mut impl Reporter) -> Result<(), ()> {
+    if speak && config.text_to_speech.is_none() {
+        reporter.report(
+            report::error!("there is no command to speak with").with_footer(
+                report::Annotation::help("set `text-to-speech` in the config file"),
+            ),
+        );
+        return Err(());
+    }
+    Ok(())
+}
+
+/// Change the number of levels if asked to, then set the knowledge weights from the flag or the
+/// config file, checking that there is one for each level.
+fn set_levels_and_weights<S: KnowledgeStore>(
+    engine: &mut Engine<S>,
+    levels: Option<Levels>,
+    weights: Option<Weights>,
+    config: &Config,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    if let Some(levels) = levels {
+        engine
+            .set_levels(levels)
+            .map_err(|e| reporter.error_chain(&e))?;
+    }
+    if let Some(Weights(weights)) = weights.or_else(|| config.weights.clone()) {
+        let levels = engine.store().levels().count();
+        if weights.len() != levels {
+            reporter.report(report::error!(
+                "expected {levels} weights, one for each level, but {} were given",
+                weights.len(),
+            ));
+            return Err(());
+        }
+        engine.set_knowledge_weights(weights);
+    }
+    Ok(())
+}
+
+/// Move every card down a level if they are all learnt already, so that the session doesn't end
+/// straight away.
+fn demote_if_complete(
+    engine: &mut Engine<impl KnowledgeStore>,
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    let demoted = engine
+        .make_incomplete()
+        .map_err(|e| reporter.error_chain(&e))?;
+    if let Some(level) = demoted {
+        reporter.report(
+            Report::new(report::Annotation::note(format!(
+                "every card was already learnt, so they have all been moved to level {}",
+                level.get(),
+            )))
+            .with_footer(report::Annotation::help(
+                "set `when-complete = \"keep\"` in the config file to leave them as they are",
+            )),
+        );
+    }
+    Ok(())
+}
+
+/// Warn about prompts that several cards share, since they can't tell which card is being asked.
+fn report_ambiguous_prompts(
+    engine: &Engine<impl KnowledgeStore>,
+    accept_ambiguous: bool,
+    reporter: &mut impl Reporter,
+) {
+    let ambiguous = engine.ambiguous_prompts();
+    if ambiguous.is_empty() {
+        return;
+    }
+
+    let mut report = report::warning!(
+        "{} prompt{} shared by more than one card",
+        ambiguous.len(),
+        if ambiguous.len() == 1 { " is" } else { "s are" },
+    );
+    for (prompt, cards) in ambiguous {
+        let answers = cards
+            .iter()
+            .map(|card| {
+                let definitions = card.definitions.iter().map(String::as_str);
+                definitions.collect::<Vec<_>>().join(", ")
+            })
+            .collect::<Vec<_>>();
+        report = report.with_footer(report::Annotation::note(format!(
+            "`{prompt}` is answered by: {}",
+            answers.join("; "),
+        )));
+    }
+    if !accept_ambiguous {
+        report = report.with_footer(report::Annotation::help(
+            "pass `--accept-ambiguous` to accept the answer of any card with the same prompt",
+        ));
+    }
+    reporter.report(report);
+}
+
+fn search(args: SearchArgs, config: &Config, reporter: &mut impl Reporter) -> Result<(), ()> {
+    let SearchArgs {
+        pattern,
+        sets,
+        dir,
+        regex,
+        fuzzy,
+        json,
+    } = args;
+
+    let matcher =
+        search::Matcher::new(&pattern, regex, fuzzy).map_err(|e| reporter.error_chain(e))?;
+
+    let sets = if sets.is_empty() {
+        find_set_files(&dir).map_err(|e| {
+            reporter.report(report::error!("couldn't read {}: {}", dir.display(), e));
+        })?
+    } else {
+        sets
+    };
+    let sets = read_set_files(sets, reporter)?;
+
+    let database = open_database(config).map_err(|e| reporter.error_chain(e))?;
+    search::search(&database, &matcher, &sets, json, &mut io::stdout().lock())
+        .map_err(|e| reporter.error_chain(&*e))?;
+
+    Ok(())
+}
+
+fn profiles_dir(reporter: &mut impl Reporter) -> Result<PathBuf, ()> {
+    revise_database::profiles_dir().ok_or_else(|| {
+        reporter.report(report::error!("couldn't find home directory"));
+    })
+}
+
+/// Find every set file in a directory, in order of their paths.
+fn find_set_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut sets = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension() == Some("set".as_ref()) {
+            sets.push(path);
+        }
+    }
+    sets.sort();
+    Ok(sets)
+}
+
+/// Read and parse several set files, reporting the errors in all of them.
+fn read_set_files(
+    paths: Vec<PathBuf>,
+    reporter: &mut impl Reporter,
+) -> Result<Vec<(PathBuf, Set)>, ()> {
+    let mut result = Ok(());
+
+    let sets = paths
+        .into_iter()
+        .filter_map(|path| {
+            let set = record_err(read_set_file(&path, reporter), &mut result)?;
+            Some((path, set))
+        })
+        .collect();
+
+    result.map(|()| sets)
+}
+
+fn exam(args: ExamArgs, config: &Config, reporter: &mut impl Reporter) -> Result<(), ()> {
+    fn sit(
+        mut engine: Engine<impl KnowledgeStore>,
+        sets: Vec<(PathBuf, Set)>,
+        questions: usize,
+        invert: bool,
+        record: bool,
+        config: &Config,
+    ) -> anyhow::Result<()> {
+        for (_, set) in sets {
+            engine.load_set(set, invert);
+        }
+        engine.set_typo_tolerance(config.typo_tolerance);
+        engine.set_order_tolerance(config.order_tolerance.0);
+        let result = exam::exam(
+            &mut engine,
+            questions,
+            record,
+            io::stdin().lock(),
+            io::stdout().lock(),
+        );
+        engine.store_mut().flush()?;
+        result
+    }
+
+    let ExamArgs {
+        sets,
+        questions,
+        invert,
+        record,
+    } = args;
+    let sets = learn_sets(sets, Vec::new(), Vec::new(), config, reporter)?;
+    let result = if record {
+        let mut database = open_database(config).map_err(|e| reporter.error_chain(e))?;
+        record_sets(&mut database, &sets, reporter)?;
+        let session = database.session().map_err(|e| reporter.error_chain(&e))?;
+        sit(
+            Engine::new(session),
+            sets,
+            questions,
+            invert,
+            record,
+            config,
+        )
+    } else {
+        let store = MemoryStore::new();
+        sit(Engine::new(store), sets, questions, invert, record, config)
+    };
+    result.map_err(|e| reporter.error_chain(&*e))
+}
+
+fn cram(mut args: CramArgs, config: &Config, reporter: &mut impl Reporter) -> Result<(), ()> {
+    fn sit(
+        mut engine: Engine<impl KnowledgeStore>,
+        sets: Vec<(PathBuf, Set)>,
+        matcher: Option<&search::Matcher>,
+        args: &CramArgs,
+        config: &Config,
+    ) -> anyhow::Result<()> {
+        for (_, set) in sets {
+            engine.load_set(set, args.invert);
+        }
+        engine.set_typo_tolerance(config.typo_tolerance);
+        engine.set_order_tolerance(config.order_tolerance.0);
+        let cards = cram::cards_to_cram(&engine, matcher, args.include_mastered)?;
+        let result = cram::cram(
+            &mut engine,
+            cards,
+            args.repeat,
+            args.record,
+            io::stdin().lock(),
+            io::stdout().lock(),
+        );
+        engine.store_mut().flush()?;
+        result
+    }
+
+    let matcher = args
+        .matching
+        .as_deref()
+        .map(|text| search::Matcher::new(text, false, false).unwrap());
+    let sets = std::mem::take(&mut args.sets);
+    let sets = learn_sets(sets, Vec::new(), Vec::new(), config, reporter)?;
+    let mut database = open_database(config).map_err(|e| reporter.error_chain(e))?;
+    let result = if args.record {
+        record_sets(&mut database, &sets, reporter)?;
+        let session = database.session().map_err(|e| reporter.error_chain(&e))?;
+        sit(Engine::new(session), sets, matcher.as_ref(), &args, config)
+    } else {
+        sit(Engine::new(database), sets, matcher.as_ref(), &args, config)
+    };
+    result.map_err(|e| reporter.error_chain(&*e))
+}
+
+/// Read the sets to learn, falling back to the default sets of the config file, then leave out of
+/// each set the cards in any of the `except` sets and keep only the cards in all of the `only_in`
+/// sets.
+fn learn_sets(
+    sets: Vec<PathBuf>,
+    except: Vec<PathBuf>,
+    only_in: Vec<PathBuf>,
+    config: &Config,
+    reporter: &mut impl Reporter,
+) -> Result<Vec<(PathBuf, Set)>, ()> {
+    let sets = if sets.is_empty() {
+        config.sets.clone()
+    } else {
+        sets
+    };
+    if sets.is_empty() {
+        reporter.report(
+            report::error!("no sets to learn").with_footer(report::Annotation::help(
+                "give the sets to learn, or set default `sets` in the config file",
+            )),
+        );
+        return Err(());
+    }
+    let sets = read_set_files(sets, reporter)?;
+    if except.is_empty() && only_in.is_empty() {
+        return Ok(sets);
+    }
+    let except = read_set_files(except, reporter)?;
+    let only_in = read_set_files(only_in, reporter)?;
+    let sets = sets
+        .into_iter()
+        .map(|(path, mut set)| {
+            for (_, other) in &except {
+                set = set.difference(other);
+            }
+            for (_, other) in &only_in {
+                set = set.intersection(other);
+            }
+            (path, set)
+        })
+        .collect::<Vec<_>>();
+    if sets.iter().all(|(_, set)| set.cards.is_empty()) {
+        reporter.report(report::error!("no cards are left to learn"));
+        return Err(());
+    }
+    Ok(sets)
+}
+
+/// The keys of every card in the sets, both ways round.
+fn card_keys(sets: Vec<(PathBuf, Set)>) -> HashSet<CardKey> {
+    sets.into_iter()
+        .flat_map(|(_, set)| {
+            set.cards.into_iter().flat_map(|card| {
+                [
+                    CardKey::new(&card.terms, &card.definitions),
+                    CardKey::new(&card.definitions, &card.terms),
+                ]
+            })
+        })
+        .collect()
+}
+
+/// Remember in the database which cards are in each set, so that `revise db sets` can list them
+/// without reading the set files again. Sets with cards left out by `--except` or `--only-in`
+/// shouldn't be recorded, since the cards that are left aren't the whole set.
+fn record_sets(
+    database: &mut Database,
+    sets: &[(PathBuf, Set)],
+    reporter: &mut impl Reporter,
+) -> Result<(), ()> {
+    for (path, set) in sets {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        database
+            .record_set(
+                &path,
+                &set.title,
+                &set.cards
+                    .iter()
+                    .map(|card| CardKey::new(&card.terms, &card.definitions))
+                    .collect::<Vec<_>>(),
+            )
+            .map_err(|e| reporter.error_chain(e))?;
+    }
     Ok(())
 }
 
+fn check(lint: bool, paths: Vec<PathBuf>, reporter: &mut impl Reporter) -> Result<(), ()> {
+    // The sets are only needed while their sources are, so their cards are never copied out.
+    let mut result = Ok(());
+    for path in paths {
+        let Some(source) = record_err(read_set_source(&path, reporter), &mut result) else {
+            continue;
+        };
+        let Some(set) = record_err(parse_set_source(&source, reporter), &mut result) else {
+            continue;
+        };
+        if !lint {
+            continue;
+        }
+        for (original, inverted) in revise_parser::inverted_duplicates_borrowed(&set) {
+            reporter.report(report_parse_error::report_inverted_duplicate(
+                &source, original, inverted,
+            ));
+        }
+    }
+    result
+}
+
 fn read_set_file<P: AsRef<Path>>(path: P, reporter: &mut impl Reporter) -> Result<Set, ()> {
-    let path = path.as_ref();
+    let source = read_set_source(path.as_ref(), reporter)?;
+    parse_set_source(&source, reporter).map(BorrowedSet::into_owned)
+}
 
+/// Read a set file, keeping its path for reporting on it.
+fn read_set_source(path: &Path, reporter: &mut impl Reporter) -> Result<Source, ()> {
     if path.extension() != Some("set".as_ref()) {
         reporter.report(report::warning!(
             "{} is recommended to have a file extension of `.set`: `{}`",
@@ -211,28 +1371,66 @@ fn read_set_file<P: AsRef<Path>>(path: P, reporter: &mut impl Reporter) -> Resul
         reporter.report(report::error!("couldn't read to {}: {}", path.display(), e));
     })?;
 
-    revise_parser::parse_set(&text).map_err(|errors| {
-        let source = Source {
-            origin: Some(path.to_string_lossy().into_owned()),
-            text,
-        };
+    Ok(Source {
+        origin: Some(path.to_string_lossy().into_owned()),
+        text,
+    })
+}
 
+/// Parse a set file that has been read, reporting its warnings and errors.
+fn parse_set_source<'a>(
+    source: &'a Source,
+    reporter: &mut impl Reporter,
+) -> Result<BorrowedSet<'a>, ()> {
+    let (set, warnings) = revise_parser::parse_set_borrowed_with_warnings(&source.text);
+    for warning in warnings {
+        reporter.report(report_parse_error::report_parse_warning(source, warning));
+    }
+    set.map_err(|errors| {
         for error in errors {
-            reporter.report(self::report_parse_error::report_parse_error(&source, error));
+            reporter.report(report_parse_error::report_parse_error(source, error));
         }
     })
 }
 
-fn open_database() -> Result<Database, OpenDatabaseError> {
-    let dirs =
-        ProjectDirs::from("", "", "revise").ok_or(OpenDatabaseErrorInner::NoHomeDirectory)?;
+/// Format a set file, returning its new contents if they differ from the old ones.
+fn format_set_file(path: &Path, reporter: &mut impl Reporter) -> Result<Option<String>, ()> {
+    let text = fs::read_to_string(path).map_err(|e| {
+        reporter.report(report::error!("couldn't read {}: {}", path.display(), e));
+    })?;
+    let formatted = revise_parser::format_source(&text)
+        .map_err(|errors| report_parse_errors(path, text.clone(), errors, reporter))?;
+    Ok(Some(formatted).filter(|formatted| *formatted != text))
+}
+
+fn report_parse_errors(
+    path: &Path,
+    text: String,
+    errors: Vec<revise_parser::ParseError>,
+    reporter: &mut impl Reporter,
+) {
+    let source = Source {
+        origin: Some(path.to_string_lossy().into_owned()),
+        text,
+    };
+
+    for error in errors {
+        reporter.report(self::report_parse_error::report_parse_error(&source, error));
+    }
+}
+
+fn open_database(config: &Config) -> Result<Database, OpenDatabaseError> {
+    let database_path = match &config.database {
+        Some(path) => path.clone(),
+        None => revise_database::default_path().ok_or(OpenDatabaseErrorInner::NoHomeDirectory)?,
+    };
 
-    fs::create_dir_all(dirs.data_dir()).map_err(|source| OpenDatabaseErrorInner::CreateDir {
-        path: dirs.data_dir().to_owned(),
+    let data_dir = database_path.parent().unwrap();
+    fs::create_dir_all(data_dir).map_err(|source| OpenDatabaseErrorInner::CreateDir {
+        path: data_dir.to_owned(),
         source,
     })?;
 
-    let database_path = dirs.data_dir().join("data.sqlite3");
     Ok(Database::open(database_path).map_err(OpenDatabaseErrorInner::Open)?)
 }
 
@@ -254,33 +1452,84 @@ enum OpenDatabaseErrorInner {
     Open(revise_database::OpenError),
 }
 
-struct Weights([f64; 4]);
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "Vec<f64>")]
+struct Weights(Vec<f64>);
 impl FromStr for Weights {
     type Err = anyhow::Error;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let mut parts = input.split(',');
-        let parts = (|| {
-            let array = [parts.next()?, parts.next()?, parts.next()?, parts.next()?];
-            if parts.next().is_some() {
-                return None;
-            }
-            Some(array)
-        })()
-        .context("expected 4 weights")?;
-
-        let [a, b, c, d] = parts.map(|s| s.trim().parse::<f64>());
-        let weights = [a?, b?, c?, d?];
+        let weights = input
+            .split(',')
+            .map(parse_weight)
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::try_from(weights)
+    }
+}
+impl TryFrom<Vec<f64>> for Weights {
+    type Error = anyhow::Error;
 
+    fn try_from(weights: Vec<f64>) -> Result<Self, Self::Error> {
         anyhow::ensure!(
             weights.iter().all(|&w| w > 0.0 && w.is_normal()),
             "all weights must be normal and positive"
         );
+        anyhow::ensure!(
+            (2..=Levels::LIMIT.count()).contains(&weights.len()),
+            "expected between 2 and {} weights",
+            Levels::LIMIT.count(),
+        );
 
         Ok(Self(weights))
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(try_from = "f64")]
+struct OrderTolerance(f64);
+impl FromStr for OrderTolerance {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::try_from(input.trim().parse::<f64>()?)
+    }
+}
+impl TryFrom<f64> for OrderTolerance {
+    type Error = anyhow::Error;
+
+    fn try_from(tolerance: f64) -> Result<Self, Self::Error> {
+        anyhow::ensure!(
+            (0.0..=1.0).contains(&tolerance),
+            "the order tolerance must be between 0 and 1"
+        );
+        Ok(Self(tolerance))
+    }
+}
+
+fn parse_weight(input: &str) -> anyhow::Result<f64> {
+    let weight = input.trim().parse::<f64>()?;
+    anyhow::ensure!(
+        weight > 0.0 && weight.is_normal(),
+        "all weights must be normal and positive"
+    );
+    Ok(weight)
+}
+
+fn parse_probability(input: &str) -> anyhow::Result<f64> {
+    let probability = input.trim().parse::<f64>()?;
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&probability),
+        "the probability must be between 0 and 1"
+    );
+    Ok(probability)
+}
+
+fn parse_months(input: &str) -> anyhow::Result<u32> {
+    let months = input.trim().parse::<u32>()?;
+    anyhow::ensure!(months <= 1200, "the calendar can show at most 1200 months");
+    Ok(months)
+}
+
 #[test]
 #[allow(clippy::float_cmp)]
 fn test_weights() {
@@ -292,6 +1541,71 @@ fn test_weights() {
         "\t2.5 , 6 , 8,0.001\n".parse::<Weights>().unwrap().0,
         [2.5, 6., 8., 0.001]
     );
+    assert_eq!("1,2,3".parse::<Weights>().unwrap().0, [1., 2., 3.]);
+    assert!("1".parse::<Weights>().is_err());
+    assert!("1,-2".parse::<Weights>().is_err());
+
+    assert_eq!(parse_probability("0.25").unwrap(), 0.25);
+    assert!(parse_probability("1.5").is_err());
+    assert!(parse_probability("NaN").is_err());
+
+    assert_eq!(parse_months("12").unwrap(), 12);
+    assert!(parse_months("1201").is_err());
+    assert!(parse_months("4294967295").is_err());
+}
+
+#[test]
+fn test_plan_leaves_database_untouched() {
+    struct PanicReporter;
+    impl Reporter for PanicReporter {
+        fn report(&mut self, report: Report<'_>) {
+            panic!("{}", report.styled(report::Style::default()));
+        }
+        fn set_style(&mut self, _: report::Style) {}
+    }
+
+    let dir = env::temp_dir().join(format!("revise-plan-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("revise.db");
+    let set = revise_parser::parse_set("a - b\nc - d\n").unwrap();
+    let mut database = Database::open(&path).unwrap();
+    let first = set.cards.iter().next().unwrap();
+    let card = CardKey::new(&first.terms, &first.definitions);
+    let knowledge = Knowledge {
+        level: KnowledgeLevel::new(3).unwrap(),
+        safety_net: true,
+    };
+    database.set_knowledge(&card, knowledge).unwrap();
+    drop(database);
+    let before = fs::read(&path).unwrap();
+
+    let Command::Learn(args) =
+        Args::try_parse_from(["revise", "learn", "--plan", "--json", "--levels", "2"])
+            .unwrap()
+            .command
+    else {
+        unreachable!();
+    };
+    let database = Database::open(&path).unwrap();
+    let mut out = Vec::new();
+    plan_learn(
+        &database,
+        vec![(PathBuf::from("test.set"), set)],
+        &args,
+        &Config::default(),
+        &mut out,
+        &mut PanicReporter,
+    )
+    .unwrap();
+    drop(database);
+    assert!(!out.is_empty());
+    assert_eq!(fs::read(&path).unwrap(), before);
+
+    let database = Database::open(&path).unwrap();
+    assert_eq!(database.levels(), Levels::default());
+    assert_eq!(database.knowledge(&card).unwrap(), knowledge);
+    drop(database);
+    fs::remove_dir_all(&dir).unwrap();
 }
 
 fn record_err<T, U, E>(res: Result<T, E>, other: &mut Result<U, E>) -> Option<T> {
@@ -0,0 +1,174 @@
+//! Recordings of answers said out loud, kept for listening back to. They never affect grading.
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use chrono::Local;
+use serde::Deserialize;
+
+use revise_database::CardKey;
+use revise_parser::{Card, Set};
+
+use crate::config::Config;
+use crate::speech::ExternalCommand;
+
+/// How to record clips, from the `[record-audio]` table of the config file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RecordAudio {
+    /// The command that records a short clip and writes it to its standard output.
+    command: ExternalCommand,
+    /// The file extension to save clips with.
+    #[serde(default = "default_extension")]
+    extension: String,
+}
+
+fn default_extension() -> String {
+    "wav".to_owned()
+}
+
+/// The directory recordings are kept in, next to the knowledge database.
+pub(crate) fn recordings_dir(config: &Config) -> Option<PathBuf> {
+    let database = config
+        .database
+        .clone()
+        .or_else(revise_database::default_path)?;
+    Some(database.parent()?.join("recordings"))
+}
+
+/// The directory the recordings of a card are kept in, which is the same whichever way round the
+/// card is.
+fn card_dir(recordings: &Path, card: &Card) -> PathBuf {
+    let forwards = CardKey::new(&card.terms, &card.definitions);
+    let backwards = CardKey::new(&card.definitions, &card.terms);
+    let key = forwards.as_bytes().min(backwards.as_bytes());
+    recordings.join(format!("{:016x}", fnv1a(key)))
+}
+
+/// The 64-bit FNV-1a hash, which unlike the standard library's hashes is the same in every
+/// version of Rust.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Record a clip with the configured command and save it with the card's other recordings.
+/// Returns the path it was saved to.
+pub(crate) fn record(
+    recordings: &Path,
+    record_audio: &RecordAudio,
+    card: &Card,
+) -> io::Result<PathBuf> {
+    let dir = card_dir(recordings, card);
+    fs::create_dir_all(&dir)?;
+    let name = Local::now().format("%Y-%m-%d-%H%M%S");
+    let path = dir.join(format!("{name}.{}", record_audio.extension));
+
+    let command = &record_audio.command;
+    let status = command
+        .command()
+        .stdin(Stdio::null())
+        .stdout(File::create(&path)?)
+        .stderr(Stdio::null())
+        .status();
+    let failed = match status {
+        Ok(status) if status.success() => return Ok(path),
+        Ok(status) => io::Error::other(format!("`{command}` failed with {status}")),
+        Err(e) => io::Error::new(e.kind(), format!("couldn't run `{command}`: {e}")),
+    };
+    // Don't leave an empty or partial clip behind.
+    let _ = fs::remove_file(&path);
+    Err(failed)
+}
+
+/// Write every recording of the cards in the sets, under the card it is of.
+pub(crate) fn list(
+    recordings: &Path,
+    sets: &[(PathBuf, Set)],
+    mut out: impl io::Write,
+) -> io::Result<()> {
+    let mut any = false;
+    for (_, set) in sets {
+        let mut cards = set.cards.iter().collect::<Vec<_>>();
+        cards.sort_by_key(|card| card.line);
+        for card in cards {
+            let mut clips = match fs::read_dir(card_dir(recordings, card)) {
+                Ok(entries) => entries
+                    .map(|entry| Ok(entry?.path()))
+                    .collect::<io::Result<Vec<_>>>()?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            if clips.is_empty() {
+                continue;
+            }
+            clips.sort();
+            any = true;
+            writeln!(out, "{}", revise_parser::format_card(card).unwrap())?;
+            for clip in clips {
+                writeln!(out, "  {}", clip.display())?;
+            }
+        }
+    }
+    if !any {
+        writeln!(out, "There are no recordings of these cards.")?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_record() {
+    let recordings =
+        std::env::temp_dir().join(format!("revise-test-record-{}", std::process::id()));
+    let set = revise_parser::parse_set("Colours\n\nrouge - red\nbleu - blue\n").unwrap();
+    let sets = [(PathBuf::from("colours.set"), set)];
+    let card = |term: &str| {
+        sets[0]
+            .1
+            .cards
+            .iter()
+            .find(|card| card.terms.contains(term))
+            .unwrap()
+    };
+    let record_audio = |words: &[&str], extension: &str| RecordAudio {
+        command: ExternalCommand::try_from(words.iter().map(|&w| w.to_owned()).collect::<Vec<_>>())
+            .unwrap(),
+        extension: extension.to_owned(),
+    };
+
+    let mut out = Vec::new();
+    list(&recordings, &sets, &mut out).unwrap();
+    assert_eq!(out, b"There are no recordings of these cards.\n");
+
+    let path = record(
+        &recordings,
+        &record_audio(&["echo", "clip"], "txt"),
+        card("rouge"),
+    )
+    .unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "clip\n");
+    assert!(record(&recordings, &record_audio(&["false"], "txt"), card("bleu")).is_err());
+
+    let inverted = Card {
+        terms: card("rouge").definitions.clone(),
+        definitions: card("rouge").terms.clone(),
+        ..card("rouge").clone()
+    };
+    assert_eq!(
+        card_dir(&recordings, &inverted),
+        card_dir(&recordings, card("rouge"))
+    );
+
+    let mut out = Vec::new();
+    list(&recordings, &sets, &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!("rouge - red\n  {}\n", path.display()),
+    );
+
+    fs::remove_dir_all(recordings).unwrap();
+}
@@ -0,0 +1,142 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use revise_parser::{Card, Set, SetMetadata};
+
+/// Several sets combined into one.
+#[derive(Debug)]
+pub struct Merged {
+    /// The source of the merged set file.
+    pub source: String,
+    /// Descriptions of the duplicates and conflicts that were resolved.
+    pub warnings: Vec<String>,
+}
+
+/// Combine sets into the source of one set file, with their cards in the order of the files they
+/// came from. Cards with the same terms and definitions as an earlier card are left out, and the
+/// title is the first set's unless one is given.
+pub fn merge(sets: &[(PathBuf, Set)], title: Option<&str>) -> anyhow::Result<Merged> {
+    let mut warnings = Vec::new();
+
+    let title = title.unwrap_or_else(|| {
+        let first = &sets[0].1.title;
+        if sets.iter().any(|(_, set)| set.title != *first) {
+            warnings.push(format!(
+                "the sets have different titles, so the first, `{first}`, was used; \
+                 pass `--title` to choose another"
+            ));
+        }
+        first
+    });
+
+    let mut seen = HashMap::<(&BTreeSet<String>, &BTreeSet<String>), &Path>::new();
+    let mut cards = Vec::new();
+    for (path, set) in sets {
+        let mut set_cards = set.cards.iter().collect::<Vec<_>>();
+        set_cards.sort_by_key(|card| card.line);
+        for card in set_cards {
+            if let Some(first) = seen.get(&(&card.terms, &card.definitions)) {
+                warnings.push(format!(
+                    "`{}` in {} is already in {}, so it was left out",
+                    format_card(card),
+                    path.display(),
+                    first.display(),
+                ));
+                continue;
+            }
+            seen.insert((&card.terms, &card.definitions), path);
+            cards.push(card);
+        }
+    }
+
+    let metadata = merge_metadata(sets, &mut warnings);
+    let Some(source) = revise_parser::format_set(title, &metadata, cards) else {
+        anyhow::bail!("`{title}` can't be the title of a set");
+    };
+
+    Ok(Merged { source, warnings })
+}
+
+/// Keep each piece of metadata that every set giving it agrees on.
+fn merge_metadata(sets: &[(PathBuf, Set)], warnings: &mut Vec<String>) -> SetMetadata {
+    fn agreed<T: PartialEq + Clone>(
+        name: &str,
+        values: impl Iterator<Item = Option<T>>,
+        warnings: &mut Vec<String>,
+    ) -> Option<T> {
+        let mut values = values.flatten();
+        let first = values.next()?;
+        if values.any(|value| value != first) {
+            warnings.push(format!(
+                "the sets have different {name}s, so it was left out"
+            ));
+            return None;
+        }
+        Some(first)
+    }
+
+    let metadata = sets.iter().map(|(_, set)| &set.metadata);
+    SetMetadata {
+        author: agreed(
+            "author",
+            metadata.clone().map(|m| m.author.clone()),
+            warnings,
+        ),
+        language: agreed(
+            "language",
+            metadata.clone().map(|m| m.language.clone()),
+            warnings,
+        ),
+        version: agreed("version", metadata.clone().map(|m| m.version), warnings),
+        optional_parentheses: agreed(
+            "parentheses setting",
+            metadata.map(|m| Some(m.optional_parentheses)),
+            warnings,
+        )
+        .unwrap_or(false),
+    }
+}
+
+fn format_card(card: &Card) -> String {
+    revise_parser::format_card(card).expect("parsed cards can be formatted")
+}
+
+#[test]
+fn test_merge() {
+    let set = |path: &str, source: &str| {
+        (
+            PathBuf::from(path),
+            revise_parser::parse_set(source).unwrap(),
+        )
+    };
+    let sets = [
+        set("a.set", "Words\n#! language: fr\n\nz - 3\nx - 1\n"),
+        set(
+            "b.set",
+            "Words\n#! language: fr\n#! version: 2\n\ny - 2\nx - 1 !2\n",
+        ),
+    ];
+    let merged = merge(&sets, None).unwrap();
+    assert_eq!(
+        merged.source,
+        "Words\n#! language: fr\n#! version: 2\n\nz - 3\nx - 1\ny - 2\n",
+    );
+    assert_eq!(
+        merged.warnings,
+        ["`x - 1 !2` in b.set is already in a.set, so it was left out"],
+    );
+    revise_parser::parse_set(&merged.source).unwrap();
+
+    let sets = [
+        set("a.set", "A\n#! author: me\n\nx - 1\n"),
+        set("b.set", "B\n#! author: you\n\ny - 2\n"),
+    ];
+    let merged = merge(&sets, None).unwrap();
+    assert_eq!(merged.source, "A\n\nx - 1\ny - 2\n");
+    assert_eq!(merged.warnings.len(), 2);
+    assert_eq!(
+        merge(&sets, Some("C")).unwrap().source,
+        "C\n\nx - 1\ny - 2\n",
+    );
+    assert!(merge(&sets, Some("# C")).is_err());
+}
@@ -0,0 +1,122 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::{Config, WhenComplete};
+use crate::profile::ProfileName;
+use crate::report;
+
+/// The settings the command line resolves from its flags and the configuration file, for editor
+/// plugins to use the same ones.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Meta<'a> {
+    config_file: Option<PathBuf>,
+    profile: Option<String>,
+    database: Option<PathBuf>,
+    sets: &'a [PathBuf],
+    weights: Option<&'a [f64]>,
+    typo_tolerance: usize,
+    order_tolerance: f64,
+    when_complete: WhenComplete,
+    report_style: report::Style,
+}
+
+pub fn meta(
+    config: &Config,
+    config_file: Option<PathBuf>,
+    profile: Option<&ProfileName>,
+    json: bool,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let meta = Meta {
+        config_file,
+        profile: profile.map(ToString::to_string),
+        database: config
+            .database
+            .clone()
+            .or_else(revise_database::default_path),
+        sets: &config.sets,
+        weights: config.weights.as_ref().map(|weights| &*weights.0),
+        typo_tolerance: config.typo_tolerance,
+        order_tolerance: config.order_tolerance.0,
+        when_complete: config.when_complete,
+        report_style: config.report_style,
+    };
+
+    if json {
+        serde_json::to_writer_pretty(&mut out, &meta)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    let path = |path: Option<&Path>| path.map_or("none".to_owned(), |p| p.display().to_string());
+    writeln!(out, "config file: {}", path(meta.config_file.as_deref()))?;
+    writeln!(
+        out,
+        "profile: {}",
+        meta.profile.as_deref().unwrap_or("none")
+    )?;
+    writeln!(out, "database: {}", path(meta.database.as_deref()))?;
+    if meta.sets.is_empty() {
+        writeln!(out, "sets: none")?;
+    } else {
+        let sets = meta.sets.iter().map(|set| set.display().to_string());
+        writeln!(out, "sets: {}", sets.collect::<Vec<_>>().join(", "))?;
+    }
+    match meta.weights {
+        Some(weights) => {
+            let weights = weights.iter().map(ToString::to_string);
+            writeln!(out, "weights: {}", weights.collect::<Vec<_>>().join(", "))?;
+        }
+        None => writeln!(out, "weights: default")?,
+    }
+    writeln!(out, "typo tolerance: {}", meta.typo_tolerance)?;
+    writeln!(out, "order tolerance: {}", meta.order_tolerance)?;
+    let when_complete = match meta.when_complete {
+        WhenComplete::Demote => "demote",
+        WhenComplete::Keep => "keep",
+    };
+    writeln!(out, "when complete: {when_complete}")?;
+    let report_style = match meta.report_style {
+        report::Style::Annotated => "annotated",
+        report::Style::Short => "short",
+    };
+    writeln!(out, "report style: {report_style}")?;
+    Ok(())
+}
+
+#[test]
+fn test_meta() {
+    let config = Config {
+        database: Some(PathBuf::from("/data.sqlite3")),
+        sets: vec![PathBuf::from("/a.set"), PathBuf::from("/b.set")],
+        ..Config::default()
+    };
+    let profile = "sam".parse::<ProfileName>().unwrap();
+    let run = |json| {
+        let mut out = Vec::new();
+        meta(&config, None, Some(&profile), json, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+
+    assert_eq!(
+        run(false),
+        "config file: none\n\
+        profile: sam\n\
+        database: /data.sqlite3\n\
+        sets: /a.set, /b.set\n\
+        weights: default\n\
+        typo tolerance: 0\n\
+        order tolerance: 0\n\
+        when complete: demote\n\
+        report style: annotated\n",
+    );
+    let json = serde_json::from_str::<serde_json::Value>(&run(true)).unwrap();
+    assert_eq!(json["database"], "/data.sqlite3");
+    assert_eq!(json["sets"][1], "/b.set");
+    assert_eq!(json["weights"], serde_json::Value::Null);
+    assert_eq!(json["when-complete"], "demote");
+    assert_eq!(json["report-style"], "annotated");
+}
@@ -0,0 +1,104 @@
+use std::io;
+
+use crossterm::style::Stylize;
+use serde::Serialize;
+
+use revise_engine::{Engine, KnowledgeStore};
+
+#[derive(Serialize)]
+struct Plan {
+    cards: Vec<CardRow>,
+    expected_questions: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct CardRow {
+    terms: Vec<String>,
+    definitions: Vec<String>,
+    level: u8,
+    safety_net: bool,
+    at_risk: bool,
+    priority: u32,
+    probability: f64,
+}
+
+/// Show how likely each card is to be asked next, most likely first, and how many questions it
+/// would take to master them all.
+pub fn plan(
+    engine: &Engine<impl KnowledgeStore>,
+    json: bool,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let plan = engine.plan()?;
+    let mut cards = plan
+        .cards
+        .iter()
+        .map(|planned| CardRow {
+            terms: planned.card.terms.iter().cloned().collect(),
+            definitions: planned.card.definitions.iter().cloned().collect(),
+            level: planned.knowledge.level.get(),
+            safety_net: planned.knowledge.safety_net,
+            at_risk: planned.at_risk,
+            priority: planned.card.priority,
+            probability: planned.probability,
+        })
+        .collect::<Vec<_>>();
+    cards.sort_by(|a, b| {
+        b.probability
+            .total_cmp(&a.probability)
+            .then_with(|| a.terms.cmp(&b.terms))
+    });
+    let plan = Plan {
+        cards,
+        expected_questions: plan.expected_questions,
+    };
+
+    if json {
+        serde_json::to_writer_pretty(&mut out, &plan)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    writeln!(out, "{}", "Chance  Level          Priority  Card".bold())?;
+    for card in &plan.cards {
+        let level = format!(
+            "{}{}",
+            card.level,
+            if card.at_risk { " (at risk)" } else { "" }
+        );
+        let row = format!(
+            "{:>5.1}%  {:<13}  {:>8}  {} - {}",
+            card.probability * 100.0,
+            level,
+            card.priority,
+            card.terms.join(", "),
+            card.definitions.join(", "),
+        );
+        if card.probability > 0.0 {
+            writeln!(out, "{row}")?;
+        } else {
+            writeln!(out, "{}", row.dim())?;
+        }
+    }
+
+    writeln!(out)?;
+    let eligible = plan.cards.iter().filter(|card| card.probability > 0.0);
+    writeln!(
+        out,
+        "{} of {} cards can be asked next.",
+        eligible.count(),
+        plan.cards.len(),
+    )?;
+    match plan.expected_questions {
+        Some(questions) => writeln!(
+            out,
+            "Mastering every card takes about {questions:.0} questions if every answer is correct.",
+        )?,
+        None => writeln!(
+            out,
+            "Some cards will never be mastered, because their weight is zero.",
+        )?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use revise_database::Database;
+
+/// The name of a profile: letters, digits, `-` and `_`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileName(String);
+
+impl ProfileName {
+    /// The path of the profile's database in the given profiles directory.
+    pub fn path(&self, profiles_dir: &Path) -> PathBuf {
+        profiles_dir.join(format!("{}.sqlite3", self.0))
+    }
+}
+
+impl FromStr for ProfileName {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        anyhow::ensure!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_'),
+            "profile names may only contain letters, digits, `-` and `_`",
+        );
+        Ok(Self(name.to_owned()))
+    }
+}
+
+impl Display for ProfileName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[test]
+fn test_profile_name() {
+    assert_eq!(
+        "alex_2"
+            .parse::<ProfileName>()
+            .unwrap()
+            .path(Path::new("p")),
+        Path::new("p/alex_2.sqlite3")
+    );
+    assert!("".parse::<ProfileName>().is_err());
+    assert!("../x".parse::<ProfileName>().is_err());
+    assert!("a b".parse::<ProfileName>().is_err());
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// List the profiles that have been created.
+    List,
+    /// Create a new profile.
+    Create { name: ProfileName },
+    /// Delete a profile and all of its recorded knowledge.
+    Delete { name: ProfileName },
+}
+
+pub fn profile(
+    command: Command,
+    profiles_dir: &Path,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    match command {
+        Command::List => {
+            for name in list(profiles_dir)? {
+                writeln!(out, "{name}")?;
+            }
+        }
+        Command::Create { name } => {
+            let path = name.path(profiles_dir);
+            anyhow::ensure!(!path.exists(), "profile `{name}` already exists");
+            fs::create_dir_all(profiles_dir)?;
+            Database::open(path)?;
+            writeln!(
+                out,
+                "Created profile `{name}`; use it with `--profile {name}`."
+            )?;
+        }
+        Command::Delete { name } => {
+            let path = name.path(profiles_dir);
+            anyhow::ensure!(path.exists(), "profile `{name}` does not exist");
+            fs::remove_file(path)?;
+            writeln!(out, "Deleted profile `{name}`.")?;
+        }
+    }
+    Ok(())
+}
+
+/// The names of every profile in the profiles directory, sorted.
+fn list(profiles_dir: &Path) -> io::Result<Vec<String>> {
+    let entries = match fs::read_dir(profiles_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension() == Some("sqlite3".as_ref()) {
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(name.to_owned());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[test]
+fn test_profile() {
+    let dir = std::env::temp_dir().join(format!("revise-profile-test-{}", std::process::id()));
+    let run = |command| {
+        let mut out = Vec::new();
+        profile(command, &dir, &mut out).map(|()| String::from_utf8(out).unwrap())
+    };
+    let name = |name: &str| name.parse::<ProfileName>().unwrap();
+
+    assert_eq!(run(Command::List).unwrap(), "");
+    run(Command::Create { name: name("sam") }).unwrap();
+    run(Command::Create { name: name("alex") }).unwrap();
+    assert!(run(Command::Create { name: name("sam") }).is_err());
+    assert_eq!(run(Command::List).unwrap(), "alex\nsam\n");
+
+    run(Command::Delete { name: name("sam") }).unwrap();
+    assert!(run(Command::Delete { name: name("sam") }).is_err());
+    assert_eq!(run(Command::List).unwrap(), "alex\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -9,11 +9,27 @@ use std::ops::Range;
 use annotate_snippets::display_list::{DisplayList, FormatOptions};
 use annotate_snippets::snippet::{self, Snippet};
 
+use serde::{Deserialize, Serialize};
+
+use revise_parser::LineCol;
+
 pub use annotate_snippets::snippet::AnnotationType;
 
+/// How reports are shown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ArgEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Style {
+    /// Show the source around the error, with annotations.
+    #[default]
+    Annotated,
+    /// One line per report, like `file:line:col: error[E0007]: message`, for editors and logs.
+    Short,
+}
+
 #[must_use]
 pub struct Report<'a> {
     pub title: Annotation<'a>,
+    code: Option<&'static str>,
     sections: Vec<Section<'a>>,
     footers: Vec<Annotation<'a>>,
 }
@@ -22,6 +38,7 @@ impl<'a> Report<'a> {
     pub fn new(title: Annotation<'a>) -> Self {
         Self {
             title,
+            code: None,
             sections: Vec::new(),
             footers: Vec::new(),
         }
@@ -33,6 +50,11 @@ impl<'a> Report<'a> {
         Self::new(Annotation::warning(title))
     }
 
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
     pub fn with_section(mut self, section: Section<'a>) -> Self {
         assert!(!section.labels.is_empty());
         self.sections.push(section);
@@ -47,21 +69,120 @@ impl<'a> Report<'a> {
     pub fn error_chain(error: impl Error) -> Self {
         let mut this = Self::new(Annotation::error(error.to_string()));
 
+        let mut help = None;
         let mut parent: &dyn Error = &error;
         while let Some(source) = parent.source() {
             this = this.with_footer(Annotation::note(format!("caused by: {source}")));
+            help = help.or_else(|| revise_database::help(source));
             parent = source;
         }
 
+        if let Some(help) = help {
+            this = this.with_footer(Annotation::help(help));
+        }
         this
     }
+
+    /// Show the report in the given style.
+    pub fn styled(&self, style: Style) -> impl Display + '_ {
+        Styled {
+            report: self,
+            style,
+        }
+    }
+
+    fn fmt_short(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let labels = self.sections.iter().flat_map(|section| {
+            let source = section.source;
+            section.labels.iter().map(move |label| (source, label))
+        });
+        let primary = labels
+            .clone()
+            .find(|(_, label)| label.annotation.annotation_type == self.title.annotation_type)
+            .or_else(|| labels.clone().next());
+        if let Some((source, label)) = primary {
+            if let Some(origin) = &source.origin {
+                write!(f, "{origin}:")?;
+            }
+            write!(f, "{}: ", LineCol::of(&source.text, label.span.start))?;
+        }
+
+        write!(f, "{}", type_name(self.title.annotation_type))?;
+        if let Some(code) = self.code {
+            write!(f, "[{code}]")?;
+        }
+        write!(f, ": {}", one_line(&self.title.message))?;
+        for footer in &self.footers {
+            let name = type_name(footer.annotation_type);
+            write!(f, "; {name}: {}", one_line(&footer.message))?;
+        }
+        writeln!(f)
+    }
+}
+
+struct Styled<'r, 'a> {
+    report: &'r Report<'a>,
+    style: Style,
+}
+
+impl Display for Styled<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.style {
+            Style::Annotated => self.report.fmt(f),
+            Style::Short => self.report.fmt_short(f),
+        }
+    }
+}
+
+fn type_name(annotation_type: AnnotationType) -> &'static str {
+    match annotation_type {
+        AnnotationType::Error => "error",
+        AnnotationType::Warning => "warning",
+        AnnotationType::Info => "info",
+        AnnotationType::Note => "note",
+        AnnotationType::Help => "help",
+    }
+}
+
+fn one_line(message: &str) -> Cow<'_, str> {
+    if message.contains('\n') {
+        Cow::Owned(message.lines().collect::<Vec<_>>().join(" "))
+    } else {
+        Cow::Borrowed(message)
+    }
+}
+
+#[test]
+fn test_short() {
+    let source = Source {
+        origin: Some("french.set".to_owned()),
+        text: "Title\né - b\nc-d\n".to_owned(),
+    };
+    let report = Report::error("missing whitespace")
+        .with_code("E0005")
+        .with_section(
+            source
+                .label(6..8, Annotation::warning("first"))
+                .label(14..15, Annotation::error("dash here")),
+        )
+        .with_footer(Annotation::help("add spaces\naround it"));
+    assert_eq!(
+        report.styled(Style::Short).to_string(),
+        "french.set:3:2: error[E0005]: missing whitespace; help: add spaces around it\n"
+    );
+    assert_eq!(
+        Report::warning("no sections")
+            .styled(Style::Short)
+            .to_string(),
+        "warning: no sections\n"
+    );
 }
 
 impl Display for Report<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let display_list = DisplayList::from(Snippet {
             title: Some(snippet::Annotation {
-                id: None,
+                id: self.code,
                 label: Some(&self.title.message),
                 annotation_type: self.title.annotation_type,
             }),
@@ -218,13 +339,13 @@ impl Source {
 }
 
 fn context_to(span: Range<usize>, s: &str) -> (Range<usize>, usize) {
-    let (line_num, start_line) = s
+    let start_line = s
         .lines()
-        .enumerate()
-        .map(|(i, line)| (i + 1, offset_of(line, s)))
-        .take_while(|&(_, line)| line <= span.start)
+        .map(|line| offset_of(line, s))
+        .take_while(|&line| line <= span.start)
         .last()
-        .unwrap_or((1, 0));
+        .unwrap_or(0);
+    let line_num = LineCol::of(s, start_line).line;
     let end_line = s
         .lines()
         .map(|line| offset_of(line, s))
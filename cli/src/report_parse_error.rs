@@ -1,11 +1,12 @@
 use std::ops::Range;
 
-use revise_parser::ParseError;
+use revise_parser::{ParseError, ParseWarning};
 
 use crate::report::{Annotation, Report, Source};
 
 pub(crate) fn report_parse_error(source: &Source, error: ParseError) -> Report<'_> {
-    match error {
+    let code = error.code();
+    let report = match error {
         ParseError::NoTitle { line } => no_title(source, line),
         ParseError::EmptySet => empty_set(source),
         ParseError::DuplicateCard {
@@ -31,7 +32,72 @@ pub(crate) fn report_parse_error(source: &Source, error: ParseError) -> Report<'
         }
         ParseError::ExpectedSpace { character, span } => expected_space(source, character, span),
         ParseError::MissingLineFeed { cr_span } => missing_line_feed(source, cr_span),
-    }
+        ParseError::MalformedMetadata { span } => malformed_metadata(source, span),
+        ParseError::DuplicateMetadataKey {
+            original,
+            duplicate,
+        } => duplicate_metadata_key(source, original, duplicate),
+        ParseError::InvalidMetadataValue { span } => invalid_metadata_value(source, span),
+        ParseError::InvalidPriority { span } => invalid_priority(source, span),
+        ParseError::MalformedTable { span } => malformed_table(source, span),
+    };
+    report.with_code(code)
+}
+
+pub(crate) fn report_parse_warning(source: &Source, warning: ParseWarning) -> Report<'_> {
+    let code = warning.code();
+    let report = match warning {
+        ParseWarning::EscapedComma { span } => escaped_comma(source, span),
+        ParseWarning::UnknownMetadataKey { key, span } => unknown_metadata_key(source, &key, span),
+    };
+    report.with_code(code)
+}
+
+fn escaped_comma(source: &Source, span: Range<usize>) -> Report<'_> {
+    Report::new(Annotation::note("comma escaped outside quotes"))
+        .with_section(source.label(
+            span,
+            Annotation::note("this comma is part of the option, not a separator"),
+        ))
+        .with_footer(Annotation::help(
+            "quote the option instead, like `\"1, 2, 3\"`, or run `revise fmt` to do it for you",
+        ))
+}
+
+/// A warning that the card on one line is the card on another line inverted.
+pub(crate) fn report_inverted_duplicate(
+    source: &Source,
+    original: usize,
+    inverted: usize,
+) -> Report<'_> {
+    Report::warning("card is another card inverted")
+        .with_section(
+            source
+                .label(
+                    line_span(&source.text, original),
+                    Annotation::note("original card declared here"),
+                )
+                .label(
+                    line_span(&source.text, inverted),
+                    Annotation::warning("the same card with its terms and definitions swapped"),
+                ),
+        )
+        .with_footer(Annotation::help(
+            "consider deleting one of them, and passing `--invert` or `--both-directions` to \
+            learn the other way round",
+        ))
+}
+
+/// The span of a line of the source, starting from 1, without its line ending.
+fn line_span(text: &str, line: usize) -> Range<usize> {
+    let start = text
+        .split_inclusive('\n')
+        .take(line - 1)
+        .map(str::len)
+        .sum::<usize>();
+    let rest = &text[start..];
+    let end = start + rest.find('\n').unwrap_or(rest.len());
+    start..text[..end].strip_suffix('\r').map_or(end, str::len)
 }
 
 fn no_title(source: &Source, line: Range<usize>) -> Report<'_> {
@@ -163,3 +229,59 @@ fn missing_line_feed(source: &Source, cr_span: Range<usize>) -> Report<'_> {
         Annotation::error("found a bare CR with no following LF"),
     ))
 }
+
+fn malformed_metadata(source: &Source, span: Range<usize>) -> Report<'_> {
+    Report::error("malformed metadata")
+        .with_section(source.label(span, Annotation::error("expected `key: value`")))
+        .with_footer(Annotation::help(
+            "metadata lines look like `#! language: fr`; use `#` for a regular comment",
+        ))
+}
+
+fn unknown_metadata_key<'a>(source: &'a Source, key: &str, span: Range<usize>) -> Report<'a> {
+    Report::warning(format!("unknown metadata key `{key}`"))
+        .with_section(source.label(
+            span,
+            Annotation::warning("unknown key, so this line is a comment"),
+        ))
+        .with_footer(Annotation::help(
+            "known keys are `author`, `language`, `version` and `parentheses`; use `#` for a \
+            regular comment",
+        ))
+}
+
+fn duplicate_metadata_key(
+    source: &Source,
+    original: Range<usize>,
+    duplicate: Range<usize>,
+) -> Report<'_> {
+    Report::error("duplicate metadata key").with_section(
+        source
+            .label(original, Annotation::warning("original key here"))
+            .label(duplicate, Annotation::error("key given again here")),
+    )
+}
+
+fn invalid_metadata_value(source: &Source, span: Range<usize>) -> Report<'_> {
+    Report::error("invalid metadata value").with_section(source.label(
+        span,
+        Annotation::error("expected a non-negative whole number"),
+    ))
+}
+
+fn malformed_table(source: &Source, span: Range<usize>) -> Report<'_> {
+    Report::error("malformed table")
+        .with_section(source.label(span, Annotation::error("expected a table")))
+        .with_footer(Annotation::help(
+            "write a table like `\"je | suis; tu | *es\"`, with `;` between rows, `|` between \
+             cells and `*` before at most one cell to make it the only answer",
+        ))
+}
+
+fn invalid_priority(source: &Source, span: Range<usize>) -> Report<'_> {
+    Report::error("invalid card priority")
+        .with_section(source.label(span, Annotation::error("expected a positive whole number")))
+        .with_footer(Annotation::help(
+            "a priority like `!3` makes a card be asked 3 times as often",
+        ))
+}
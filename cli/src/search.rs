@@ -0,0 +1,215 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::PathBuf;
+
+use crossterm::style::Stylize;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+use revise_database::{CardKey, Database, KnowledgeStore as _};
+use revise_parser::{Card, Set};
+
+/// How to match the text being searched for, always ignoring case.
+pub enum Matcher {
+    Plain(String),
+    Regex(Regex),
+    /// Matches text containing the characters in order, with anything between them.
+    Fuzzy(Vec<char>),
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, regex: bool, fuzzy: bool) -> Result<Self, regex::Error> {
+        Ok(if regex {
+            Self::Regex(RegexBuilder::new(pattern).case_insensitive(true).build()?)
+        } else if fuzzy {
+            Self::Fuzzy(pattern.to_lowercase().chars().collect())
+        } else {
+            Self::Plain(pattern.to_lowercase())
+        })
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Self::Plain(pattern) => text.to_lowercase().contains(pattern),
+            Self::Regex(regex) => regex.is_match(text),
+            Self::Fuzzy(pattern) => {
+                let mut text = text.to_lowercase().chars().collect::<Vec<_>>().into_iter();
+                pattern.iter().all(|&c| text.any(|t| t == c))
+            }
+        }
+    }
+
+    pub fn matches_card(&self, card: &Card) -> bool {
+        card.terms
+            .iter()
+            .chain(&card.definitions)
+            .any(|option| self.is_match(option))
+    }
+}
+
+#[test]
+fn test_matcher() {
+    let matches =
+        |pattern, regex, fuzzy, text| Matcher::new(pattern, regex, fuzzy).unwrap().is_match(text);
+
+    assert!(matches("JOUR", false, false, "Bonjour"));
+    assert!(!matches("bjr", false, false, "Bonjour"));
+    assert!(matches("bjr", false, true, "Bonjour"));
+    assert!(!matches("bjrx", false, true, "Bonjour"));
+    assert!(!matches("rjb", false, true, "Bonjour"));
+    assert!(matches("^bon.*r$", true, false, "Bonjour"));
+    assert!(!matches("^jour", true, false, "Bonjour"));
+    assert!(Matcher::new("(", true, false).is_err());
+}
+
+#[derive(Serialize)]
+struct SearchResult {
+    /// The set file containing the card, or `None` if only the database knows about it.
+    file: Option<String>,
+    line: Option<usize>,
+    terms: Vec<String>,
+    definitions: Vec<String>,
+    level: u8,
+    inverted_level: u8,
+}
+
+pub fn search(
+    database: &Database,
+    matcher: &Matcher,
+    sets: &[(PathBuf, Set)],
+    json: bool,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let mut results = Vec::new();
+    let mut in_sets = HashSet::new();
+    for (path, set) in sets {
+        let mut cards = set
+            .cards
+            .iter()
+            .filter(|card| matcher.matches_card(card))
+            .collect::<Vec<_>>();
+        cards.sort_by_key(|card| card.line);
+
+        for card in cards {
+            let key = CardKey::new(&card.terms, &card.definitions);
+            let inverted_key = CardKey::new(&card.definitions, &card.terms);
+            results.push(SearchResult {
+                file: Some(path.display().to_string()),
+                line: Some(card.line),
+                terms: card.terms.iter().cloned().collect(),
+                definitions: card.definitions.iter().cloned().collect(),
+                level: database.knowledge(&key)?.level.get(),
+                inverted_level: database.knowledge(&inverted_key)?.level.get(),
+            });
+        }
+
+        for card in &set.cards {
+            in_sets.insert(CardKey::new(&card.terms, &card.definitions));
+            in_sets.insert(CardKey::new(&card.definitions, &card.terms));
+        }
+    }
+
+    // Cards that were studied but are in none of the sets are found through the database, by
+    // their terms only.
+    let text = match matcher {
+        Matcher::Plain(pattern) => pattern.as_str(),
+        Matcher::Regex(_) | Matcher::Fuzzy(_) => "",
+    };
+    for key in database.search_terms(text)? {
+        if in_sets.contains(&key) {
+            continue;
+        }
+        let Some((terms, definitions)) = database.card_text(&key)? else {
+            continue;
+        };
+        if !terms.iter().any(|term| matcher.is_match(term)) {
+            continue;
+        }
+        let inverted_key = CardKey::new(&key.definitions(), &key.terms());
+        results.push(SearchResult {
+            file: None,
+            line: None,
+            terms,
+            definitions,
+            level: database.knowledge(&key)?.level.get(),
+            inverted_level: database.knowledge(&inverted_key)?.level.get(),
+        });
+    }
+
+    if json {
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        writeln!(out, "No cards match.")?;
+    }
+    for result in results {
+        let mut level = format!("level {}", result.level);
+        if result.inverted_level != 0 {
+            level = format!("{level}, inverted {}", result.inverted_level);
+        }
+        let location = match (result.file, result.line) {
+            (Some(file), Some(line)) => format!("{file}:{line}:"),
+            _ => "(in no set):".to_owned(),
+        };
+        writeln!(
+            out,
+            "{} {} - {} {}",
+            location.dim(),
+            result.terms.join(", ").bold(),
+            result.definitions.join(", "),
+            format!("({level})").dim(),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_search() {
+    use std::collections::BTreeSet;
+
+    let mut database = Database::open_in_memory().unwrap();
+    let set = revise_parser::parse_set("Set\n\nBonjour - hello\nmerci - thanks\n").unwrap();
+    let gone = CardKey::new(&BTreeSet::from(["au revoir"]), &BTreeSet::from(["goodbye"]));
+    database.record_correct(&gone).unwrap();
+    let bonjour = CardKey::new(&BTreeSet::from(["hello"]), &BTreeSet::from(["Bonjour"]));
+    database.record_correct(&bonjour).unwrap();
+    let sets = [(PathBuf::from("a.set"), set)];
+
+    let run = |pattern, regex| {
+        let mut out = Vec::new();
+        let matcher = Matcher::new(pattern, regex, false).unwrap();
+        search(&database, &matcher, &sets, true, &mut out).unwrap();
+        serde_json::from_slice::<serde_json::Value>(&out).unwrap()
+    };
+
+    assert_eq!(
+        run("o", false),
+        serde_json::json!([
+            {
+                "file": "a.set",
+                "line": 3,
+                "terms": ["Bonjour"],
+                "definitions": ["hello"],
+                "level": 0,
+                "inverted_level": 1,
+            },
+            {
+                "file": null,
+                "line": null,
+                "terms": ["au revoir"],
+                "definitions": ["goodbye"],
+                "level": 1,
+                "inverted_level": 0,
+            },
+        ])
+    );
+    assert_eq!(
+        run("^au", true)[0]["terms"],
+        serde_json::json!(["au revoir"])
+    );
+    assert_eq!(run("goodbye", false), serde_json::json!([]));
+}
@@ -0,0 +1,179 @@
+//! Reading prompts aloud and answering out loud, through external speech programs.
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write as _};
+use std::process::{Child, Command, Stdio};
+
+use serde::Deserialize;
+
+/// An external program and its arguments, given in the config file as a list like
+/// `["whisper-listen", "--model", "base"]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "Vec<String>")]
+pub(crate) struct ExternalCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+impl ExternalCommand {
+    pub(crate) fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        command
+    }
+}
+
+impl TryFrom<Vec<String>> for ExternalCommand {
+    type Error = &'static str;
+
+    fn try_from(mut words: Vec<String>) -> Result<Self, Self::Error> {
+        if words.is_empty() {
+            return Err("the program to run must be given");
+        }
+        let program = words.remove(0);
+        Ok(Self {
+            program,
+            args: words,
+        })
+    }
+}
+
+impl Display for ExternalCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.program)?;
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads text aloud with a text-to-speech command, without waiting for it to finish.
+#[derive(Debug)]
+pub(crate) struct Speaker<'a> {
+    command: &'a ExternalCommand,
+    speaking: Option<Child>,
+}
+
+impl<'a> Speaker<'a> {
+    pub(crate) fn new(command: &'a ExternalCommand) -> Self {
+        Self {
+            command,
+            speaking: None,
+        }
+    }
+
+    /// Start reading text aloud, stopping whatever was being read before.
+    ///
+    /// The text is written to the command's standard input, and its language, if known, is in the
+    /// `REVISE_LANGUAGE` environment variable.
+    pub(crate) fn speak(&mut self, text: &str, language: Option<&str>) -> io::Result<()> {
+        self.stop();
+
+        let mut command = self.command.command();
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        match language {
+            Some(language) => command.env("REVISE_LANGUAGE", language),
+            None => command.env_remove("REVISE_LANGUAGE"),
+        };
+        let mut child = command.spawn().map_err(|e| {
+            let command = self.command;
+            io::Error::new(e.kind(), format!("couldn't run `{command}`: {e}"))
+        })?;
+        // The text is far smaller than a pipe's buffer, so this doesn't wait for the command to
+        // read it. Dropping the pipe closes it, so the command sees the end of the text.
+        let written = child.stdin.take().unwrap().write_all(text.as_bytes());
+        self.speaking = Some(child);
+        // A command that exits without reading its input isn't an error.
+        match written {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            written => written,
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut child) = self.speaking.take() {
+            // It has most likely finished already, and there is nothing to do if it can't be
+            // stopped.
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for Speaker<'_> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Run a speech-to-text command and return what it heard.
+///
+/// The command is expected to record from the microphone itself, stopping when the speaker does,
+/// and to print the transcription. Lines are joined with spaces.
+pub(crate) fn transcribe(command: &ExternalCommand) -> io::Result<String> {
+    let output = command
+        .command()
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("couldn't run `{command}`: {e}")))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "`{command}` failed with {}",
+            output.status
+        )));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(unix)]
+#[test]
+fn test_transcribe() {
+    let command = |words: &[&str]| {
+        ExternalCommand::try_from(
+            words
+                .iter()
+                .map(|&word| word.to_owned())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        transcribe(&command(&["printf", "  le chat\\nnoir\\n"])).unwrap(),
+        "le chat noir",
+    );
+    assert!(transcribe(&command(&["false"])).is_err());
+    assert!(transcribe(&command(&["revise-no-such-program"])).is_err());
+    assert!(ExternalCommand::try_from(Vec::new()).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_speaker() {
+    let dir = std::env::temp_dir().join(format!("revise-test-speaker-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let out = dir.join("spoken");
+    let script = format!(
+        "cat > {0}; echo \" $REVISE_LANGUAGE\" >> {0}",
+        out.display()
+    );
+    let command =
+        ExternalCommand::try_from(vec!["sh".to_owned(), "-c".to_owned(), script]).unwrap();
+
+    let mut speaker = Speaker::new(&command);
+    speaker.speak("rouge", Some("fr")).unwrap();
+    speaker.speaking.as_mut().unwrap().wait().unwrap();
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), "rouge fr\n");
+
+    speaker.speak("red", None).unwrap();
+    speaker.speaking.as_mut().unwrap().wait().unwrap();
+    assert_eq!(std::fs::read_to_string(&out).unwrap(), "red \n");
+    drop(speaker);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::Serialize;
+
+use revise_database::{CardKey, Database, HistoryEntry};
+
+use crate::csv;
+
+/// The format to export statistics in.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+/// Answers given more than this long apart are counted as being in different sessions.
+const SESSION_GAP: Duration = Duration::from_mins(30);
+
+#[derive(Serialize)]
+struct CardRow {
+    terms: Vec<String>,
+    definitions: Vec<String>,
+    level: u8,
+    safety_net: bool,
+    attempts: u64,
+    correct: u64,
+    accuracy: Option<f64>,
+    last_seen: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct SessionRow {
+    start: String,
+    end: String,
+    cards: usize,
+    answers: u64,
+    correct: u64,
+    accuracy: f64,
+}
+
+pub fn stats(
+    database: &Database,
+    format: Format,
+    sessions: bool,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    if sessions {
+        let activity =
+            database.recent_activity(UNIX_EPOCH..SystemTime::now() + Duration::from_secs(1))?;
+        let rows = session_rows(&activity);
+        match format {
+            Format::Csv => write_csv(
+                &mut out,
+                &["start", "end", "cards", "answers", "correct", "accuracy"],
+                rows.iter().map(|row| {
+                    vec![
+                        row.start.clone(),
+                        row.end.clone(),
+                        row.cards.to_string(),
+                        row.answers.to_string(),
+                        row.correct.to_string(),
+                        row.accuracy.to_string(),
+                    ]
+                }),
+            )?,
+            Format::Json => write_json(&mut out, &rows)?,
+        }
+        return Ok(());
+    }
+
+    let mut rows = Vec::new();
+    for stats in database.card_stats()? {
+        let Some((terms, definitions)) = database.card_text(&stats.card)? else {
+            continue;
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let accuracy = (stats.attempts != 0).then(|| stats.correct as f64 / stats.attempts as f64);
+        rows.push(CardRow {
+            terms,
+            definitions,
+            level: stats.knowledge.level.get(),
+            safety_net: stats.knowledge.safety_net,
+            attempts: stats.attempts,
+            correct: stats.correct,
+            accuracy,
+            last_seen: stats.last_seen.map(format_time),
+        });
+    }
+    match format {
+        Format::Csv => write_csv(
+            &mut out,
+            &[
+                "terms",
+                "definitions",
+                "level",
+                "safety_net",
+                "attempts",
+                "correct",
+                "accuracy",
+                "last_seen",
+            ],
+            rows.iter().map(|row| {
+                vec![
+                    row.terms.join(", "),
+                    row.definitions.join(", "),
+                    row.level.to_string(),
+                    row.safety_net.to_string(),
+                    row.attempts.to_string(),
+                    row.correct.to_string(),
+                    row.accuracy.map(|a| a.to_string()).unwrap_or_default(),
+                    row.last_seen.clone().unwrap_or_default(),
+                ]
+            }),
+        )?,
+        Format::Json => write_json(&mut out, &rows)?,
+    }
+
+    Ok(())
+}
+
+/// Split the answer history into sessions, separated by gaps of more than [`SESSION_GAP`].
+fn session_rows(activity: &[(CardKey, HistoryEntry)]) -> Vec<SessionRow> {
+    let mut rows = Vec::new();
+    let mut rest = activity;
+    while let Some((first, _)) = rest.split_first() {
+        let len = rest
+            .windows(2)
+            .position(|pair| {
+                let gap = pair[1].1.time.duration_since(pair[0].1.time);
+                gap.is_ok_and(|gap| gap > SESSION_GAP)
+            })
+            .map_or(rest.len(), |i| i + 1);
+        let (session, after) = rest.split_at(len);
+        rest = after;
+
+        let answers = session.len() as u64;
+        let correct = session.iter().filter(|(_, entry)| entry.correct).count() as u64;
+        #[allow(clippy::cast_precision_loss)]
+        let accuracy = correct as f64 / answers as f64;
+        rows.push(SessionRow {
+            start: format_time(first.1.time),
+            end: format_time(session[len - 1].1.time),
+            cards: session
+                .iter()
+                .map(|(card, _)| card)
+                .collect::<HashSet<_>>()
+                .len(),
+            answers,
+            correct,
+            accuracy,
+        });
+    }
+    rows
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+fn write_json(out: &mut impl io::Write, rows: &impl Serialize) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, rows)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn write_csv(
+    out: &mut impl io::Write,
+    header: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> io::Result<()> {
+    csv::write_row(out, header.iter().copied())?;
+    for row in rows {
+        csv::write_row(out, row.iter().map(String::as_str))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_session_rows() {
+    let card = |term: &str| CardKey::new(&[term].into(), &["definition"].into());
+    let entry = |minutes: u64, correct| HistoryEntry {
+        time: UNIX_EPOCH + Duration::from_mins(minutes),
+        correct,
+        hinted: false,
+    };
+    let activity = [
+        (card("a"), entry(0, true)),
+        (card("b"), entry(10, false)),
+        (card("a"), entry(40, true)),
+        (card("a"), entry(100, false)),
+    ];
+
+    let rows = session_rows(&activity);
+    assert_eq!(
+        rows.iter()
+            .map(|row| (row.cards, row.answers, row.correct))
+            .collect::<Vec<_>>(),
+        [(2, 3, 2), (1, 1, 0)]
+    );
+    assert_eq!(rows[0].start, format_time(UNIX_EPOCH));
+    assert_eq!(
+        rows[0].end,
+        format_time(UNIX_EPOCH + SESSION_GAP + SESSION_GAP / 3)
+    );
+    assert!(session_rows(&[]).is_empty());
+}
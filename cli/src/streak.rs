@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{Datelike, Months, NaiveDate};
+use crossterm::style::{style, Stylize};
+
+use revise_database::Database;
+
+use crate::days::StudyDays;
+
+pub fn streak(
+    database: &Database,
+    days: StudyDays,
+    months: u32,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let activity =
+        database.recent_activity(UNIX_EPOCH..SystemTime::now() + Duration::from_secs(1))?;
+
+    let mut answers_per_day = BTreeMap::<NaiveDate, usize>::new();
+    for (_, entry) in activity {
+        *answers_per_day.entry(days.day_of(entry.time)).or_default() += 1;
+    }
+
+    let today = days.today();
+
+    writeln!(
+        out,
+        "{} {}",
+        "Current streak:".bold(),
+        format_days(current_streak(&answers_per_day, today)),
+    )?;
+    writeln!(
+        out,
+        "{} {}",
+        "Longest streak:".bold(),
+        format_days(longest_streak(&answers_per_day)),
+    )?;
+    writeln!(
+        out,
+        "{} {}",
+        "Answers today:".bold(),
+        answers_per_day.get(&today).copied().unwrap_or(0),
+    )?;
+
+    let this_month = today.with_day(1).unwrap();
+    let months = (0..months)
+        .map_while(|months_ago| this_month.checked_sub_months(Months::new(months_ago)))
+        .collect::<Vec<_>>();
+    for month in months.into_iter().rev() {
+        writeln!(out)?;
+        write_month(&mut out, month, &answers_per_day)?;
+    }
+
+    Ok(())
+}
+
+fn format_days(n: usize) -> String {
+    if n == 1 {
+        "1 day".to_owned()
+    } else {
+        format!("{n} days")
+    }
+}
+
+/// The number of consecutive days studied up to today. A streak is not broken until a whole day is
+/// missed, so if today hasn't been studied yet the streak up to yesterday is counted.
+fn current_streak(answers_per_day: &BTreeMap<NaiveDate, usize>, today: NaiveDate) -> usize {
+    let mut day = if answers_per_day.contains_key(&today) {
+        today
+    } else {
+        today.pred_opt().unwrap()
+    };
+
+    let mut streak = 0;
+    while answers_per_day.contains_key(&day) {
+        streak += 1;
+        day = day.pred_opt().unwrap();
+    }
+    streak
+}
+
+fn longest_streak(answers_per_day: &BTreeMap<NaiveDate, usize>) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for &day in answers_per_day.keys() {
+        current = if previous.and_then(|previous| previous.succ_opt()) == Some(day) {
+            current + 1
+        } else {
+            1
+        };
+        longest = longest.max(current);
+        previous = Some(day);
+    }
+
+    longest
+}
+
+#[test]
+fn test_streaks() {
+    let date = |day| NaiveDate::from_ymd_opt(2024, 2, day).unwrap();
+    let answers = |days: &[u32]| days.iter().map(|&day| (date(day), 1)).collect();
+
+    assert_eq!(current_streak(&answers(&[]), date(10)), 0);
+    assert_eq!(current_streak(&answers(&[10]), date(10)), 1);
+    assert_eq!(current_streak(&answers(&[8, 9]), date(10)), 2);
+    assert_eq!(current_streak(&answers(&[7, 9, 10]), date(10)), 2);
+    assert_eq!(current_streak(&answers(&[7, 8]), date(10)), 0);
+
+    assert_eq!(longest_streak(&answers(&[])), 0);
+    assert_eq!(longest_streak(&answers(&[3])), 1);
+    assert_eq!(longest_streak(&answers(&[1, 2, 3, 5, 6])), 3);
+    assert_eq!(longest_streak(&answers(&[1, 3, 4, 5, 6, 8])), 4);
+}
+
+#[test]
+fn test_many_months() {
+    let database = Database::open_in_memory().unwrap();
+    let days = StudyDays::new(&crate::config::Config::default());
+    let mut out = Vec::new();
+    streak(&database, days, 1200, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.matches("Mo Tu We Th Fr Sa Su").count(), 1200);
+}
+
+fn write_month(
+    out: &mut impl io::Write,
+    month: NaiveDate,
+    answers_per_day: &BTreeMap<NaiveDate, usize>,
+) -> io::Result<()> {
+    writeln!(out, "{}", format!("{:^20}", month.format("%B %Y")).bold())?;
+    writeln!(out, "{}", "Mo Tu We Th Fr Sa Su".dim())?;
+
+    let offset = month.weekday().num_days_from_monday();
+    for _ in 0..offset {
+        write!(out, "   ")?;
+    }
+
+    let mut column = offset;
+    for day in month
+        .iter_days()
+        .take_while(|day| day.month() == month.month())
+    {
+        let cell = format!("{:>2}", day.day());
+        let answers = answers_per_day.get(&day).copied().unwrap_or(0);
+        match answers {
+            0 => write!(out, "{}", cell.dim())?,
+            1..=9 => write!(out, "{}", cell.dark_green())?,
+            10..=49 => write!(out, "{}", cell.green().bold())?,
+            _ => write!(out, "{}", style(cell).black().on_green())?,
+        }
+
+        column += 1;
+        if column == 7 {
+            column = 0;
+            writeln!(out)?;
+        } else {
+            write!(out, " ")?;
+        }
+    }
+    if column != 0 {
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
 use std::io;
+use std::str::FromStr;
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::queue;
@@ -7,28 +9,399 @@ use crossterm::{
     cursor,
     terminal::{self, ClearType},
 };
-use unicode_width::UnicodeWidthStr as _;
+use serde::Deserialize;
+use unicode_width::{UnicodeWidthChar as _, UnicodeWidthStr as _};
 
 use Direction::{Left, Right};
 
-pub(crate) fn read_line(mut out: impl io::Write) -> io::Result<Option<String>> {
-    let (start_x, start_y) = cursor::position()?;
+/// Whether the terminal is likely to lack an alternate screen or Unicode box-drawing characters,
+/// judging by the environment variables returned by `var`.
+///
+/// This is the case for terminals that declare themselves dumb, consoles on Windows other than
+/// Windows Terminal and terminals using a non-UTF-8 locale.
+pub(crate) fn is_limited_terminal(windows: bool, var: impl Fn(&str) -> Option<String>) -> bool {
+    let term = var("TERM");
+    if term.as_deref() == Some("dumb") {
+        return true;
+    }
+    if windows {
+        return term.is_none() && var("WT_SESSION").is_none() && var("TERM_PROGRAM").is_none();
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|name| var(name).filter(|value| !value.is_empty()));
+    locale.is_some_and(|locale| {
+        let locale = locale.to_ascii_uppercase();
+        !locale.contains("UTF-8") && !locale.contains("UTF8")
+    })
+}
+
+#[test]
+fn test_is_limited_terminal() {
+    let env = |vars: &'static [(&str, &str)]| {
+        move |name: &str| {
+            let value = vars.iter().find(|(var, _)| *var == name)?.1;
+            Some(value.to_owned())
+        }
+    };
+    assert!(!is_limited_terminal(false, env(&[])));
+    assert!(is_limited_terminal(false, env(&[("TERM", "dumb")])));
+    assert!(!is_limited_terminal(false, env(&[("LANG", "en_GB.UTF-8")])));
+    assert!(!is_limited_terminal(
+        false,
+        env(&[("LC_ALL", ""), ("LANG", "en_US.utf8")])
+    ));
+    assert!(is_limited_terminal(
+        false,
+        env(&[("LC_ALL", "C"), ("LANG", "en_GB.UTF-8")])
+    ));
+    assert!(is_limited_terminal(true, env(&[])));
+    assert!(!is_limited_terminal(true, env(&[("WT_SESSION", "1")])));
+}
+
+/// Terminals narrower than this many columns, like those of SSH sessions from phones, use the
+/// compact layout.
+pub(crate) const COMPACT_WIDTH: u16 = 60;
+
+/// Split text into lines no wider than `width`, breaking between words where possible.
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
     let mut line = String::new();
-    let mut position = 0;
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.width() + 1 + word.width() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        for c in word.chars() {
+            if line.width() + c.width().unwrap_or(0) > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(c);
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[test]
+fn test_wrap() {
+    assert_eq!(wrap("", 10), [""]);
+    assert_eq!(
+        wrap("the cat sat on the mat", 7),
+        ["the cat", "sat on", "the mat"]
+    );
+    assert_eq!(wrap("a  b", 10), ["a b"]);
+    assert_eq!(wrap("abcdefgh ij", 3), ["abc", "def", "gh", "ij"]);
+    assert_eq!(wrap("日本語 です", 4), ["日本", "語", "です"]);
+}
+
+/// The keys bound to each action while learning.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Keys {
+    /// Finish typing an answer.
+    pub(crate) submit: Bindings,
+    /// End the session.
+    pub(crate) quit: Bindings,
+    /// Mark an incorrect answer as correct.
+    pub(crate) override_correct: Bindings,
+    /// Ask for a hint while answering.
+    pub(crate) hint: Bindings,
+    /// Show the answer to a flashcard.
+    pub(crate) reveal: Bindings,
+    /// Move the selected step up while putting steps in order.
+    pub(crate) move_up: Bindings,
+    /// Move the selected step down while putting steps in order.
+    pub(crate) move_down: Bindings,
+    /// Say that the answer shown in true or false mode is right.
+    pub(crate) answer_true: Bindings,
+    /// Say that the answer shown in true or false mode is wrong.
+    pub(crate) answer_false: Bindings,
+    /// Answer out loud with the speech-to-text command.
+    pub(crate) listen: Bindings,
+    /// Record a clip of the answer said out loud.
+    #[cfg(feature = "media")]
+    pub(crate) record: Bindings,
+}
+
+impl Default for Keys {
+    fn default() -> Self {
+        let bindings =
+            |keys: &[&str]| Bindings(keys.iter().map(|key| key.parse().unwrap()).collect());
+        Self {
+            submit: bindings(&["enter"]),
+            quit: bindings(&["ctrl-c", "ctrl-q"]),
+            override_correct: bindings(&["c"]),
+            hint: bindings(&["tab"]),
+            reveal: bindings(&["space"]),
+            move_up: bindings(&["shift-up", "alt-up"]),
+            move_down: bindings(&["shift-down", "alt-down"]),
+            answer_true: bindings(&["t", "right"]),
+            answer_false: bindings(&["f", "left"]),
+            listen: bindings(&["f2"]),
+            #[cfg(feature = "media")]
+            record: bindings(&["f3"]),
+        }
+    }
+}
+
+/// One or more keys that perform the same action.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "Vec<KeyBinding>")]
+pub(crate) struct Bindings(Vec<KeyBinding>);
+
+impl Bindings {
+    pub(crate) fn matches(&self, key: &KeyEvent) -> bool {
+        self.0.iter().any(|binding| binding.matches(key))
+    }
+}
+
+impl TryFrom<Vec<KeyBinding>> for Bindings {
+    type Error = &'static str;
+
+    fn try_from(bindings: Vec<KeyBinding>) -> Result<Self, Self::Error> {
+        if bindings.is_empty() {
+            return Err("at least one key must be given");
+        }
+        Ok(Self(bindings))
+    }
+}
+
+/// Shows the first of the keys.
+impl Display for Bindings {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0[0], f)
+    }
+}
+
+/// A key with modifiers, written like `ctrl-q`, `alt-enter` or `f1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Whether the key event is this key. Shift is ignored for characters, since it is already part
+    /// of the character typed.
+    fn matches(&self, key: &KeyEvent) -> bool {
+        let mut modifiers = key.modifiers;
+        if matches!(key.code, KeyCode::Char(_)) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        key.code == self.code && modifiers == self.modifiers
+    }
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("esc", KeyCode::Esc),
+    ("space", KeyCode::Char(' ')),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("page-up", KeyCode::PageUp),
+    ("page-down", KeyCode::PageDown),
+];
+
+const MODIFIERS: &[(&str, KeyModifiers)] = &[
+    ("ctrl", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("shift", KeyModifiers::SHIFT),
+];
+
+impl FromStr for KeyBinding {
+    type Err = ParseKeyBindingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseKeyBindingError(s.to_owned());
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        'modifiers: loop {
+            for &(name, modifier) in MODIFIERS {
+                if let Some(after) = rest.strip_prefix(name).and_then(|r| r.strip_prefix('-')) {
+                    if !after.is_empty() {
+                        modifiers |= modifier;
+                        rest = after;
+                        continue 'modifiers;
+                    }
+                }
+            }
+            break;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => {
+                if let Some(&(_, code)) = NAMED_KEYS.iter().find(|(name, _)| *name == rest) {
+                    code
+                } else {
+                    let number = rest.strip_prefix('f').and_then(|n| n.parse().ok());
+                    match number {
+                        Some(n @ 1..=12) => KeyCode::F(n),
+                        _ => return Err(error()),
+                    }
+                }
+            }
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = ParseKeyBindingError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for &(name, modifier) in MODIFIERS {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}-")?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            code => {
+                let (name, _) = NAMED_KEYS.iter().find(|(_, named)| *named == code).unwrap();
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+/// Error parsing a [`KeyBinding`].
+#[derive(Debug, thiserror::Error)]
+#[error("unknown key `{0}`")]
+pub(crate) struct ParseKeyBindingError(String);
+
+#[test]
+fn test_key_binding() {
+    let key = |code, modifiers| KeyEvent { code, modifiers };
+    let binding = |s: &str| s.parse::<KeyBinding>().unwrap();
+
+    assert!(binding("ctrl-q").matches(&key(KeyCode::Char('q'), KeyModifiers::CONTROL)));
+    assert!(!binding("ctrl-q").matches(&key(KeyCode::Char('q'), KeyModifiers::NONE)));
+    assert!(binding("C").matches(&key(KeyCode::Char('C'), KeyModifiers::SHIFT)));
+    assert!(binding("-").matches(&key(KeyCode::Char('-'), KeyModifiers::NONE)));
+    assert!(binding("ctrl--").matches(&key(KeyCode::Char('-'), KeyModifiers::CONTROL)));
+    assert!(binding("shift-tab").matches(&key(KeyCode::Tab, KeyModifiers::SHIFT)));
+    assert!(binding("f5").matches(&key(KeyCode::F(5), KeyModifiers::NONE)));
+
+    for s in ["ctrl-alt-space", "page-down", "f12", "c", "shift-enter"] {
+        assert_eq!(binding(s).to_string(), s);
+    }
+    for s in ["", "ctrl-", "hyper-x", "f13", "enterr"] {
+        assert!(s.parse::<KeyBinding>().is_err(), "{s}");
+    }
+}
+
+/// What was entered by [`read_input`].
+pub(crate) enum Input {
+    /// A finished line.
+    Line(String),
+    /// A request for a hint, made by pressing tab, along with the text typed so far.
+    Hint(String),
+    /// A request to answer out loud, made by pressing the listen key, along with the text typed so
+    /// far.
+    Listen(String),
+    /// A request to record a clip of the answer said out loud, made by pressing the record key,
+    /// along with the text typed so far.
+    #[cfg(feature = "media")]
+    Record(String),
+}
+
+/// The requests that can be made while reading input, each stopping the read when its key is
+/// pressed.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Requests {
+    /// Whether a hint can be asked for.
+    pub(crate) hint: bool,
+    /// Whether the answer can be said out loud.
+    pub(crate) listen: bool,
+    /// Whether a clip of the answer can be recorded.
+    #[cfg(feature = "media")]
+    pub(crate) record: bool,
+}
+
+/// Read a line of input, echoing it as it is typed.
+///
+/// In simple mode the line is redrawn in place on the cursor's current row, without wrapping, so
+/// that it keeps working when the output scrolls.
+pub(crate) fn read_line(
+    out: impl io::Write,
+    keys: &Keys,
+    simple: bool,
+) -> io::Result<Option<String>> {
+    Ok(
+        read_input(out, keys, simple, String::new(), Requests::default())?.map(
+            |input| match input {
+                Input::Line(line) => line,
+                _ => unreachable!(),
+            },
+        ),
+    )
+}
+
+/// Like [`read_line`], but starting with the given text already typed, and stopping to return a
+/// request when the key of one of the allowed `requests` is pressed.
+pub(crate) fn read_input(
+    mut out: impl io::Write,
+    keys: &Keys,
+    simple: bool,
+    mut line: String,
+    requests: Requests,
+) -> io::Result<Option<Input>> {
+    let (start_x, mut start_y) = cursor::position()?;
+    let mut position = line.len();
+    out.write_all(line.as_bytes())?;
+    out.flush()?;
 
     loop {
-        let Some(key_event) = read_key()? else {
+        let Some(key_event) = read_key(keys)? else {
             return Ok(None);
         };
+        if keys.submit.matches(&key_event) {
+            break;
+        }
+        if requests.hint && keys.hint.matches(&key_event) {
+            return Ok(Some(Input::Hint(line)));
+        }
+        if requests.listen && keys.listen.matches(&key_event) {
+            return Ok(Some(Input::Listen(line)));
+        }
+        #[cfg(feature = "media")]
+        if requests.record && keys.record.matches(&key_event) {
+            return Ok(Some(Input::Record(line)));
+        }
         match (key_event.code, key_event.modifiers) {
             (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
                 line.insert(position, c);
                 position += c.len_utf8();
             }
-            (KeyCode::Char('h'), KeyModifiers::CONTROL) | (KeyCode::Backspace, _) => {
-                if next_boundary(Left, &mut position, &line) {
-                    line.remove(position);
-                }
+            (KeyCode::Char('h'), KeyModifiers::CONTROL) | (KeyCode::Backspace, _)
+                if next_boundary(Left, &mut position, &line) =>
+            {
+                line.remove(position);
             }
             (KeyCode::Char('w'), KeyModifiers::CONTROL) => {
                 let remove_from = last_word_start(&line[..position]);
@@ -39,10 +412,8 @@ pub(crate) fn read_line(mut out: impl io::Write) -> io::Result<Option<String>> {
                 line.clear();
                 position = 0;
             }
-            (KeyCode::Delete, _) => {
-                if position < line.len() {
-                    line.remove(position);
-                }
+            (KeyCode::Delete, _) if position < line.len() => {
+                line.remove(position);
             }
             (KeyCode::Left, _) => {
                 next_boundary(Left, &mut position, &line);
@@ -52,12 +423,14 @@ pub(crate) fn read_line(mut out: impl io::Write) -> io::Result<Option<String>> {
             }
             (KeyCode::Up | KeyCode::Home, _) => position = 0,
             (KeyCode::Down | KeyCode::End, _) => position = line.len(),
-            (KeyCode::Enter, _) => break,
             _ => (),
         }
 
         let (cols, _) = terminal::size()?;
 
+        if simple {
+            start_y = cursor::position()?.1;
+        }
         queue!(
             out,
             cursor::MoveTo(start_x, start_y),
@@ -65,7 +438,11 @@ pub(crate) fn read_line(mut out: impl io::Write) -> io::Result<Option<String>> {
         )?;
         out.write_all(line.as_bytes())?;
         let x = start_x + u16::try_from(line[..position].width()).unwrap();
-        queue!(out, cursor::MoveTo(x % cols, start_y + x / cols))?;
+        if simple {
+            queue!(out, cursor::MoveTo(x, start_y))?;
+        } else {
+            queue!(out, cursor::MoveTo(x % cols, start_y + x / cols))?;
+        }
         out.flush()?;
     }
 
@@ -75,24 +452,17 @@ pub(crate) fn read_line(mut out: impl io::Write) -> io::Result<Option<String>> {
     )?;
     out.flush()?;
 
-    Ok(Some(line))
+    Ok(Some(Input::Line(line)))
 }
 
-pub(crate) fn read_key() -> io::Result<Option<KeyEvent>> {
+/// Read a key press, returning `None` if it is a quit key.
+pub(crate) fn read_key(keys: &Keys) -> io::Result<Option<KeyEvent>> {
     let key = loop {
         if let Event::Key(key) = event::read()? {
             break key;
         }
     };
-    Ok(
-        if key.modifiers == KeyModifiers::CONTROL
-            && (key.code == KeyCode::Char('c') || key.code == KeyCode::Char('q'))
-        {
-            None
-        } else {
-            Some(key)
-        },
-    )
+    Ok((!keys.quit.matches(&key)).then_some(key))
 }
 
 fn next_boundary(direction: Direction, position: &mut usize, on: &str) -> bool {
@@ -0,0 +1,259 @@
+use std::cmp;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// How well you know a card.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Knowledge {
+    /// The level from 0 up to the highest of the configured [`Levels`].
+    pub level: KnowledgeLevel,
+    /// A safety net prevents you from going down a level if you get it wrong. It is replenished
+    /// once you get a question right.
+    pub safety_net: bool,
+}
+
+impl Knowledge {
+    /// The knowledge after answering a question on the card correctly.
+    ///
+    /// The card moves up a level and its safety net is replenished.
+    #[must_use]
+    pub fn after_correct(self, levels: Levels) -> Self {
+        Self {
+            level: KnowledgeLevel(cmp::min(self.level.0 + 1, levels.max().0)),
+            safety_net: true,
+        }
+    }
+
+    /// The knowledge after answering a question on the card incorrectly.
+    ///
+    /// If the card has a safety net it is used up instead of moving down a level, except at the
+    /// highest level where the card always moves down.
+    #[must_use]
+    pub fn after_incorrect(self, levels: Levels) -> Self {
+        if self.level.0 == 0 {
+            return Self::default();
+        }
+        Self {
+            level: if self.safety_net && self.level < levels.max() {
+                self.level
+            } else {
+                KnowledgeLevel(self.level.0 - 1)
+            },
+            safety_net: false,
+        }
+    }
+
+    /// The knowledge after answering a question on the card correctly with the help of hints.
+    ///
+    /// This counts as half correct: the card doesn't move up a level, but keeps its safety net.
+    #[must_use]
+    pub fn after_hinted(self) -> Self {
+        self
+    }
+
+    /// The knowledge after telling whether a definition shown with the card's term is right.
+    ///
+    /// Recognising an answer is easier than recalling it, so this counts for less than a question:
+    /// the card never changes level, but its safety net is replenished if the answer was correct
+    /// and used up if it wasn't.
+    #[must_use]
+    pub fn after_recognised(self, correct: bool) -> Self {
+        Self {
+            level: self.level,
+            safety_net: correct && self.level.0 != 0,
+        }
+    }
+
+    /// Whether answering a question on the card incorrectly would move it down a level.
+    #[must_use]
+    pub fn is_at_risk(self, levels: Levels) -> bool {
+        self.after_incorrect(levels).level < self.level
+    }
+}
+
+#[test]
+fn test_transitions() {
+    let k = |level, safety_net| Knowledge {
+        level: KnowledgeLevel(level),
+        safety_net,
+    };
+    let levels = Levels::DEFAULT;
+
+    assert_eq!(k(0, false).after_correct(levels), k(1, true));
+    assert_eq!(k(1, false).after_correct(levels), k(2, true));
+    assert_eq!(k(2, true).after_correct(levels), k(3, true));
+    assert_eq!(k(3, false).after_correct(levels), k(3, true));
+
+    assert_eq!(k(0, false).after_incorrect(levels), k(0, false));
+    assert_eq!(k(1, false).after_incorrect(levels), k(0, false));
+    assert_eq!(k(1, true).after_incorrect(levels), k(1, false));
+    assert_eq!(k(2, true).after_incorrect(levels), k(2, false));
+    assert_eq!(k(2, false).after_incorrect(levels), k(1, false));
+    assert_eq!(k(3, true).after_incorrect(levels), k(2, false));
+    assert_eq!(k(3, false).after_incorrect(levels), k(2, false));
+
+    assert_eq!(k(0, false).after_hinted(), k(0, false));
+    assert_eq!(k(2, true).after_hinted(), k(2, true));
+    assert_eq!(k(2, false).after_hinted(), k(2, false));
+
+    assert_eq!(k(0, false).after_recognised(true), k(0, false));
+    assert_eq!(k(2, false).after_recognised(true), k(2, true));
+    assert_eq!(k(2, true).after_recognised(false), k(2, false));
+    assert_eq!(k(3, true).after_recognised(false), k(3, false));
+
+    assert!(!k(0, false).is_at_risk(levels));
+    assert!(k(1, false).is_at_risk(levels));
+    assert!(!k(2, true).is_at_risk(levels));
+    assert!(k(3, true).is_at_risk(levels));
+
+    let levels = Levels::new(6).unwrap();
+    assert_eq!(k(3, false).after_correct(levels), k(4, true));
+    assert_eq!(k(5, true).after_correct(levels), k(5, true));
+    assert_eq!(k(3, true).after_incorrect(levels), k(3, false));
+    assert_eq!(k(5, true).after_incorrect(levels), k(4, false));
+}
+
+/// Integer ranging from 0 to [`KnowledgeLevel::LIMIT`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KnowledgeLevel(u8);
+
+impl KnowledgeLevel {
+    /// The highest knowledge level that can ever be used, whatever the number of [`Levels`].
+    pub const LIMIT: Self = Self(Levels::LIMIT.0 - 1);
+
+    /// Create a new `KnowledgeLevel`. Returns `None` if the value is above
+    /// [`KnowledgeLevel::LIMIT`].
+    #[must_use]
+    pub const fn new(value: u8) -> Option<Self> {
+        if value <= Self::LIMIT.0 {
+            Some(Self(value))
+        } else {
+            None
+        }
+    }
+
+    /// Get the knowledge level as a `u8`.
+    #[must_use]
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl FromStr for KnowledgeLevel {
+    type Err = ParseKnowledgeLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .ok()
+            .and_then(Self::new)
+            .ok_or_else(|| ParseKnowledgeLevelError {
+                given: s.to_owned(),
+            })
+    }
+}
+
+/// An error parsing a [`KnowledgeLevel`].
+#[derive(Debug)]
+pub struct ParseKnowledgeLevelError {
+    given: String,
+}
+
+impl Display for ParseKnowledgeLevelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid knowledge level {}; expected a number from 0 to {}",
+            self.given,
+            KnowledgeLevel::LIMIT.0,
+        )
+    }
+}
+
+impl Error for ParseKnowledgeLevelError {}
+
+/// The number of knowledge levels cards move between, from 2 to [`Levels::LIMIT`].
+///
+/// Cards start on level 0 and are considered learnt once they reach [`Levels::max`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Levels(u8);
+
+impl Levels {
+    /// The number of levels used unless configured otherwise.
+    pub const DEFAULT: Self = Self(4);
+
+    /// The largest supported number of levels.
+    pub const LIMIT: Self = Self(16);
+
+    /// Create a new `Levels`. Returns `None` if the count is below 2 or above [`Levels::LIMIT`].
+    #[must_use]
+    pub const fn new(count: u8) -> Option<Self> {
+        if 2 <= count && count <= Self::LIMIT.0 {
+            Some(Self(count))
+        } else {
+            None
+        }
+    }
+
+    /// Get the number of levels.
+    #[must_use]
+    pub const fn count(self) -> usize {
+        self.0 as usize
+    }
+
+    /// The highest knowledge level, at which a card is considered learnt.
+    #[must_use]
+    pub const fn max(self) -> KnowledgeLevel {
+        KnowledgeLevel(self.0 - 1)
+    }
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl FromStr for Levels {
+    type Err = ParseLevelsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .ok()
+            .and_then(Self::new)
+            .ok_or_else(|| ParseLevelsError {
+                given: s.to_owned(),
+            })
+    }
+}
+
+/// An error parsing [`Levels`].
+#[derive(Debug)]
+pub struct ParseLevelsError {
+    given: String,
+}
+
+impl Display for ParseLevelsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid number of levels {}; expected a number from 2 to {}",
+            self.given,
+            Levels::LIMIT.0,
+        )
+    }
+}
+
+impl Error for ParseLevelsError {}
+
+#[test]
+fn test_parse() {
+    assert_eq!("0".parse::<KnowledgeLevel>().unwrap().get(), 0);
+    assert_eq!("15".parse::<KnowledgeLevel>().unwrap().get(), 15);
+    assert!("16".parse::<KnowledgeLevel>().is_err());
+    assert!("-1".parse::<KnowledgeLevel>().is_err());
+
+    assert_eq!("5".parse::<Levels>().unwrap().max().get(), 4);
+    assert!("1".parse::<Levels>().is_err());
+    assert!("17".parse::<Levels>().is_err());
+}
@@ -0,0 +1,14 @@
+//! Storage-agnostic learning logic shared by every `revise` frontend.
+//!
+//! This crate knows how knowledge of a card changes when a question is answered and how the next
+//! question is chosen, but not where knowledge is stored: callers fetch knowledge from their own
+//! store and feed it in.
+#![warn(clippy::all, clippy::pedantic)]
+#![warn(missing_docs)]
+#![allow(clippy::items_after_statements, clippy::missing_panics_doc)]
+
+mod knowledge;
+pub use knowledge::*;
+
+mod select;
+pub use select::*;
@@ -0,0 +1,221 @@
+use rand::distributions::Distribution as _;
+use rand::Rng;
+
+use crate::Knowledge;
+
+/// Chooses which card to ask about next, weighted by how well each card is known.
+#[derive(Debug)]
+pub struct Selector<K> {
+    /// The previous card that was chosen.
+    /// This is used to avoid asking the same card twice in a row.
+    previous: Option<K>,
+}
+
+impl<K> Default for Selector<K> {
+    fn default() -> Self {
+        Self { previous: None }
+    }
+}
+
+impl<K: Copy + PartialEq> Selector<K> {
+    /// Create a selector that has not chosen any cards yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Choose a card out of the given cards and their knowledge.
+    ///
+    /// Each knowledge level has a weight given by `knowledge_weights`, which is multiplied by the
+    /// number of cards at that level to give the probability of a card from that level being
+    /// picked. The same card is never chosen twice in a row unless no other card can be chosen.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cards` is empty or if a card's level has no weight.
+    pub fn select<I, R>(&mut self, cards: I, knowledge_weights: &[f64], rng: &mut R) -> Selection<K>
+    where
+        I: IntoIterator<Item = (K, Knowledge)>,
+        I::IntoIter: Clone + ExactSizeIterator,
+        R: Rng + ?Sized,
+    {
+        let cards = cards
+            .into_iter()
+            .map(|(card, knowledge)| (card, knowledge, 1.0));
+        self.select_with_priority(cards, knowledge_weights, rng)
+    }
+
+    /// Choose a card out of the given cards, their knowledge and their priority.
+    ///
+    /// This is like [`Selector::select`], except that each card's weight is also multiplied by its
+    /// priority, so a card with priority 3 is asked three times as often as a card with priority 1
+    /// at the same level.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cards` is empty, if a card's level has no weight or if no card has a positive
+    /// priority.
+    pub fn select_with_priority<I, R>(
+        &mut self,
+        cards: I,
+        knowledge_weights: &[f64],
+        rng: &mut R,
+    ) -> Selection<K>
+    where
+        I: IntoIterator<Item = (K, Knowledge, f64)>,
+        I::IntoIter: Clone + ExactSizeIterator,
+        R: Rng + ?Sized,
+    {
+        let cards = cards.into_iter();
+
+        match cards.len() {
+            0 => panic!("no cards given to `Selector::select`"),
+            1 => self.previous = None,
+            _ => {}
+        }
+
+        let mut level_distribution = vec![0; knowledge_weights.len()];
+        for (_, knowledge, _) in cards.clone() {
+            level_distribution[usize::from(knowledge.level.get())] += 1;
+        }
+
+        let weight = |(_, knowledge, priority): &(K, Knowledge, f64)| {
+            knowledge_weights[usize::from(knowledge.level.get())] * priority
+        };
+        let others_possible = cards
+            .clone()
+            .any(|card| self.previous != Some(card.0) && weight(&card) > 0.0);
+        let weights = cards.clone().map(|card| {
+            if others_possible && self.previous == Some(card.0) {
+                0.0
+            } else {
+                weight(&card)
+            }
+        });
+        let index = rand::distributions::WeightedIndex::new(weights)
+            .unwrap()
+            .sample(rng);
+        let (card, _, _) = cards.clone().nth(index).unwrap();
+
+        self.previous = Some(card);
+
+        Selection {
+            card,
+            index,
+            level_distribution,
+        }
+    }
+}
+
+/// A card chosen by [`Selector::select`].
+#[derive(Debug, Clone)]
+pub struct Selection<K> {
+    /// The chosen card.
+    pub card: K,
+    /// The index of the chosen card in the iterator given to [`Selector::select`].
+    pub index: usize,
+    /// The number of cards at each knowledge level.
+    pub level_distribution: Vec<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::Selector;
+    use crate::{Knowledge, Levels};
+
+    #[test]
+    fn no_duplicates() {
+        let mut rng = rand::thread_rng();
+        let mut selector = Selector::new();
+        let mut knowledge = [Knowledge::default(); 2];
+
+        let mut previous = None;
+        for _ in 0..1000 {
+            let selection =
+                selector.select(knowledge.iter().copied().enumerate(), &[1.0; 4], &mut rng);
+            if let Some(previous) = previous {
+                assert_ne!(selection.card, previous);
+            }
+            previous = Some(selection.card);
+
+            let k = &mut knowledge[selection.card];
+            *k = if rng.gen() {
+                k.after_correct(Levels::DEFAULT)
+            } else {
+                k.after_incorrect(Levels::DEFAULT)
+            };
+        }
+
+        assert!(previous.is_some());
+    }
+
+    #[test]
+    fn equal_distribution() {
+        let mut rng = rand::thread_rng();
+        let mut selector = Selector::new();
+        let mut knowledge = [Knowledge::default(); 5];
+        let mut occurrences = [0; 5];
+
+        const ITERATIONS: usize = 1000;
+        for _ in 0..ITERATIONS {
+            let selection =
+                selector.select(knowledge.iter().copied().enumerate(), &[1.0; 4], &mut rng);
+            assert_eq!(selection.card, selection.index);
+            occurrences[selection.index] += 1;
+            knowledge[selection.card] = knowledge[selection.card].after_correct(Levels::DEFAULT);
+        }
+
+        let average = ITERATIONS / knowledge.len();
+        for occurrences in occurrences {
+            assert!(
+                ((average - 50)..(average + 50)).contains(&occurrences),
+                "{occurrences} is too far off {average}"
+            );
+        }
+    }
+
+    #[test]
+    fn level_distribution() {
+        let mut rng = rand::thread_rng();
+        let knowledge = [0, 3, 3, 1, 5].map(|level| Knowledge {
+            level: crate::KnowledgeLevel::new(level).unwrap(),
+            safety_net: false,
+        });
+        let selection =
+            Selector::new().select(knowledge.iter().copied().enumerate(), &[1.0; 6], &mut rng);
+        assert_eq!(selection.level_distribution, [1, 1, 0, 2, 0, 1]);
+    }
+
+    #[test]
+    fn priority() {
+        let mut rng = rand::thread_rng();
+        let mut selector = Selector::new();
+        let cards = [(0, 1.0), (1, 1.0), (2, 8.0)]
+            .map(|(card, priority)| (card, Knowledge::default(), priority));
+        let mut occurrences = [0; 3];
+
+        for _ in 0..1000 {
+            let selection = selector.select_with_priority(cards, &[1.0; 4], &mut rng);
+            occurrences[selection.card] += 1;
+        }
+
+        // Card 2 can't be asked twice in a row, so it is asked 8/17 of the time rather than 8/10.
+        assert!(occurrences[2] * 2 > occurrences[0] * 3, "{occurrences:?}");
+        assert!(occurrences[2] * 2 > occurrences[1] * 3, "{occurrences:?}");
+    }
+
+    #[test]
+    fn zero_priority() {
+        let mut rng = rand::thread_rng();
+        let mut selector = Selector::new();
+        let cards = [(0, 0.0), (1, 1.0), (2, 0.0)]
+            .map(|(card, priority)| (card, Knowledge::default(), priority));
+
+        for _ in 0..100 {
+            let selection = selector.select_with_priority(cards, &[1.0; 4], &mut rng);
+            assert_eq!(selection.card, 1);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::BTreeSet;
+use std::ptr;
+
+use bincode::Options as _;
+use rusqlite::types::ToSql;
+
+/// A unique key that every card has.
+///
+/// Keys are stored in the database as bytes. The current format, version 2, is:
+///
+/// - the bytes `0x00 0x02`, marking a versioned key of version 2;
+/// - the terms, then the definitions, each as a set.
+///
+/// A set is its number of elements as a little-endian `u32` followed by each element in sorted
+/// order, and each element is its length in bytes as a little-endian `u32` followed by its UTF-8
+/// encoding.
+///
+/// Keys in the older version 1 format, a `bincode` encoding of the two sets with variable-length
+/// integers, are still understood by [`CardKey::from_bytes`]. They can never start with a zero
+/// byte because sets are never empty.
+#[allow(clippy::derived_hash_with_manual_eq)]
+#[derive(Debug, Clone, Eq, Hash)]
+pub struct CardKey(Vec<u8>);
+
+impl CardKey {
+    /// Compute the card key for the given card's terms and definitions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either set is empty.
+    #[must_use]
+    pub fn new<T, D>(terms: &BTreeSet<T>, definitions: &BTreeSet<D>) -> Self
+    where
+        T: AsRef<str>,
+        D: AsRef<str>,
+    {
+        assert!(!terms.is_empty());
+        assert!(!definitions.is_empty());
+
+        let mut bytes = vec![0, 2];
+        push_set(&mut bytes, terms.iter().map(AsRef::as_ref), terms.len());
+        push_set(
+            &mut bytes,
+            definitions.iter().map(AsRef::as_ref),
+            definitions.len(),
+        );
+        Self(bytes)
+    }
+
+    /// Read a card key from its bytes, in either the current or the version 1 format.
+    ///
+    /// Version 1 keys are converted to the current format. Returns `None` if the bytes are not a
+    /// valid key.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (terms, definitions) = decode(bytes)?;
+        Some(Self::new(&terms, &definitions))
+    }
+
+    /// The bytes of the key, in the current format.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The card's terms.
+    #[must_use]
+    pub fn terms(&self) -> BTreeSet<&str> {
+        self.decode().0
+    }
+
+    /// The card's definitions.
+    #[must_use]
+    pub fn definitions(&self) -> BTreeSet<&str> {
+        self.decode().1
+    }
+
+    fn decode(&self) -> (BTreeSet<&str>, BTreeSet<&str>) {
+        // Every way of making a key checks that it can be decoded.
+        decode(&self.0).expect("malformed card key")
+    }
+
+    pub(crate) fn as_sql(&self) -> &impl ToSql {
+        &self.0
+    }
+
+    /// Read a key stored in the database, or `None` if it is malformed, which can only happen if
+    /// the database was modified by something other than `revise`.
+    pub(crate) fn from_sql(sql: Vec<u8>) -> Option<Self> {
+        decode(&sql)?;
+        Some(Self(sql))
+    }
+}
+
+impl PartialEq for CardKey {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self, other) || self.0 == other.0
+    }
+}
+
+fn push_set<'a>(bytes: &mut Vec<u8>, set: impl Iterator<Item = &'a str>, len: usize) {
+    push_len(bytes, len);
+    for element in set {
+        push_len(bytes, element.len());
+        bytes.extend_from_slice(element.as_bytes());
+    }
+}
+
+fn push_len(bytes: &mut Vec<u8>, len: usize) {
+    bytes.extend_from_slice(&u32::try_from(len).unwrap().to_le_bytes());
+}
+
+type Decoded<'a> = (BTreeSet<&'a str>, BTreeSet<&'a str>);
+
+fn decode(bytes: &[u8]) -> Option<Decoded<'_>> {
+    let (terms, definitions) = match bytes {
+        [0, 2, rest @ ..] => {
+            let mut rest = rest;
+            let terms = read_set(&mut rest)?;
+            let definitions = read_set(&mut rest)?;
+            if !rest.is_empty() {
+                return None;
+            }
+            (terms, definitions)
+        }
+        [0, ..] => return None,
+        _ => {
+            let (terms, definitions) = bincode::DefaultOptions::new()
+                .deserialize::<(Vec<&[u8]>, Vec<&[u8]>)>(bytes)
+                .ok()?;
+            fn to_set(set: Vec<&[u8]>) -> Option<BTreeSet<&str>> {
+                set.into_iter()
+                    .map(|element| std::str::from_utf8(element).ok())
+                    .collect()
+            }
+            (to_set(terms)?, to_set(definitions)?)
+        }
+    };
+    if terms.is_empty() || definitions.is_empty() {
+        return None;
+    }
+    Some((terms, definitions))
+}
+
+fn read_set<'a>(bytes: &mut &'a [u8]) -> Option<BTreeSet<&'a str>> {
+    (0..read_len(bytes)?)
+        .map(|_| {
+            let len = read_len(bytes)?;
+            let element = bytes.get(..len)?;
+            *bytes = &bytes[len..];
+            std::str::from_utf8(element).ok()
+        })
+        .collect()
+}
+
+fn read_len(bytes: &mut &[u8]) -> Option<usize> {
+    let (len, rest) = bytes.split_first_chunk::<4>()?;
+    *bytes = rest;
+    usize::try_from(u32::from_le_bytes(*len)).ok()
+}
+
+/// Encode a card key in the version 1 format.
+#[cfg(test)]
+pub(crate) fn v1(terms: &BTreeSet<&str>, definitions: &BTreeSet<&str>) -> Vec<u8> {
+    fn set<'a>(set: &BTreeSet<&'a str>) -> Vec<&'a [u8]> {
+        set.iter().map(|s| s.as_bytes()).collect()
+    }
+    bincode::DefaultOptions::new()
+        .serialize(&(set(terms), set(definitions)))
+        .unwrap()
+}
+
+#[test]
+fn test_card_key() {
+    use maplit::btreeset;
+
+    let terms = btreeset!("b", "a", "ü");
+    let definitions = btreeset!("definition");
+    let key = CardKey::new(&terms, &definitions);
+
+    assert_eq!(
+        key.as_bytes(),
+        b"\0\x02\
+        \x03\0\0\0\
+        \x01\0\0\0a\
+        \x01\0\0\0b\
+        \x02\0\0\0\xc3\xbc\
+        \x01\0\0\0\
+        \x0a\0\0\0definition"
+    );
+    assert_eq!(key.terms(), terms);
+    assert_eq!(key.definitions(), definitions);
+
+    assert_eq!(CardKey::from_bytes(key.as_bytes()), Some(key.clone()));
+    assert_eq!(CardKey::from_bytes(&v1(&terms, &definitions)), Some(key));
+
+    assert_eq!(CardKey::from_bytes(&[]), None);
+    assert_eq!(CardKey::from_bytes(&[0, 1]), None);
+    assert_eq!(CardKey::from_bytes(&[0, 2, 0, 0, 0, 0, 0, 0, 0, 0]), None);
+    assert_eq!(CardKey::from_bytes(&[0, 2, 1, 0, 0, 0, 9, 0, 0, 0]), None);
+    let mut trailing = CardKey::new(&terms, &definitions).0;
+    trailing.push(0);
+    assert_eq!(CardKey::from_bytes(&trailing), None);
+}
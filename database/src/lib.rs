@@ -7,29 +7,155 @@
     clippy::missing_errors_doc
 )]
 
-use std::collections::{BTreeSet, HashMap};
+use std::cmp;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::ptr;
-use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use bincode::Options as _;
+use directories::ProjectDirs;
 use rusqlite::types::ToSql;
-use rusqlite::OptionalExtension as _;
-use serde::ser::{Serialize, Serializer};
+use rusqlite::{ErrorCode, OpenFlags, OptionalExtension as _};
 use thiserror::Error;
 
-/// The database of how well you know which cards.
+pub use revise_core::{
+    Knowledge, KnowledgeLevel, Levels, ParseKnowledgeLevelError, ParseLevelsError,
+};
+
+mod card_key;
+pub use card_key::CardKey;
+
+mod memory;
+pub use memory::MemoryStore;
+
+mod session;
+pub use session::Session;
+
+/// The path of the database shared by all `revise` frontends, or `None` if no home directory could
+/// be found.
+#[must_use]
+pub fn default_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "revise").map(|dirs| dirs.data_dir().join("data.sqlite3"))
+}
+
+/// The directory containing the database of each profile, or `None` if no home directory could be
+/// found.
+///
+/// Profiles keep separate knowledge in separate databases, for example for several people sharing
+/// one computer. The database of a profile named `name` is `name.sqlite3` in this directory.
+#[must_use]
+pub fn profiles_dir() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "revise").map(|dirs| dirs.data_dir().join("profiles"))
+}
+
+/// A suggestion for how to fix an error, if it is a database error that the user can do something
+/// about. Errors from this crate are caused by database errors, so this should be given each error
+/// in their [source](Error::source) chain.
+#[must_use]
+pub fn help(error: &(dyn Error + 'static)) -> Option<&'static str> {
+    let error = match error.downcast_ref() {
+        Some(OpenErrorKind::Sqlite(error)) => error,
+        Some(OpenErrorKind::TooNew { .. }) => return Some("update `revise` to use this database"),
+        None => error.downcast_ref()?,
+    };
+    let rusqlite::Error::SqliteFailure(failure, _) = error else {
+        return None;
+    };
+    Some(match failure.code {
+        ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => {
+            "another program, perhaps another `revise`, is using the database; try again when it has finished"
+        }
+        ErrorCode::ReadOnly | ErrorCode::PermissionDenied | ErrorCode::CannotOpen => {
+            "check that you can write to the database file and the directory that contains it"
+        }
+        ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase => {
+            "the file is damaged or is not a `revise` database; pass `--database` to use another one"
+        }
+        ErrorCode::DiskFull => "free up some disk space and try again",
+        _ => return None,
+    })
+}
+
+/// A store of how well you know which cards.
+pub trait KnowledgeStore {
+    /// Get how well known a card is.
+    fn knowledge(&self, card: &CardKey) -> Result<Knowledge, GetKnowledgeError>;
+
+    /// Get how well known a set of cards are.
+    ///
+    /// The resulting iterator is in the same order as the input iterator.
+    fn knowledge_all<'a, I>(
+        &self,
+        cards: I,
+    ) -> Result<
+        impl 'a + Clone + ExactSizeIterator<Item = (&'a CardKey, Knowledge)>,
+        GetKnowledgeError,
+    >
+    where
+        I: IntoIterator<Item = &'a CardKey>,
+        I::IntoIter: ExactSizeIterator + Clone + 'a;
+
+    /// Set the knowledge of a card.
+    fn set_knowledge(
+        &mut self,
+        card: &CardKey,
+        knowledge: Knowledge,
+    ) -> Result<(), SetKnowledgeError>;
+
+    /// Set the knowledge of several cards.
+    fn set_knowledge_all<'a, I>(
+        &mut self,
+        cards: I,
+        knowledge: Knowledge,
+    ) -> Result<(), SetKnowledgeError>
+    where
+        I: IntoIterator<Item = &'a CardKey>,
+        I::IntoIter: ExactSizeIterator;
+
+    /// Record the answer to a question as correct.
+    fn record_correct(&mut self, card: &CardKey) -> Result<(), RecordCorrectError>;
+
+    /// Record the answer to a question as incorrect.
+    fn record_incorrect(&mut self, card: &CardKey) -> Result<(), RecordIncorrectError>;
+
+    /// Record the answer to a question as correct, but only reached with the help of hints.
+    fn record_hinted(&mut self, card: &CardKey) -> Result<(), RecordHintedError>;
+
+    /// Record whether a statement about the card was correctly told to be true or false. See
+    /// [`Knowledge::after_recognised`].
+    fn record_recognised(
+        &mut self,
+        card: &CardKey,
+        correct: bool,
+    ) -> Result<(), RecordRecognisedError>;
+
+    /// The number of knowledge levels cards move between.
+    fn levels(&self) -> Levels;
+
+    /// Change the number of knowledge levels. Cards above the new highest level are moved down to
+    /// it.
+    fn set_levels(&mut self, levels: Levels) -> Result<(), SetLevelsError>;
+
+    /// Save every write made so far, for stores that batch them. Does nothing by default.
+    fn flush(&mut self) -> Result<(), FlushError> {
+        Ok(())
+    }
+}
+
+/// The database of how well you know which cards, stored in a file.
 #[derive(Debug)]
 pub struct Database {
     connection: rusqlite::Connection,
+    levels: Levels,
 }
 
 impl Database {
     /// Open a database with the given path.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, OpenError> {
         rusqlite::Connection::open(&path)
+            .map_err(OpenErrorKind::Sqlite)
             .and_then(Self::new)
             .map_err(|inner| OpenError {
                 inner,
@@ -37,55 +163,895 @@ impl Database {
             })
     }
 
+    /// Open a database without changing it, such as one to [`merge`](Self::merge) in. Its schema
+    /// is left as it is, so it may be in an older format that only `merge` understands, and
+    /// anything that writes to it fails.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self, OpenError> {
+        rusqlite::Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(OpenErrorKind::Sqlite)
+            .and_then(|connection| {
+                let version: usize =
+                    connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+                if version > MIGRATIONS.len() {
+                    return Err(OpenErrorKind::TooNew { version });
+                }
+                let levels = if has_table(&connection, "settings")? {
+                    let max_level =
+                        connection.query_row("SELECT max_level FROM settings", [], |row| {
+                            row.get::<_, u8>(0)
+                        })?;
+                    Levels::new(max_level + 1).unwrap()
+                } else {
+                    Levels::DEFAULT
+                };
+                Ok(Self { connection, levels })
+            })
+            .map_err(|inner| OpenError {
+                inner,
+                path: path.as_ref().to_owned(),
+            })
+    }
+
     /// Open an in-memory database.
     pub fn open_in_memory() -> Result<Self, OpenInMemoryError> {
         rusqlite::Connection::open_in_memory()
+            .map_err(OpenErrorKind::Sqlite)
             .and_then(Self::new)
             .map_err(|inner| OpenInMemoryError { inner })
     }
 
-    fn new(connection: rusqlite::Connection) -> rusqlite::Result<Self> {
-        connection.execute_batch(
-            "\
-                CREATE TABLE IF NOT EXISTS v1 (
-                    card BLOB NOT NULL PRIMARY KEY,
-                    knowledge_level INTEGER NOT NULL CHECK(knowledge_level >= 1 AND knowledge_level <= 3),
-                    safety_net INTEGER NOT NULL CHECK(safety_net = 0 OR safety_net = 1)
-                ) WITHOUT ROWID
-            ",
-        )?;
-        Ok(Self { connection })
+    /// Start batching writes to the database into one transaction, which is much faster than
+    /// committing every answer on its own when the database is on a slow or network filesystem.
+    pub fn session(&mut self) -> Result<Session<'_>, StartSessionError> {
+        Session::new(self).map_err(|inner| StartSessionError { inner })
     }
 
-    /// Get how well known a card is.
-    pub fn knowledge(&self, card: &CardKey) -> Result<Knowledge, GetKnowledgeError> {
-        self.connection
+    fn new(mut connection: rusqlite::Connection) -> Result<Self, OpenErrorKind> {
+        let transaction = connection.transaction()?;
+        let version: usize = transaction.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > MIGRATIONS.len() {
+            return Err(OpenErrorKind::TooNew { version });
+        }
+        // Only write the version when migrating, so that opening an up-to-date database leaves
+        // the file as it was.
+        if version < MIGRATIONS.len() {
+            for migration in &MIGRATIONS[version..] {
+                migration(&transaction)?;
+            }
+            transaction.execute(&format!("PRAGMA user_version = {}", MIGRATIONS.len()), [])?;
+        }
+        let max_level = transaction.query_row("SELECT max_level FROM settings", [], |row| {
+            row.get::<_, u8>(0)
+        })?;
+        transaction.commit()?;
+
+        Ok(Self {
+            connection,
+            levels: Levels::new(max_level + 1).unwrap(),
+        })
+    }
+
+    /// Get every answer recorded for a card, oldest first.
+    pub fn history(&self, card: &CardKey) -> Result<Vec<HistoryEntry>, GetHistoryError> {
+        (|| {
+            self.connection
+                .prepare(
+                    "SELECT time,correct,hinted FROM history WHERE card = ? ORDER BY time, rowid",
+                )?
+                .query_map([card.as_sql()], |row| {
+                    Ok(HistoryEntry {
+                        time: from_unix_time(row.get_unwrap(0)),
+                        correct: row.get_unwrap(1),
+                        hinted: row.get_unwrap(2),
+                    })
+                })?
+                .collect::<rusqlite::Result<_>>()
+        })()
+        .map_err(|inner| GetHistoryError { inner })
+    }
+
+    /// Get every answer recorded in the given time range, oldest first.
+    pub fn recent_activity(
+        &self,
+        range: Range<SystemTime>,
+    ) -> Result<Vec<(CardKey, HistoryEntry)>, GetHistoryError> {
+        (|| {
+            self.connection
+                .prepare(
+                    "SELECT card,time,correct,hinted FROM history WHERE time >= ? AND time < ? ORDER BY time, rowid",
+                )?
+                .query_map(
+                    [to_unix_time(range.start), to_unix_time(range.end)],
+                    |row| {
+                        Ok(CardKey::from_sql(row.get_unwrap(0)).map(|card| {
+                            let entry = HistoryEntry {
+                                time: from_unix_time(row.get_unwrap(1)),
+                                correct: row.get_unwrap(2),
+                                hinted: row.get_unwrap(3),
+                            };
+                            (card, entry)
+                        }))
+                    },
+                )?
+                .filter_map(Result::transpose)
+                .collect::<rusqlite::Result<_>>()
+        })()
+        .map_err(|inner| GetHistoryError { inner })
+    }
+
+    /// Get the terms and definitions of a card, or `None` if the database has no record of it.
+    #[allow(clippy::type_complexity)]
+    pub fn card_text(
+        &self,
+        card: &CardKey,
+    ) -> Result<Option<(Vec<String>, Vec<String>)>, GetCardTextError> {
+        let known = self
+            .connection
             .query_row(
-                "SELECT knowledge_level,safety_net FROM v1 WHERE card = ?",
+                "SELECT EXISTS (SELECT * FROM v1 WHERE card = ?1) OR EXISTS (SELECT * FROM history WHERE card = ?1)",
                 [card.as_sql()],
-                |row| {
-                    Ok(Knowledge {
-                        level: KnowledgeLevel::new(row.get_unwrap(0)).unwrap(),
-                        safety_net: row.get_unwrap(1),
-                    })
-                },
+                |row| row.get::<_, bool>(0),
             )
-            .map(|knowledge| {
-                assert_ne!(knowledge.level.get(), 0);
-                knowledge
+            .map_err(|inner| GetCardTextError { inner })?;
+        let to_vec = |set: BTreeSet<&str>| set.into_iter().map(str::to_owned).collect();
+        Ok(known.then(|| (to_vec(card.terms()), to_vec(card.definitions()))))
+    }
+
+    /// Describe a card by its terms and definitions, like `bonjour, salut - hello`, or get `None`
+    /// if the database has no record of it.
+    pub fn describe(&self, card: &CardKey) -> Result<Option<String>, GetCardTextError> {
+        Ok(self.card_text(card)?.map(|(terms, definitions)| {
+            format!("{} - {}", terms.join(", "), definitions.join(", "))
+        }))
+    }
+
+    /// Find every card the database has a record of with a term containing the given text,
+    /// ignoring case. The cards are sorted by their terms.
+    pub fn search_terms(&self, text: &str) -> Result<Vec<CardKey>, SearchTermsError> {
+        let cards = (|| {
+            self.connection
+                .prepare("SELECT card FROM v1 UNION SELECT card FROM history")?
+                .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })()
+        .map_err(|inner| SearchTermsError { inner })?;
+
+        let text = text.to_lowercase();
+        let mut cards = cards
+            .iter()
+            .filter_map(|bytes| CardKey::from_bytes(bytes))
+            .filter(|card| {
+                card.terms()
+                    .iter()
+                    .any(|term| term.to_lowercase().contains(&text))
             })
-            .optional()
-            .map(Option::unwrap_or_default)
-            .map_err(|inner| GetKnowledgeError { inner })
+            .collect::<Vec<_>>();
+        sort_by_text(&mut cards);
+        Ok(cards)
     }
 
-    /// Get how well known a set of cards are.
+    /// Check the database file for corruption, returning a description of every problem found.
+    pub fn integrity_check(&self) -> Result<Vec<String>, IntegrityCheckError> {
+        let problems = (|| {
+            self.connection
+                .prepare("PRAGMA integrity_check")?
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })()
+        .map_err(|inner| IntegrityCheckError { inner })?;
+
+        Ok(if problems == ["ok"] {
+            Vec::new()
+        } else {
+            problems
+        })
+    }
+
+    /// Count the cards the database has a record of whose keys can't be read, which can only happen
+    /// if it was modified by something other than `revise`. They are skipped everywhere else.
+    pub fn malformed_keys(&self) -> Result<usize, GetMalformedKeysError> {
+        malformed_keys(&self.connection)
+            .map(|keys| keys.len())
+            .map_err(|inner| GetMalformedKeysError { inner })
+    }
+
+    /// Remove every record of the cards whose keys can't be read, returning how many there were.
+    pub fn prune_malformed_keys(&mut self) -> Result<usize, PruneError> {
+        (|| {
+            let transaction = self.connection.savepoint()?;
+            let keys = malformed_keys(&transaction)?;
+            for table in CARD_TABLES {
+                let mut statement =
+                    transaction.prepare(&format!("DELETE FROM {table} WHERE card = ?"))?;
+                for key in &keys {
+                    statement.execute([key])?;
+                }
+            }
+            transaction.commit()?;
+            Ok(keys.len())
+        })()
+        .map_err(|inner| PruneError { inner })
+    }
+
+    /// Find every card the database has a record of that is not one of the given cards, sorted by
+    /// their terms.
+    pub fn orphans(&self, known: &HashSet<CardKey>) -> Result<Vec<CardKey>, GetOrphansError> {
+        let cards = (|| {
+            self.connection
+                .prepare("SELECT card FROM v1 UNION SELECT card FROM history")?
+                .query_map([], |row| Ok(CardKey::from_sql(row.get_unwrap(0))))?
+                .filter_map(Result::transpose)
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })()
+        .map_err(|inner| GetOrphansError { inner })?;
+
+        let mut orphans = cards
+            .into_iter()
+            .filter(|card| !known.contains(card))
+            .collect::<Vec<_>>();
+        sort_by_text(&mut orphans);
+        Ok(orphans)
+    }
+
+    /// Remember which cards are in a set, replacing what was remembered about it before. Sets are
+    /// told apart by their path, which should be absolute so that it doesn't depend on the
+    /// directory `revise` is run in.
+    ///
+    /// Cards should be given the way round they are written in the set. Their inverted keys, which
+    /// hold their knowledge when learned inverted, are remembered as being in the set too.
+    pub fn record_set<'a, I>(
+        &mut self,
+        path: &Path,
+        title: &str,
+        cards: I,
+    ) -> Result<(), RecordSetError>
+    where
+        I: IntoIterator<Item = &'a CardKey>,
+    {
+        let cards = cards.into_iter().collect::<HashSet<_>>();
+        (|| {
+            let transaction = self.connection.savepoint()?;
+            let path = path.to_string_lossy();
+            transaction.execute(
+                "INSERT INTO sets (path, title, loaded_at, cards) VALUES (?1, ?2, ?3, ?4) \
+                ON CONFLICT(path) DO UPDATE SET \
+                    title = excluded.title, loaded_at = excluded.loaded_at, cards = excluded.cards",
+                rusqlite::params![
+                    path,
+                    title,
+                    to_unix_time(SystemTime::now()),
+                    i64::try_from(cards.len()).unwrap(),
+                ],
+            )?;
+            let id =
+                transaction.query_row("SELECT id FROM sets WHERE path = ?", [&path], |row| {
+                    row.get::<_, i64>(0)
+                })?;
+            transaction.execute("DELETE FROM set_cards WHERE set_id = ?", [id])?;
+            let mut insert = transaction
+                .prepare("INSERT OR IGNORE INTO set_cards (set_id, card) VALUES (?, ?)")?;
+            for card in cards {
+                let inverted = CardKey::new(&card.definitions(), &card.terms());
+                insert.execute(rusqlite::params![id, card.as_sql()])?;
+                insert.execute(rusqlite::params![id, inverted.as_sql()])?;
+            }
+            drop(insert);
+            transaction.commit()
+        })()
+        .map_err(|inner| RecordSetError { inner })
+    }
+
+    /// Get every set recorded with [`record_set`](Self::record_set), sorted by their paths.
+    pub fn sets(&self) -> Result<Vec<SetRecord>, GetSetsError> {
+        (|| {
+            self.connection
+                .prepare(
+                    "\
+                        SELECT path, title, loaded_at, cards FROM sets ORDER BY path
+                    ",
+                )?
+                .query_map([], |row| {
+                    Ok(SetRecord {
+                        path: PathBuf::from(row.get_unwrap::<_, String>(0)),
+                        title: row.get_unwrap(1),
+                        loaded_at: from_unix_time(row.get_unwrap(2)),
+                        cards: row.get_unwrap(3),
+                    })
+                })?
+                .collect::<rusqlite::Result<_>>()
+        })()
+        .map_err(|inner| GetSetsError { inner })
+    }
+
+    /// Get the cards last recorded as being in the set at the given path, both ways round, sorted
+    /// by their terms, or `None` if the set has never been recorded.
+    pub fn set_cards(&self, path: &Path) -> Result<Option<Vec<CardKey>>, GetSetsError> {
+        let cards = (|| {
+            let Some(id) = self
+                .connection
+                .query_row(
+                    "SELECT id FROM sets WHERE path = ?",
+                    [path.to_string_lossy()],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?
+            else {
+                return Ok(None);
+            };
+            self.connection
+                .prepare("SELECT card FROM set_cards WHERE set_id = ?")?
+                .query_map([id], |row| Ok(CardKey::from_sql(row.get_unwrap(0))))?
+                .filter_map(Result::transpose)
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .map(Some)
+        })()
+        .map_err(|inner| GetSetsError { inner })?;
+
+        Ok(cards.map(|mut cards| {
+            sort_by_text(&mut cards);
+            cards
+        }))
+    }
+
+    /// Remove every record of the given cards: their knowledge, their answer history and the
+    /// knowledge saved when they were cleared.
+    pub fn prune(&mut self, cards: &[CardKey]) -> Result<(), PruneError> {
+        (|| {
+            let transaction = self.connection.savepoint()?;
+            for table in CARD_TABLES {
+                let mut statement =
+                    transaction.prepare(&format!("DELETE FROM {table} WHERE card = ?"))?;
+                for card in cards {
+                    statement.execute([card.as_sql()])?;
+                }
+            }
+            transaction.commit()
+        })()
+        .map_err(|inner| PruneError { inner })
+    }
+
+    /// Set the knowledge of several cards, first saving their current knowledge so that
+    /// [`unclear`](Self::unclear) can restore it.
+    pub fn clear<'a, I>(&mut self, cards: I, knowledge: Knowledge) -> Result<(), ClearError>
+    where
+        I: IntoIterator<Item = &'a CardKey>,
+        I::IntoIter: ExactSizeIterator + Clone,
+    {
+        let cards = cards.into_iter();
+        (|| {
+            let transaction = self.connection.savepoint()?;
+            transaction.execute(
+                "INSERT INTO clears (cleared_at) VALUES (?)",
+                [to_unix_time(SystemTime::now())],
+            )?;
+            let clear = transaction.last_insert_rowid();
+            let mut insert = transaction.prepare(
+                "INSERT INTO cleared SELECT ?1, ?2, ifnull(knowledge_level, 0), ifnull(safety_net, 0) \
+                FROM (SELECT 1) LEFT JOIN v1 ON card = ?2",
+            )?;
+            for card in cards.clone() {
+                insert.execute(rusqlite::params![clear, card.as_sql()])?;
+            }
+            drop(insert);
+            for card in cards {
+                set_knowledge(&transaction, card, knowledge).map_err(|e| match e {
+                    SetKnowledgeErrorKind::Remove(e) | SetKnowledgeErrorKind::Insert(e) => e,
+                })?;
+            }
+            transaction.commit()
+        })()
+        .map_err(|inner| ClearError { inner })
+    }
+
+    /// Restore the knowledge of the cards in the most recent [`clear`](Self::clear) that has not
+    /// already been restored. Returns when that clear happened and how many cards it covered,
+    /// counting a card and its inverted key once, or [`None`] if there is nothing to restore.
+    pub fn unclear(&mut self) -> Result<Option<Unclear>, UnclearError> {
+        (|| {
+            let transaction = self.connection.savepoint()?;
+            let Some((clear, cleared_at)) = transaction
+                .query_row(
+                    "SELECT id, cleared_at FROM clears ORDER BY id DESC LIMIT 1",
+                    [],
+                    |row| Ok((row.get_unwrap::<_, i64>(0), row.get_unwrap(1))),
+                )
+                .optional()?
+            else {
+                return Ok(None);
+            };
+            transaction.execute(
+                "INSERT INTO v1 SELECT card, knowledge_level, safety_net FROM cleared \
+                WHERE clear = ?1 AND knowledge_level != 0 \
+                ON CONFLICT(card) DO UPDATE SET knowledge_level = excluded.knowledge_level, safety_net = excluded.safety_net",
+                [clear],
+            )?;
+            transaction.execute(
+                "DELETE FROM v1 WHERE card IN (SELECT card FROM cleared WHERE clear = ?1 AND knowledge_level = 0)",
+                [clear],
+            )?;
+            let keys = transaction
+                .prepare("SELECT card FROM cleared WHERE clear = ?")?
+                .query_map([clear], |row| Ok(CardKey::from_sql(row.get_unwrap(0))))?
+                .filter_map(Result::transpose)
+                .collect::<rusqlite::Result<HashSet<_>>>()?;
+            let cards = keys
+                .iter()
+                .filter(|card| {
+                    let inverted = CardKey::new(&card.definitions(), &card.terms());
+                    !keys.contains(&inverted) || card.as_bytes() <= inverted.as_bytes()
+                })
+                .count();
+            transaction.execute("DELETE FROM cleared WHERE clear = ?", [clear])?;
+            transaction.execute("DELETE FROM clears WHERE id = ?", [clear])?;
+            transaction.commit()?;
+            Ok(Some(Unclear {
+                cleared_at: from_unix_time(cleared_at),
+                cards,
+            }))
+        })()
+        .map_err(|inner| UnclearError { inner })
+    }
+
+    /// Merge the knowledge and answer history of another database into this one, such as one
+    /// used on another computer. Cards that both databases know are combined using the strategy,
+    /// and answers already in this database are not added again, so merging twice changes nothing.
     ///
-    /// The resulting iterator may not be in the same order as the input iterator.
-    pub fn knowledge_all<'a, I>(
+    /// The other database is only read from, so it can be [opened read-only](Self::open_read_only)
+    /// even if its schema is older than this one's.
+    pub fn merge(
+        &mut self,
+        other: &Database,
+        strategy: MergeStrategy,
+    ) -> Result<MergeSummary, MergeError> {
+        (|| {
+            let mut knowledge = Vec::new();
+            if has_table(&other.connection, "v1")? {
+                knowledge = other
+                    .connection
+                    .prepare("SELECT card, knowledge_level, safety_net FROM v1")?
+                    .query_map([], |row| {
+                        let knowledge = Knowledge {
+                            level: KnowledgeLevel::new(row.get_unwrap(1)).unwrap(),
+                            safety_net: row.get_unwrap(2),
+                        };
+                        let card = CardKey::from_bytes(&row.get_unwrap::<_, Vec<u8>>(0));
+                        Ok(card.map(|card| (card, knowledge)))
+                    })?
+                    .filter_map(Result::transpose)
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+            }
+            let mut history = Vec::new();
+            if has_table(&other.connection, "history")? {
+                // Answers from before hints were recorded weren't hinted.
+                let hinted = if has_column(&other.connection, "history", "hinted")? {
+                    "hinted"
+                } else {
+                    "0"
+                };
+                history = other
+                    .connection
+                    .prepare(&format!("SELECT card, time, correct, {hinted} FROM history"))?
+                    .query_map([], |row| {
+                        let card = CardKey::from_bytes(&row.get_unwrap::<_, Vec<u8>>(0));
+                        Ok(card.map(|card| {
+                            let time = row.get_unwrap::<_, i64>(1);
+                            (card, time, row.get_unwrap::<_, bool>(2), row.get_unwrap::<_, bool>(3))
+                        }))
+                    })?
+                    .filter_map(Result::transpose)
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+            }
+
+            let transaction = self.connection.savepoint()?;
+            let mut summary = MergeSummary::default();
+            let max_level = self.levels.max();
+            for (card, theirs) in knowledge {
+                let ours = self::knowledge(&transaction, &card)?;
+                let theirs = Knowledge {
+                    level: cmp::min(theirs.level, max_level),
+                    ..theirs
+                };
+                let merged = match strategy {
+                    MergeStrategy::Highest => Knowledge {
+                        level: cmp::max(ours.level, theirs.level),
+                        safety_net: ours.safety_net || theirs.safety_net,
+                    },
+                    MergeStrategy::Ours if ours.level.get() != 0 => ours,
+                    MergeStrategy::Ours | MergeStrategy::Theirs => theirs,
+                };
+                if merged != ours {
+                    set_knowledge(&transaction, &card, merged).map_err(|e| match e {
+                        SetKnowledgeErrorKind::Remove(e) | SetKnowledgeErrorKind::Insert(e) => e,
+                    })?;
+                    summary.cards += 1;
+                }
+            }
+            let mut insert = transaction.prepare(
+                "INSERT INTO history (card, time, correct, hinted) SELECT ?1, ?2, ?3, ?4 \
+                WHERE NOT EXISTS (SELECT * FROM history WHERE card = ?1 AND time = ?2 AND correct = ?3 AND hinted = ?4)",
+            )?;
+            for (card, time, correct, hinted) in history {
+                summary.answers +=
+                    insert.execute(rusqlite::params![card.as_sql(), time, correct, hinted])?;
+            }
+            drop(insert);
+            transaction.commit()?;
+            Ok(summary)
+        })()
+        .map_err(|inner| MergeError { inner })
+    }
+
+    /// Rebuild the database file, reclaiming the space left behind by removed records.
+    pub fn vacuum(&self) -> Result<(), VacuumError> {
+        self.connection
+            .execute_batch("VACUUM")
+            .map_err(|inner| VacuumError { inner })
+    }
+
+    /// Summarize the knowledge and answer history of every card the database has a record of.
+    /// The cards are sorted by their terms.
+    pub fn card_stats(&self) -> Result<Vec<CardStats>, GetCardStatsError> {
+        let mut stats = (|| {
+            self.connection
+                .prepare(
+                    "\
+                        SELECT cards.card, v1.knowledge_level, v1.safety_net,
+                            COUNT(history.card), COALESCE(SUM(history.correct), 0), MAX(history.time)
+                        FROM (SELECT card FROM v1 UNION SELECT card FROM history) AS cards
+                        LEFT JOIN v1 ON v1.card = cards.card
+                        LEFT JOIN history ON history.card = cards.card
+                        GROUP BY cards.card
+                    ",
+                )?
+                .query_map([], |row| {
+                    let level = row.get_unwrap::<_, Option<u8>>(1);
+                    let Some(card) = CardKey::from_sql(row.get_unwrap(0)) else {
+                        return Ok(None);
+                    };
+                    Ok(Some(CardStats {
+                        card,
+                        knowledge: Knowledge {
+                            level: level.map_or_else(Default::default, |level| {
+                                KnowledgeLevel::new(level).unwrap()
+                            }),
+                            safety_net: row.get_unwrap::<_, Option<bool>>(2).unwrap_or(false),
+                        },
+                        attempts: row.get_unwrap(3),
+                        correct: row.get_unwrap(4),
+                        last_seen: row.get_unwrap::<_, Option<i64>>(5).map(from_unix_time),
+                    }))
+                })?
+                .filter_map(Result::transpose)
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })()
+        .map_err(|inner| GetCardStatsError { inner })?;
+
+        stats.sort_by_cached_key(|stats| {
+            let terms = stats.card.terms().into_iter().map(str::to_owned);
+            let definitions = stats.card.definitions().into_iter().map(str::to_owned);
+            (terms.collect::<Vec<_>>(), definitions.collect::<Vec<_>>())
+        });
+        Ok(stats)
+    }
+
+    fn record(&mut self, card: &CardKey, answer: Answer) -> rusqlite::Result<()> {
+        // A savepoint rather than a transaction, since this may be inside a session's transaction.
+        let transaction = self.connection.savepoint()?;
+        let old_knowledge = knowledge(&transaction, card)?;
+        let knowledge = match answer {
+            Answer::Correct => old_knowledge.after_correct(self.levels),
+            Answer::Incorrect => old_knowledge.after_incorrect(self.levels),
+            Answer::Hinted => old_knowledge.after_hinted(),
+            Answer::Recognised(correct) => old_knowledge.after_recognised(correct),
+        };
+        let correct = !matches!(answer, Answer::Incorrect | Answer::Recognised(false));
+        let hinted = answer == Answer::Hinted;
+        set_knowledge(&transaction, card, knowledge).map_err(|e| match e {
+            SetKnowledgeErrorKind::Remove(e) | SetKnowledgeErrorKind::Insert(e) => e,
+        })?;
+        transaction.execute(
+            "INSERT INTO history (card, time, correct, hinted) VALUES (?, ?, ?, ?)",
+            rusqlite::params![
+                card.as_sql(),
+                to_unix_time(SystemTime::now()),
+                correct,
+                hinted
+            ],
+        )?;
+        transaction.commit()
+    }
+}
+
+/// The tables that refer to cards by their keys.
+const CARD_TABLES: [&str; 4] = ["v1", "history", "cleared", "set_cards"];
+
+/// Get every distinct card key in the database that can't be read.
+fn malformed_keys(connection: &rusqlite::Connection) -> rusqlite::Result<Vec<Vec<u8>>> {
+    let sql = CARD_TABLES.map(|table| format!("SELECT card FROM {table}"));
+    let mut keys = connection
+        .prepare(&sql.join(" UNION "))?
+        .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    keys.retain(|key| CardKey::from_bytes(key).is_none());
+    Ok(keys)
+}
+
+/// Sort cards by their terms, then their definitions.
+fn sort_by_text(cards: &mut [CardKey]) {
+    cards.sort_by_cached_key(|card| {
+        let terms = card.terms().into_iter().map(str::to_owned);
+        let definitions = card.definitions().into_iter().map(str::to_owned);
+        (terms.collect::<Vec<_>>(), definitions.collect::<Vec<_>>())
+    });
+}
+
+/// The steps that bring the schema of a database up to date. The schema version stored in the
+/// database's `user_version` is the number of steps that have been run on it, so steps must only
+/// ever be added to the end, never changed or removed.
+const MIGRATIONS: [fn(&rusqlite::Transaction<'_>) -> rusqlite::Result<()>; 6] = [
+    create_tables,
+    migrate_card_keys,
+    add_hinted,
+    add_clears,
+    add_sets,
+    add_set_card_counts,
+];
+
+/// Create the tables, or for databases created before the number of levels was configurable,
+/// recreate the knowledge table without its limit of 3 levels, which can only be changed that way.
+fn create_tables(transaction: &rusqlite::Transaction<'_>) -> rusqlite::Result<()> {
+    let migrate_levels = has_table(transaction, "v1")?;
+    if migrate_levels {
+        transaction.execute("ALTER TABLE v1 RENAME TO v1_old", [])?;
+    }
+    transaction.execute_batch(
+        "\
+            CREATE TABLE v1 (
+                card BLOB NOT NULL PRIMARY KEY,
+                knowledge_level INTEGER NOT NULL CHECK(knowledge_level >= 1 AND knowledge_level <= 15),
+                safety_net INTEGER NOT NULL CHECK(safety_net = 0 OR safety_net = 1)
+            ) WITHOUT ROWID;
+            CREATE TABLE IF NOT EXISTS settings (
+                max_level INTEGER NOT NULL CHECK(max_level >= 1 AND max_level <= 15)
+            );
+            INSERT INTO settings SELECT 3 WHERE NOT EXISTS (SELECT * FROM settings);
+            CREATE TABLE IF NOT EXISTS history (
+                card BLOB NOT NULL,
+                time INTEGER NOT NULL,
+                correct INTEGER NOT NULL CHECK(correct = 0 OR correct = 1)
+            );
+            CREATE INDEX IF NOT EXISTS history_card ON history (card);
+            CREATE INDEX IF NOT EXISTS history_time ON history (time);
+        ",
+    )?;
+    if migrate_levels {
+        transaction.execute_batch("INSERT INTO v1 SELECT * FROM v1_old; DROP TABLE v1_old;")?;
+    }
+    Ok(())
+}
+
+/// Whether the database has a table with the given name.
+fn has_table(connection: &rusqlite::Connection, table: &str) -> rusqlite::Result<bool> {
+    Ok(connection
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?",
+            [table],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+/// Whether a table of the database has a column with the given name.
+fn has_column(
+    connection: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+) -> rusqlite::Result<bool> {
+    Ok(connection
+        .query_row(
+            "SELECT 1 FROM pragma_table_info(?) WHERE name = ?",
+            [table, column],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+/// Record whether each answer was reached with hints.
+fn add_hinted(transaction: &rusqlite::Transaction<'_>) -> rusqlite::Result<()> {
+    if !has_column(transaction, "history", "hinted")? {
+        transaction.execute(
+            "ALTER TABLE history ADD COLUMN hinted INTEGER NOT NULL DEFAULT 0 CHECK(hinted = 0 OR hinted = 1)",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Keep the knowledge that cards had before they were cleared, so that clears can be undone. A
+/// `knowledge_level` of 0 means the card was unknown.
+fn add_clears(transaction: &rusqlite::Transaction<'_>) -> rusqlite::Result<()> {
+    transaction.execute_batch(
+        "\
+            CREATE TABLE IF NOT EXISTS clears (
+                id INTEGER PRIMARY KEY,
+                cleared_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS cleared (
+                clear INTEGER NOT NULL REFERENCES clears (id),
+                card BLOB NOT NULL,
+                knowledge_level INTEGER NOT NULL CHECK(knowledge_level >= 0 AND knowledge_level <= 15),
+                safety_net INTEGER NOT NULL CHECK(safety_net = 0 OR safety_net = 1)
+            );
+            CREATE INDEX IF NOT EXISTS cleared_clear ON cleared (clear);
+        ",
+    )
+}
+
+/// Remember which sets cards were loaded from, so that they can be found without reading the set
+/// files again.
+fn add_sets(transaction: &rusqlite::Transaction<'_>) -> rusqlite::Result<()> {
+    transaction.execute_batch(
+        "\
+            CREATE TABLE IF NOT EXISTS sets (
+                id INTEGER PRIMARY KEY,
+                path TEXT NOT NULL UNIQUE,
+                title TEXT NOT NULL,
+                loaded_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS set_cards (
+                set_id INTEGER NOT NULL REFERENCES sets (id),
+                card BLOB NOT NULL,
+                PRIMARY KEY (set_id, card)
+            ) WITHOUT ROWID;
+            CREATE INDEX IF NOT EXISTS set_cards_card ON set_cards (card);
+        ",
+    )
+}
+
+/// Store the number of cards in each set, since sets now also have their cards' inverted keys.
+/// Sets recorded before then only have the cards the way round they are written.
+fn add_set_card_counts(transaction: &rusqlite::Transaction<'_>) -> rusqlite::Result<()> {
+    if !has_column(transaction, "sets", "cards")? {
+        transaction.execute_batch(
+            "\
+                ALTER TABLE sets ADD COLUMN cards INTEGER NOT NULL DEFAULT 0;
+                UPDATE sets SET cards = (SELECT COUNT(*) FROM set_cards WHERE set_id = sets.id);
+            ",
+        )?;
+    }
+    Ok(())
+}
+
+/// Convert every card key stored in the version 1 format to the current format. Keys that can't be
+/// read at all are left as they are, and skipped by everything that reads keys back.
+fn migrate_card_keys(transaction: &rusqlite::Transaction<'_>) -> rusqlite::Result<()> {
+    for table in ["v1", "history"] {
+        let cards = transaction
+            .prepare(&format!("SELECT DISTINCT card FROM {table}"))?
+            .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut update =
+            transaction.prepare(&format!("UPDATE {table} SET card = ? WHERE card = ?"))?;
+        for card in cards {
+            if let Some(key) = CardKey::from_bytes(&card) {
+                if key.as_bytes() != card {
+                    update.execute(rusqlite::params![key.as_bytes(), card])?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How [`Database::merge`] combines the knowledge of a card that both databases know.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Take the higher of the two levels, with a safety net if either has one.
+    Highest,
+    /// Keep this database's knowledge.
+    Ours,
+    /// Take the other database's knowledge.
+    Theirs,
+}
+
+/// What [`Database::merge`] changed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// The number of cards whose knowledge changed.
+    pub cards: usize,
+    /// The number of answers added to the history.
+    pub answers: usize,
+}
+
+/// A clear restored by [`Database::unclear`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unclear {
+    /// When the cards were cleared, to the nearest second.
+    pub cleared_at: SystemTime,
+    /// The number of cards that were cleared and have had their knowledge restored. A card cleared
+    /// along with its inverted key is counted once.
+    pub cards: usize,
+}
+
+/// A set recorded with [`Database::record_set`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetRecord {
+    /// The path the set was loaded from.
+    pub path: PathBuf,
+    /// The title of the set when it was last loaded.
+    pub title: String,
+    /// When the set was last loaded, to the nearest second.
+    pub loaded_at: SystemTime,
+    /// The number of cards that were in the set when it was last loaded.
+    pub cards: usize,
+}
+
+/// An answer to a question about a card, recorded in the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// When the answer was given, to the nearest second.
+    pub time: SystemTime,
+    /// Whether the answer was correct.
+    pub correct: bool,
+    /// Whether hints were used to reach a correct answer.
+    pub hinted: bool,
+}
+
+/// The kinds of answer that can be recorded.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Answer {
+    Correct,
+    Incorrect,
+    Hinted,
+    Recognised(bool),
+}
+
+/// A summary of a card's knowledge and answer history, from [`Database::card_stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardStats {
+    /// The card.
+    pub card: CardKey,
+    /// How well the card is known.
+    pub knowledge: Knowledge,
+    /// The number of times the card has been answered.
+    pub attempts: u64,
+    /// The number of times the card has been answered correctly.
+    pub correct: u64,
+    /// When the card was last answered, if it ever has been.
+    pub last_seen: Option<SystemTime>,
+}
+
+#[allow(clippy::cast_possible_wrap)]
+fn to_unix_time(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    }
+}
+
+#[allow(clippy::cast_sign_loss)]
+fn from_unix_time(secs: i64) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+    }
+}
+
+impl KnowledgeStore for Database {
+    fn knowledge(&self, card: &CardKey) -> Result<Knowledge, GetKnowledgeError> {
+        knowledge(&self.connection, card).map_err(|inner| GetKnowledgeError { inner })
+    }
+
+    fn knowledge_all<'a, I>(
         &self,
         cards: I,
-    ) -> Result<impl 'a + Clone + Iterator<Item = (&'a CardKey, Knowledge)>, GetKnowledgeError>
+    ) -> Result<
+        impl 'a + Clone + ExactSizeIterator<Item = (&'a CardKey, Knowledge)>,
+        GetKnowledgeError,
+    >
     where
         I: IntoIterator<Item = &'a CardKey>,
         I::IntoIter: ExactSizeIterator + Clone + 'a,
@@ -104,14 +1070,14 @@ impl Database {
                 .query_map(
                     rusqlite::params_from_iter(cards.clone().map(CardKey::as_sql)),
                     |row| {
-                        let card_key = CardKey::from_sql(row.get_unwrap(0));
                         let knowledge = Knowledge {
                             level: KnowledgeLevel::new(row.get_unwrap(1)).unwrap(),
                             safety_net: row.get_unwrap(2),
                         };
-                        Ok((card_key, knowledge))
+                        Ok(CardKey::from_sql(row.get_unwrap(0)).map(|card| (card, knowledge)))
                     },
                 )?
+                .filter_map(Result::transpose)
                 .collect::<Result<HashMap<_, _>, _>>()?;
 
             Ok(cards.map(move |card| (card, result.get(card).copied().unwrap_or_default())))
@@ -119,29 +1085,15 @@ impl Database {
         .map_err(|inner| GetKnowledgeError { inner })
     }
 
-    /// Set the knowledge of a card.
-    pub fn set_knowledge(
+    fn set_knowledge(
         &mut self,
         card: &CardKey,
         knowledge: Knowledge,
     ) -> Result<(), SetKnowledgeError> {
-        if knowledge.level.get() == 0 {
-            self.connection
-                .execute("DELETE FROM v1 WHERE card = ?", [card.as_sql()])
-                .map_err(SetKnowledgeErrorKind::Remove)?;
-        } else {
-            self.connection
-                .execute(
-                    "INSERT INTO v1 VALUES (?, ?, ?) ON CONFLICT(card) DO UPDATE SET knowledge_level = ?2, safety_net = ?3",
-                    rusqlite::params![card.as_sql(), knowledge.level.get(), knowledge.safety_net],
-                )
-                .map_err(SetKnowledgeErrorKind::Insert)?;
-        }
-        Ok(())
+        Ok(set_knowledge(&self.connection, card, knowledge)?)
     }
 
-    /// Set the knowledge of several cards.
-    pub fn set_knowledge_all<'a, I>(
+    fn set_knowledge_all<'a, I>(
         &mut self,
         cards: I,
         knowledge: Knowledge,
@@ -178,34 +1130,88 @@ impl Database {
         Ok(())
     }
 
-    /// Record the answer to a question as correct.
-    pub fn record_correct(&mut self, card: &CardKey) -> Result<(), RecordCorrectError> {
-        self.connection
-            .execute(
-                "INSERT INTO v1 VALUES (?, 1, true) ON CONFLICT(card) DO UPDATE SET knowledge_level=min(knowledge_level+1, 3), safety_net=true",
-                [card.as_sql()],
-            )
-            .map_err(|inner| RecordCorrectError { inner })?;
-        Ok(())
+    fn record_correct(&mut self, card: &CardKey) -> Result<(), RecordCorrectError> {
+        self.record(card, Answer::Correct)
+            .map_err(|inner| RecordCorrectError { inner })
     }
 
-    /// Record the answer to a question as incorrect.
-    pub fn record_incorrect(&mut self, card: &CardKey) -> Result<(), RecordIncorrectError> {
+    fn record_incorrect(&mut self, card: &CardKey) -> Result<(), RecordIncorrectError> {
+        self.record(card, Answer::Incorrect)
+            .map_err(|inner| RecordIncorrectError { inner })
+    }
+
+    fn record_hinted(&mut self, card: &CardKey) -> Result<(), RecordHintedError> {
+        self.record(card, Answer::Hinted)
+            .map_err(|inner| RecordHintedError { inner })
+    }
+
+    fn record_recognised(
+        &mut self,
+        card: &CardKey,
+        correct: bool,
+    ) -> Result<(), RecordRecognisedError> {
+        self.record(card, Answer::Recognised(correct))
+            .map_err(|inner| RecordRecognisedError { inner })
+    }
+
+    fn levels(&self) -> Levels {
+        self.levels
+    }
+
+    fn set_levels(&mut self, levels: Levels) -> Result<(), SetLevelsError> {
         (|| {
-            let transaction = self.connection.transaction()?;
+            let max_level = levels.max().get();
+            let transaction = self.connection.savepoint()?;
+            transaction.execute("UPDATE settings SET max_level = ?", [max_level])?;
             transaction.execute(
-                "DELETE FROM v1 WHERE card = ? AND knowledge_level = 1 AND safety_net = false",
-                [card.as_sql()],
-            )?;
-            transaction.execute(
-                "UPDATE v1 SET knowledge_level = IIF(safety_net AND knowledge_level < 3, knowledge_level, knowledge_level - 1), safety_net = false WHERE card = ?",
-                [card.as_sql()],
+                "UPDATE v1 SET knowledge_level = ?1 WHERE knowledge_level > ?1",
+                [max_level],
             )?;
-            transaction.commit()?;
-            Ok(())
+            transaction.commit()
         })()
-        .map_err(|inner| RecordIncorrectError { inner})
+        .map_err(|inner| SetLevelsError { inner })?;
+        self.levels = levels;
+        Ok(())
+    }
+}
+
+fn knowledge(connection: &rusqlite::Connection, card: &CardKey) -> rusqlite::Result<Knowledge> {
+    connection
+        .query_row(
+            "SELECT knowledge_level,safety_net FROM v1 WHERE card = ?",
+            [card.as_sql()],
+            |row| {
+                Ok(Knowledge {
+                    level: KnowledgeLevel::new(row.get_unwrap(0)).unwrap(),
+                    safety_net: row.get_unwrap(1),
+                })
+            },
+        )
+        .inspect(|knowledge| {
+            assert_ne!(knowledge.level.get(), 0);
+        })
+        .optional()
+        .map(Option::unwrap_or_default)
+}
+
+fn set_knowledge(
+    connection: &rusqlite::Connection,
+    card: &CardKey,
+    knowledge: Knowledge,
+) -> Result<(), SetKnowledgeErrorKind> {
+    if knowledge.level.get() == 0 {
+        connection
+            .execute("DELETE FROM v1 WHERE card = ?", [card.as_sql()])
+            .map_err(SetKnowledgeErrorKind::Remove)?;
+    } else {
+        connection
+            .execute(
+                "INSERT INTO v1 VALUES (?, ?, ?) ON CONFLICT(card) DO UPDATE SET knowledge_level = ?2, safety_net = ?3",
+                rusqlite::params![card.as_sql(), knowledge.level.get(), knowledge.safety_net],
+            )
+            .map_err(SetKnowledgeErrorKind::Insert)?;
     }
+    Ok(())
 }
 
 /// Error in [`Database::open`].
@@ -213,7 +1219,7 @@ impl Database {
 #[error("failed to open database at `{}`", path.display())]
 pub struct OpenError {
     #[source]
-    inner: rusqlite::Error,
+    inner: OpenErrorKind,
     path: PathBuf,
 }
 
@@ -221,11 +1227,35 @@ pub struct OpenError {
 #[derive(Debug, Error)]
 #[error("failed to open in-memory database")]
 pub struct OpenInMemoryError {
+    #[source]
+    inner: OpenErrorKind,
+}
+
+#[derive(Debug, Error)]
+enum OpenErrorKind {
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("the database has schema version {version}, which is newer than this version of `revise` supports")]
+    TooNew { version: usize },
+}
+
+/// Error in [`Database::session`].
+#[derive(Debug, Error)]
+#[error("failed to start a database session")]
+pub struct StartSessionError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`KnowledgeStore::flush`].
+#[derive(Debug, Error)]
+#[error("failed to save answers to the database")]
+pub struct FlushError {
     #[source]
     inner: rusqlite::Error,
 }
 
-/// Error in [`Database::knowledge`] or [`Database::knowledge_all`].
+/// Error in [`KnowledgeStore::knowledge`] or [`KnowledgeStore::knowledge_all`].
 #[derive(Debug, Error)]
 #[error("failed to retrieve knowledge of a card")]
 pub struct GetKnowledgeError {
@@ -233,7 +1263,7 @@ pub struct GetKnowledgeError {
     inner: rusqlite::Error,
 }
 
-/// Error in [`Database::set_knowledge`] or [`Database::set_knowledge_all`].
+/// Error in [`KnowledgeStore::set_knowledge`] or [`KnowledgeStore::set_knowledge_all`].
 #[derive(Debug, Error)]
 #[error("failed to set the knowledge of a card")]
 pub struct SetKnowledgeError(
@@ -250,7 +1280,7 @@ enum SetKnowledgeErrorKind {
     Insert(#[source] rusqlite::Error),
 }
 
-/// Error in [`Database::record_correct`].
+/// Error in [`KnowledgeStore::record_correct`].
 #[derive(Debug, Error)]
 #[error("failed to record card as correct")]
 pub struct RecordCorrectError {
@@ -258,7 +1288,7 @@ pub struct RecordCorrectError {
     inner: rusqlite::Error,
 }
 
-/// Error in [`Database::record_incorrect`].
+/// Error in [`KnowledgeStore::record_incorrect`].
 #[derive(Debug, Error)]
 #[error("failed to record card as incorrect")]
 pub struct RecordIncorrectError {
@@ -266,6 +1296,142 @@ pub struct RecordIncorrectError {
     inner: rusqlite::Error,
 }
 
+/// Error in [`KnowledgeStore::record_hinted`].
+#[derive(Debug, Error)]
+#[error("failed to record card as correct with hints")]
+pub struct RecordHintedError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`KnowledgeStore::record_recognised`].
+#[derive(Debug, Error)]
+#[error("failed to record whether a statement about a card was recognised")]
+pub struct RecordRecognisedError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`KnowledgeStore::set_levels`].
+#[derive(Debug, Error)]
+#[error("failed to change the number of knowledge levels")]
+pub struct SetLevelsError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::card_text`].
+#[derive(Debug, Error)]
+#[error("failed to look up the text of a card")]
+pub struct GetCardTextError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::search_terms`].
+#[derive(Debug, Error)]
+#[error("failed to search the cards in the database")]
+pub struct SearchTermsError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::history`] or [`Database::recent_activity`].
+#[derive(Debug, Error)]
+#[error("failed to retrieve answer history")]
+pub struct GetHistoryError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::integrity_check`].
+#[derive(Debug, Error)]
+#[error("failed to check the database for corruption")]
+pub struct IntegrityCheckError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::orphans`].
+#[derive(Debug, Error)]
+#[error("failed to look for cards that are in no set")]
+pub struct GetOrphansError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::malformed_keys`].
+#[derive(Debug, Error)]
+#[error("failed to look for cards that can't be read")]
+pub struct GetMalformedKeysError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::record_set`].
+#[derive(Debug, Error)]
+#[error("failed to record the cards in a set")]
+pub struct RecordSetError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::sets`] or [`Database::set_cards`].
+#[derive(Debug, Error)]
+#[error("failed to get the sets recorded in the database")]
+pub struct GetSetsError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::prune`].
+#[derive(Debug, Error)]
+#[error("failed to remove cards from the database")]
+pub struct PruneError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::clear`].
+#[derive(Debug, Error)]
+#[error("failed to clear the knowledge of cards")]
+pub struct ClearError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::unclear`].
+#[derive(Debug, Error)]
+#[error("failed to restore the knowledge of cleared cards")]
+pub struct UnclearError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::merge`].
+#[derive(Debug, Error)]
+#[error("failed to merge the databases")]
+pub struct MergeError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::vacuum`].
+#[derive(Debug, Error)]
+#[error("failed to vacuum the database")]
+pub struct VacuumError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
+/// Error in [`Database::card_stats`].
+#[derive(Debug, Error)]
+#[error("failed to summarize the cards in the database")]
+pub struct GetCardStatsError {
+    #[source]
+    inner: rusqlite::Error,
+}
+
 /// Error in [`Database::level_distribution`].
 #[derive(Debug, Error)]
 #[error("failed to get distribution of card knowledge")]
@@ -310,9 +1476,30 @@ impl<F: Fn(usize, &mut Formatter<'_>) -> fmt::Result> Display for CommaSeparated
 
 #[test]
 fn test_database() {
-    use maplit::btreeset;
+    test_store(Database::open_in_memory().unwrap());
+}
 
-    let mut db = Database::open_in_memory().unwrap();
+#[test]
+fn test_help() {
+    let path = std::env::temp_dir().join(format!("revise-test-help-{}", std::process::id()));
+    std::fs::write(&path, [0xAB; 4096]).unwrap();
+    let error = Database::open(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(help(&error), None);
+    assert!(help(error.source().unwrap())
+        .unwrap()
+        .contains("--database"));
+}
+
+#[test]
+fn test_memory_store() {
+    test_store(MemoryStore::new());
+}
+
+#[cfg(test)]
+fn test_store<S: KnowledgeStore>(mut db: S) {
+    use maplit::btreeset;
 
     let cards = [
         CardKey::new(&btreeset!("t"), &btreeset!("definition")),
@@ -323,7 +1510,7 @@ fn test_database() {
         assert_eq!(db.knowledge(card).unwrap(), Knowledge::default());
 
         for level in (0..=3).rev() {
-            let level = KnowledgeLevel(level);
+            let level = KnowledgeLevel::new(level).unwrap();
             for safety_net in [false, true] {
                 let knowledge = Knowledge { level, safety_net };
                 db.set_knowledge(card, knowledge).unwrap();
@@ -353,7 +1540,7 @@ fn test_database() {
         }
     }
 
-    let assert_knowledge = |db: &Database, levels: [(u8, bool); 2]| {
+    let assert_knowledge = |db: &S, levels: [(u8, bool); 2]| {
         for (card, (level, safety_net)) in cards.iter().zip(levels) {
             let knowledge = db.knowledge(card).unwrap();
             assert_eq!(knowledge.level.get(), level);
@@ -387,7 +1574,7 @@ fn test_database() {
     assert_knowledge(&db, [(3, true), (3, true)]);
 
     for level in 0..=3 {
-        let level = KnowledgeLevel(level);
+        let level = KnowledgeLevel::new(level).unwrap();
         for safety_net in [false, true] {
             let knowledge = Knowledge { level, safety_net };
             db.set_knowledge_all(&cards, knowledge).unwrap();
@@ -399,121 +1586,409 @@ fn test_database() {
             }
         }
     }
+
+    assert_eq!(db.levels(), Levels::DEFAULT);
+    db.set_levels(Levels::new(6).unwrap()).unwrap();
+    for _ in 0..8 {
+        db.record_correct(&cards[0]).unwrap();
+    }
+    assert_knowledge(&db, [(5, true), (3, true)]);
+
+    db.set_levels(Levels::new(3).unwrap()).unwrap();
+    assert_eq!(db.levels().max().get(), 2);
+    assert_knowledge(&db, [(2, true), (2, true)]);
+    db.record_correct(&cards[1]).unwrap();
+    assert_knowledge(&db, [(2, true), (2, true)]);
 }
 
-/// A unique key that every card has.
-#[allow(clippy::derived_hash_with_manual_eq)]
-#[derive(Debug, Clone, Eq, Hash)]
-pub struct CardKey(Vec<u8>);
+#[test]
+fn test_migration() {
+    use maplit::btreeset;
 
-impl CardKey {
-    /// Compute the card key for the given card's terms and definitions.
-    ///
-    /// # Panics
-    ///
-    /// Panics if either set is empty.
-    #[must_use]
-    pub fn new<T, D>(terms: &BTreeSet<T>, definitions: &BTreeSet<D>) -> Self
-    where
-        T: AsRef<[u8]>,
-        D: AsRef<[u8]>,
-    {
-        assert!(!terms.is_empty());
-        assert!(!definitions.is_empty());
+    let connection = rusqlite::Connection::open_in_memory().unwrap();
+    connection
+        .execute_batch(
+            "\
+                CREATE TABLE v1 (
+                    card BLOB NOT NULL PRIMARY KEY,
+                    knowledge_level INTEGER NOT NULL CHECK(knowledge_level >= 1 AND knowledge_level <= 3),
+                    safety_net INTEGER NOT NULL CHECK(safety_net = 0 OR safety_net = 1)
+                ) WITHOUT ROWID;
+            ",
+        )
+        .unwrap();
+    let card = CardKey::new(&btreeset!("a"), &btreeset!("b"));
+    let v1_card = card_key::v1(&btreeset!("a"), &btreeset!("b"));
+    connection
+        .execute("INSERT INTO v1 VALUES (?, 3, 1)", [&v1_card])
+        .unwrap();
 
-        struct SerializeSet<'a, T>(&'a BTreeSet<T>);
-        impl<T: AsRef<[u8]>> Serialize for SerializeSet<'_, T> {
-            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-                serializer.collect_seq(self.0.iter().map(AsRef::as_ref))
-            }
-        }
+    let db = Database::new(connection).unwrap();
+    assert_eq!(db.levels(), Levels::DEFAULT);
+    assert_eq!(db.knowledge(&card).unwrap().level.get(), 3);
 
-        let bincode = bincode::DefaultOptions::new()
-            .serialize(&(SerializeSet(terms), SerializeSet(definitions)))
-            .unwrap();
+    db.connection
+        .execute(
+            "INSERT INTO history (card, time, correct) VALUES (?, 0, 1)",
+            [&v1_card],
+        )
+        .unwrap();
+    db.connection
+        .execute("PRAGMA user_version = 1", [])
+        .unwrap();
+    let mut db = Database::new(db.connection).unwrap();
+    assert_eq!(db.history(&card).unwrap().len(), 1);
 
-        Self(bincode)
-    }
+    db.set_levels(Levels::new(8).unwrap()).unwrap();
+    db.record_correct(&card).unwrap();
+    assert_eq!(db.knowledge(&card).unwrap().level.get(), 4);
 
-    fn as_sql(&self) -> &impl ToSql {
-        &self.0
-    }
+    let db = Database::new(db.connection).unwrap();
+    assert_eq!(db.levels().count(), 8);
+    assert_eq!(db.knowledge(&card).unwrap().level.get(), 4);
+    let version: usize = db
+        .connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, MIGRATIONS.len());
 
-    fn from_sql(sql: Vec<u8>) -> Self {
-        Self(sql)
-    }
+    db.connection
+        .execute(&format!("PRAGMA user_version = {}", version + 1), [])
+        .unwrap();
+    let error = Database::new(db.connection).unwrap_err();
+    assert!(matches!(error, OpenErrorKind::TooNew { version: v } if v == version + 1));
+    assert!(help(&error).is_some());
 }
 
-impl PartialEq for CardKey {
-    fn eq(&self, other: &Self) -> bool {
-        ptr::eq(self, other) || self.0 == other.0
-    }
-}
+#[test]
+fn test_malformed_keys() {
+    use maplit::btreeset;
+
+    let mut db = Database::open_in_memory().unwrap();
+    let card = CardKey::new(&btreeset!("a"), &btreeset!("b"));
+    db.record_correct(&card).unwrap();
+    let garbage = vec![0xFF_u8; 7];
+    db.connection
+        .execute("INSERT INTO v1 VALUES (?, 2, 1)", [&garbage])
+        .unwrap();
+    db.connection
+        .execute(
+            "INSERT INTO history (card, time, correct) VALUES (?, 0, 1)",
+            [&garbage],
+        )
+        .unwrap();
 
-/// How well you know a card.
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Knowledge {
-    /// The level from 0 to 3.
-    pub level: KnowledgeLevel,
-    /// A safety net prevents you from going down a level if you get it wrong. It is replenished
-    /// once you get a question right.
-    pub safety_net: bool,
+    let stats = db.card_stats().unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].card, card);
+    assert_eq!(
+        db.orphans(&HashSet::new()).unwrap(),
+        std::slice::from_ref(&card)
+    );
+    let activity = db
+        .recent_activity(SystemTime::UNIX_EPOCH..SystemTime::now() + Duration::from_mins(1))
+        .unwrap();
+    assert_eq!(activity.len(), 1);
+    assert_eq!(activity[0].0, card);
+
+    assert_eq!(db.malformed_keys().unwrap(), 1);
+    assert_eq!(db.prune_malformed_keys().unwrap(), 1);
+    assert_eq!(db.malformed_keys().unwrap(), 0);
+    assert_eq!(db.card_stats().unwrap().len(), 1);
+    let rows: usize = db
+        .connection
+        .query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(rows, 1);
 }
 
-/// Integer ranging from 0 to 3.
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct KnowledgeLevel(u8);
+#[test]
+fn test_merge() {
+    use maplit::btreeset;
 
-impl KnowledgeLevel {
-    /// Create a new `KnowledgeLevel`. Returns `None` if the value is >3.
-    #[must_use]
-    pub const fn new(value: u8) -> Option<Self> {
-        if value <= 3 {
-            Some(Self(value))
-        } else {
-            None
+    let card = |name| CardKey::new(&btreeset!(name), &btreeset!("x"));
+    let (a, b, c) = (card("a"), card("b"), card("c"));
+    let at = |level, safety_net| Knowledge {
+        level: KnowledgeLevel::new(level).unwrap(),
+        safety_net,
+    };
+
+    let mut theirs = Database::open_in_memory().unwrap();
+    theirs.set_levels(Levels::new(8).unwrap()).unwrap();
+    theirs.set_knowledge(&a, at(1, true)).unwrap();
+    theirs.set_knowledge(&b, at(7, false)).unwrap();
+    theirs.record_incorrect(&c).unwrap();
+    theirs.record_correct(&c).unwrap();
+
+    let merged = |strategy, expected: [Knowledge; 3]| {
+        let mut ours = Database::open_in_memory().unwrap();
+        ours.set_knowledge(&a, at(2, false)).unwrap();
+        ours.record_correct(&b).unwrap();
+        let summary = ours.merge(&theirs, strategy).unwrap();
+        assert_eq!(summary.answers, 2);
+        for (card, expected) in [&a, &b, &c].into_iter().zip(expected) {
+            assert_eq!(ours.knowledge(card).unwrap(), expected);
         }
-    }
+        assert_eq!(ours.history(&c).unwrap().len(), 2);
+        let again = ours.merge(&theirs, strategy).unwrap();
+        assert_eq!(again, MergeSummary::default());
+        summary.cards
+    };
 
-    /// Get the knowledge level as a `u8`.
-    #[must_use]
-    pub const fn get(self) -> u8 {
-        self.0
-    }
+    let expected = [at(2, true), at(3, true), at(1, true)];
+    assert_eq!(merged(MergeStrategy::Highest, expected), 3);
+    let expected = [at(2, false), at(1, true), at(1, true)];
+    assert_eq!(merged(MergeStrategy::Ours, expected), 1);
+    let expected = [at(1, true), at(3, false), at(1, true)];
+    assert_eq!(merged(MergeStrategy::Theirs, expected), 3);
 }
 
-impl FromStr for KnowledgeLevel {
-    type Err = ParseKnowledgeLevelError;
+#[test]
+fn test_clear() {
+    use maplit::btreeset;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "0" => Self(0),
-            "1" => Self(1),
-            "2" => Self(2),
-            "3" => Self(3),
-            _ => {
-                return Err(ParseKnowledgeLevelError {
-                    given: s.to_owned(),
-                })
-            }
-        })
-    }
-}
+    let card = |name| CardKey::new(&btreeset!(name), &btreeset!("x"));
+    let (a, b, c) = (card("a"), card("b"), card("c"));
+    let at = |level, safety_net| Knowledge {
+        level: KnowledgeLevel::new(level).unwrap(),
+        safety_net,
+    };
 
-/// An error parsing a [`KnowledgeLevel`].
-#[derive(Debug)]
-pub struct ParseKnowledgeLevelError {
-    given: String,
+    let mut db = Database::open_in_memory().unwrap();
+    assert_eq!(db.unclear().unwrap(), None);
+    db.record_correct(&a).unwrap();
+    db.set_knowledge(&a, at(2, true)).unwrap();
+    db.set_knowledge(&b, at(3, false)).unwrap();
+    let inverted_a = CardKey::new(&btreeset!("x"), &btreeset!("a"));
+    db.clear([&a, &b, &c, &inverted_a], at(0, false)).unwrap();
+    db.clear([&b, &c], at(1, false)).unwrap();
+
+    let state = |db: &Database| [&a, &b, &c].map(|card| db.knowledge(card).unwrap());
+    assert_eq!(state(&db), [at(0, false), at(1, false), at(1, false)]);
+    assert_eq!(db.unclear().unwrap().unwrap().cards, 2);
+    assert_eq!(state(&db), [at(0, false), at(0, false), at(0, false)]);
+    assert_eq!(db.unclear().unwrap().unwrap().cards, 3);
+    assert_eq!(state(&db), [at(2, true), at(3, false), at(0, false)]);
+    assert_eq!(db.unclear().unwrap(), None);
+    assert_eq!(db.history(&a).unwrap().len(), 1);
 }
 
-impl Display for ParseKnowledgeLevelError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "invalid knowledge level {}; expected 0, 1, 2 or 3",
-            self.given
+#[test]
+fn test_merge_old_read_only() {
+    use maplit::btreeset;
+
+    let path = std::env::temp_dir().join(format!("revise-test-merge-{}", std::process::id()));
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    connection
+        .execute_batch(
+            "\
+                CREATE TABLE v1 (
+                    card BLOB NOT NULL PRIMARY KEY,
+                    knowledge_level INTEGER NOT NULL CHECK(knowledge_level >= 1 AND knowledge_level <= 3),
+                    safety_net INTEGER NOT NULL CHECK(safety_net = 0 OR safety_net = 1)
+                ) WITHOUT ROWID;
+                CREATE TABLE history (card BLOB NOT NULL, time INTEGER NOT NULL, correct INTEGER NOT NULL);
+            ",
         )
-    }
+        .unwrap();
+    let card = CardKey::new(&btreeset!("a"), &btreeset!("b"));
+    let v1_card = card_key::v1(&btreeset!("a"), &btreeset!("b"));
+    connection
+        .execute("INSERT INTO v1 VALUES (?, 3, 1)", [&v1_card])
+        .unwrap();
+    connection
+        .execute("INSERT INTO history VALUES (?, 0, 1)", [&v1_card])
+        .unwrap();
+    drop(connection);
+    let before = std::fs::read(&path).unwrap();
+
+    let theirs = Database::open_read_only(&path).unwrap();
+    let mut ours = Database::open_in_memory().unwrap();
+    let summary = ours.merge(&theirs, MergeStrategy::Highest).unwrap();
+    assert_eq!(
+        summary,
+        MergeSummary {
+            cards: 1,
+            answers: 1
+        }
+    );
+    assert_eq!(ours.knowledge(&card).unwrap().level.get(), 3);
+    assert!(!ours.history(&card).unwrap()[0].hinted);
+    drop(theirs);
+
+    assert_eq!(std::fs::read(&path).unwrap(), before);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_sets() {
+    use maplit::btreeset;
+
+    let card = |name| CardKey::new(&btreeset!(name), &btreeset!("x"));
+    let (a, b, c) = (card("a"), card("b"), card("c"));
+    let (first, second) = (Path::new("/sets/first.set"), Path::new("/sets/second.set"));
+
+    let mut db = Database::open_in_memory().unwrap();
+    assert_eq!(db.sets().unwrap(), []);
+    assert_eq!(db.set_cards(first).unwrap(), None);
+    db.record_set(second, "Second", [&c]).unwrap();
+    db.record_set(first, "First", [&a, &b]).unwrap();
+    db.record_set(first, "First again", [&b, &c, &c]).unwrap();
+
+    let sets = db.sets().unwrap();
+    let summary = sets
+        .iter()
+        .map(|set| (set.path.as_path(), set.title.as_str(), set.cards))
+        .collect::<Vec<_>>();
+    assert_eq!(summary, [(first, "First again", 2), (second, "Second", 1)]);
+    let inverted = |name| CardKey::new(&btreeset!("x"), &btreeset!(name));
+    assert_eq!(
+        db.set_cards(first).unwrap().unwrap(),
+        [b.clone(), c.clone(), inverted("b"), inverted("c")]
+    );
+
+    db.prune(&[c, inverted("c")]).unwrap();
+    assert_eq!(db.set_cards(first).unwrap().unwrap(), [b, inverted("b")]);
+    assert_eq!(db.set_cards(second).unwrap().unwrap(), []);
+    assert_eq!(db.sets().unwrap()[1].cards, 1);
+}
+
+#[test]
+fn test_card_text() {
+    use maplit::btreeset;
+
+    let mut db = Database::open_in_memory().unwrap();
+    let cards = [
+        CardKey::new(&btreeset!("Bonjour", "salut"), &btreeset!("hello")),
+        CardKey::new(&btreeset!("au revoir"), &btreeset!("goodbye", "bye")),
+        CardKey::new(&btreeset!("jour"), &btreeset!("day")),
+    ];
+
+    assert_eq!(db.card_text(&cards[0]).unwrap(), None);
+    assert_eq!(db.describe(&cards[0]).unwrap(), None);
+    assert_eq!(db.search_terms("jour").unwrap(), []);
+
+    db.record_correct(&cards[0]).unwrap();
+    db.record_incorrect(&cards[1]).unwrap();
+    db.record_incorrect(&cards[2]).unwrap();
+
+    assert_eq!(
+        db.card_text(&cards[0]).unwrap(),
+        Some((
+            vec!["Bonjour".to_owned(), "salut".to_owned()],
+            vec!["hello".to_owned()]
+        ))
+    );
+    assert_eq!(
+        db.card_text(&cards[1]).unwrap(),
+        Some((
+            vec!["au revoir".to_owned()],
+            vec!["bye".to_owned(), "goodbye".to_owned()]
+        ))
+    );
+    assert_eq!(
+        db.describe(&cards[1]).unwrap().as_deref(),
+        Some("au revoir - bye, goodbye")
+    );
+    assert_eq!(
+        db.search_terms("JOUR").unwrap(),
+        [cards[0].clone(), cards[2].clone()]
+    );
+    assert_eq!(db.search_terms("revoir").unwrap(), [cards[1].clone()]);
+    assert_eq!(db.search_terms("hello").unwrap(), []);
 }
 
-impl Error for ParseKnowledgeLevelError {}
+#[test]
+fn test_history() {
+    use maplit::btreeset;
+
+    let mut db = Database::open_in_memory().unwrap();
+    let cards = [
+        CardKey::new(&btreeset!("a"), &btreeset!("b")),
+        CardKey::new(&btreeset!("c"), &btreeset!("d")),
+    ];
+
+    let before = from_unix_time(to_unix_time(SystemTime::now()));
+    db.record_correct(&cards[0]).unwrap();
+    db.record_incorrect(&cards[1]).unwrap();
+    db.record_incorrect(&cards[0]).unwrap();
+    db.record_hinted(&cards[1]).unwrap();
+    db.set_knowledge(&cards[0], Knowledge::default()).unwrap();
+    let after = SystemTime::now() + Duration::from_secs(1);
+
+    let recognised = CardKey::new(&btreeset!("e"), &btreeset!("f"));
+    db.record_correct(&recognised).unwrap();
+    db.record_recognised(&recognised, false).unwrap();
+    let knowledge = db.knowledge(&recognised).unwrap();
+    assert_eq!((knowledge.level.get(), knowledge.safety_net), (1, false));
+    let history = db.history(&recognised).unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|entry| (entry.correct, entry.hinted))
+            .collect::<Vec<_>>(),
+        [(true, false), (false, false)]
+    );
+    db.prune(std::slice::from_ref(&recognised)).unwrap();
+
+    let history = db.history(&cards[0]).unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|entry| entry.correct)
+            .collect::<Vec<_>>(),
+        [true, false]
+    );
+    assert!(history
+        .iter()
+        .all(|entry| (before..after).contains(&entry.time)));
+
+    let activity = db.recent_activity(before..after).unwrap();
+    assert_eq!(
+        activity
+            .iter()
+            .map(|(card, entry)| (card, entry.correct, entry.hinted))
+            .collect::<Vec<_>>(),
+        [
+            (&cards[0], true, false),
+            (&cards[1], false, false),
+            (&cards[0], false, false),
+            (&cards[1], true, true),
+        ]
+    );
+    assert_eq!(db.knowledge(&cards[1]).unwrap(), Knowledge::default());
+    assert_eq!(db.recent_activity(after..after).unwrap(), []);
+
+    let stats = db.card_stats().unwrap();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].card, cards[0]);
+    assert_eq!(stats[0].knowledge, Knowledge::default());
+    assert_eq!((stats[0].attempts, stats[0].correct), (2, 1));
+    assert!((before..after).contains(&stats[0].last_seen.unwrap()));
+    assert_eq!((stats[1].attempts, stats[1].correct), (2, 1));
+
+    let level = KnowledgeLevel::new(2).unwrap();
+    let knowledge = Knowledge {
+        level,
+        safety_net: true,
+    };
+    let unseen = CardKey::new(&btreeset!("e"), &btreeset!("f"));
+    db.set_knowledge(&unseen, knowledge).unwrap();
+    let stats = db.card_stats().unwrap();
+    assert_eq!(stats[2].card, unseen);
+    assert_eq!(stats[2].knowledge, knowledge);
+    assert_eq!((stats[2].attempts, stats[2].last_seen), (0, None));
+
+    assert_eq!(db.integrity_check().unwrap(), Vec::<String>::new());
+    let known = [cards[0].clone()].into_iter().collect();
+    assert_eq!(db.orphans(&known).unwrap(), [cards[1].clone(), unseen]);
+    db.prune(&[cards[1].clone()]).unwrap();
+    assert_eq!(db.card_text(&cards[1]).unwrap(), None);
+    assert_eq!(db.history(&cards[0]).unwrap().len(), 2);
+    assert_eq!(db.card_stats().unwrap().len(), 2);
+    db.vacuum().unwrap();
+
+    assert_eq!(from_unix_time(to_unix_time(UNIX_EPOCH)), UNIX_EPOCH);
+    let early = UNIX_EPOCH - Duration::from_secs(5);
+    assert_eq!(from_unix_time(to_unix_time(early)), early);
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use crate::{
+    CardKey, GetKnowledgeError, Knowledge, KnowledgeStore, Levels, RecordCorrectError,
+    RecordHintedError, RecordIncorrectError, RecordRecognisedError, SetKnowledgeError,
+    SetLevelsError,
+};
+
+/// A knowledge store that lives entirely in memory and is lost when dropped.
+///
+/// Unlike [`Database::open_in_memory`](crate::Database::open_in_memory) this does not go through
+/// a database connection at all, so none of its operations can fail.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStore {
+    knowledge: HashMap<CardKey, Knowledge>,
+    levels: Levels,
+}
+
+impl MemoryStore {
+    /// Create an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update_knowledge(&mut self, card: &CardKey, f: impl FnOnce(Knowledge) -> Knowledge) {
+        let knowledge = f(self.knowledge.get(card).copied().unwrap_or_default());
+        self.set(card, knowledge);
+    }
+
+    fn set(&mut self, card: &CardKey, knowledge: Knowledge) {
+        if knowledge.level.get() == 0 {
+            self.knowledge.remove(card);
+        } else {
+            self.knowledge.insert(card.clone(), knowledge);
+        }
+    }
+}
+
+impl KnowledgeStore for MemoryStore {
+    fn knowledge(&self, card: &CardKey) -> Result<Knowledge, GetKnowledgeError> {
+        Ok(self.knowledge.get(card).copied().unwrap_or_default())
+    }
+
+    fn knowledge_all<'a, I>(
+        &self,
+        cards: I,
+    ) -> Result<
+        impl 'a + Clone + ExactSizeIterator<Item = (&'a CardKey, Knowledge)>,
+        GetKnowledgeError,
+    >
+    where
+        I: IntoIterator<Item = &'a CardKey>,
+        I::IntoIter: ExactSizeIterator + Clone + 'a,
+    {
+        let result = cards
+            .into_iter()
+            .map(|card| (card, self.knowledge.get(card).copied().unwrap_or_default()))
+            .collect::<Vec<_>>();
+        Ok(result.into_iter())
+    }
+
+    fn set_knowledge(
+        &mut self,
+        card: &CardKey,
+        knowledge: Knowledge,
+    ) -> Result<(), SetKnowledgeError> {
+        self.set(card, knowledge);
+        Ok(())
+    }
+
+    fn set_knowledge_all<'a, I>(
+        &mut self,
+        cards: I,
+        knowledge: Knowledge,
+    ) -> Result<(), SetKnowledgeError>
+    where
+        I: IntoIterator<Item = &'a CardKey>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        for card in cards {
+            self.set(card, knowledge);
+        }
+        Ok(())
+    }
+
+    fn record_correct(&mut self, card: &CardKey) -> Result<(), RecordCorrectError> {
+        let levels = self.levels;
+        self.update_knowledge(card, |knowledge| knowledge.after_correct(levels));
+        Ok(())
+    }
+
+    fn record_incorrect(&mut self, card: &CardKey) -> Result<(), RecordIncorrectError> {
+        let levels = self.levels;
+        self.update_knowledge(card, |knowledge| knowledge.after_incorrect(levels));
+        Ok(())
+    }
+
+    fn record_hinted(&mut self, card: &CardKey) -> Result<(), RecordHintedError> {
+        self.update_knowledge(card, Knowledge::after_hinted);
+        Ok(())
+    }
+
+    fn record_recognised(
+        &mut self,
+        card: &CardKey,
+        correct: bool,
+    ) -> Result<(), RecordRecognisedError> {
+        self.update_knowledge(card, |knowledge| knowledge.after_recognised(correct));
+        Ok(())
+    }
+
+    fn levels(&self) -> Levels {
+        self.levels
+    }
+
+    fn set_levels(&mut self, levels: Levels) -> Result<(), SetLevelsError> {
+        for knowledge in self.knowledge.values_mut() {
+            knowledge.level = knowledge.level.min(levels.max());
+        }
+        self.levels = levels;
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    CardKey, Database, FlushError, GetKnowledgeError, Knowledge, KnowledgeStore, Levels,
+    RecordCorrectError, RecordHintedError, RecordIncorrectError, RecordRecognisedError,
+    SetKnowledgeError, SetLevelsError,
+};
+
+/// A [`Database`] whose writes are batched into one transaction, created by
+/// [`Database::session`].
+///
+/// The writes are committed when the session is [flushed](KnowledgeStore::flush) and when it is
+/// dropped. Errors committing on drop are ignored, so flush it first to find out about them.
+#[derive(Debug)]
+pub struct Session<'a> {
+    database: &'a mut Database,
+}
+
+impl<'a> Session<'a> {
+    pub(crate) fn new(database: &'a mut Database) -> rusqlite::Result<Self> {
+        database.connection.execute_batch("BEGIN")?;
+        Ok(Self { database })
+    }
+}
+
+impl Drop for Session<'_> {
+    fn drop(&mut self) {
+        let _ = self.database.connection.execute_batch("COMMIT");
+    }
+}
+
+impl KnowledgeStore for Session<'_> {
+    fn knowledge(&self, card: &CardKey) -> Result<Knowledge, GetKnowledgeError> {
+        self.database.knowledge(card)
+    }
+
+    fn knowledge_all<'a, I>(
+        &self,
+        cards: I,
+    ) -> Result<
+        impl 'a + Clone + ExactSizeIterator<Item = (&'a CardKey, Knowledge)>,
+        GetKnowledgeError,
+    >
+    where
+        I: IntoIterator<Item = &'a CardKey>,
+        I::IntoIter: ExactSizeIterator + Clone + 'a,
+    {
+        self.database.knowledge_all(cards)
+    }
+
+    fn set_knowledge(
+        &mut self,
+        card: &CardKey,
+        knowledge: Knowledge,
+    ) -> Result<(), SetKnowledgeError> {
+        self.database.set_knowledge(card, knowledge)
+    }
+
+    fn set_knowledge_all<'a, I>(
+        &mut self,
+        cards: I,
+        knowledge: Knowledge,
+    ) -> Result<(), SetKnowledgeError>
+    where
+        I: IntoIterator<Item = &'a CardKey>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.database.set_knowledge_all(cards, knowledge)
+    }
+
+    fn record_correct(&mut self, card: &CardKey) -> Result<(), RecordCorrectError> {
+        self.database.record_correct(card)
+    }
+
+    fn record_incorrect(&mut self, card: &CardKey) -> Result<(), RecordIncorrectError> {
+        self.database.record_incorrect(card)
+    }
+
+    fn record_hinted(&mut self, card: &CardKey) -> Result<(), RecordHintedError> {
+        self.database.record_hinted(card)
+    }
+
+    fn record_recognised(
+        &mut self,
+        card: &CardKey,
+        correct: bool,
+    ) -> Result<(), RecordRecognisedError> {
+        self.database.record_recognised(card, correct)
+    }
+
+    fn levels(&self) -> Levels {
+        self.database.levels()
+    }
+
+    fn set_levels(&mut self, levels: Levels) -> Result<(), SetLevelsError> {
+        self.database.set_levels(levels)
+    }
+
+    fn flush(&mut self) -> Result<(), FlushError> {
+        self.database
+            .connection
+            .execute_batch("COMMIT; BEGIN")
+            .map_err(|inner| FlushError { inner })
+    }
+}
+
+#[test]
+fn test_session() {
+    use maplit::btreeset;
+
+    let mut db = Database::open_in_memory().unwrap();
+    crate::test_store(db.session().unwrap());
+    assert!(db.connection.is_autocommit());
+
+    let card = CardKey::new(&btreeset!("a"), &btreeset!("b"));
+    let mut session = db.session().unwrap();
+    session.record_correct(&card).unwrap();
+    session.flush().unwrap();
+    assert!(!session.database.connection.is_autocommit());
+    session.record_incorrect(&card).unwrap();
+    session
+        .database
+        .connection
+        .execute_batch("ROLLBACK")
+        .unwrap();
+    assert_eq!(session.knowledge(&card).unwrap().level.get(), 1);
+    session.database.connection.execute_batch("BEGIN").unwrap();
+    session.record_correct(&card).unwrap();
+    drop(session);
+    assert!(db.connection.is_autocommit());
+    assert_eq!(db.knowledge(&card).unwrap().level.get(), 2);
+    assert_eq!(db.history(&card).unwrap().len(), 2);
+}
@@ -0,0 +1,2027 @@
+//! Headless study API for `revise` frontends.
+//!
+//! The [`Engine`] ties together the set parser, the question scheduler and a knowledge store
+//! behind a small imperative API, so that graphical or mobile frontends do not need to
+//! reimplement any of the learning logic:
+//!
+//! ```
+//! use revise_engine::{Engine, MemoryStore};
+//!
+//! let set = revise_engine::parse_set("Colours\n\nrouge - red\nbleu - blue\n").unwrap();
+//!
+//! let mut engine = Engine::new(MemoryStore::new());
+//! engine.load_set(set, false);
+//!
+//! let question = engine.next_question().unwrap();
+//! println!("{}", question.prompt);
+//! engine.submit_answer("red").unwrap();
+//!
+//! let snapshot = engine.snapshot().unwrap();
+//! assert_eq!(snapshot.cards, 2);
+//! ```
+#![warn(clippy::all, clippy::pedantic)]
+#![warn(missing_docs)]
+#![allow(clippy::missing_panics_doc, clippy::missing_errors_doc)]
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+
+use rand::rngs::StdRng;
+use rand::seq::{IteratorRandom as _, SliceRandom as _};
+use rand::{Rng as _, SeedableRng as _};
+use thiserror::Error;
+
+use revise_core::Selector;
+
+pub use revise_core::{Knowledge, KnowledgeLevel, Levels};
+pub use revise_database::{
+    default_path as default_database_path, CardKey, Database, FlushError, GetKnowledgeError,
+    KnowledgeStore, MemoryStore, RecordCorrectError, RecordHintedError, RecordIncorrectError,
+    RecordRecognisedError, Session, SetKnowledgeError, SetLevelsError, StartSessionError,
+};
+pub use revise_parser::{
+    parse_guess, parse_guess_steps, parse_set, Card, LineCol, ParseError, Set,
+};
+
+/// A study session over some loaded sets, backed by a knowledge store.
+#[derive(Debug)]
+pub struct Engine<S> {
+    store: S,
+    title: String,
+    cards: Vec<(CardKey, Card)>,
+    /// The keys of the loaded cards, for skipping cards that are loaded again.
+    keys: HashSet<CardKey>,
+    /// The language of the terms of each loaded card, if it is known.
+    term_languages: Vec<Option<String>>,
+    /// Whether text in parentheses in each loaded card's definitions can be left out.
+    optional_parentheses: Vec<bool>,
+    /// Whether each loaded card was loaded from an inverted set.
+    inverted: Vec<bool>,
+    knowledge_weights: Option<Vec<f64>>,
+    at_risk_weight: f64,
+    mastery: Option<KnowledgeLevel>,
+    overlearn: f64,
+    accept_ambiguous: bool,
+    typo_tolerance: usize,
+    order_tolerance: f64,
+    redrill: bool,
+    /// The cards answered incorrectly this session that haven't been redrilled yet, in the order
+    /// they are to be asked.
+    redrill_queue: VecDeque<Redrill>,
+    both_directions: Option<KnowledgeLevel>,
+    /// Whether the last question asked was about a card from an inverted set.
+    last_inverted: Option<bool>,
+    selector: Selector<usize>,
+    current: Option<Current>,
+    rng: StdRng,
+}
+
+#[derive(Debug)]
+struct Current {
+    card: usize,
+    prompt: String,
+    /// Whether the card was asked from the redrill queue.
+    redrilling: bool,
+}
+
+/// A card waiting to be redrilled, from [`Engine::set_redrill`].
+#[derive(Debug, Clone, Copy)]
+struct Redrill {
+    card: usize,
+    /// The number of times in a row it has been answered correctly since it was last answered
+    /// incorrectly.
+    correct: u8,
+}
+
+/// The number of times in a row a card must be answered correctly to leave the redrill queue.
+const REDRILL_CORRECT: u8 = 2;
+
+impl<S: KnowledgeStore> Engine<S> {
+    /// Create an engine with no sets loaded that records knowledge in the given store.
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            title: String::new(),
+            cards: Vec::new(),
+            keys: HashSet::new(),
+            term_languages: Vec::new(),
+            optional_parentheses: Vec::new(),
+            inverted: Vec::new(),
+            knowledge_weights: None,
+            at_risk_weight: DEFAULT_AT_RISK_WEIGHT,
+            mastery: None,
+            overlearn: 0.0,
+            accept_ambiguous: false,
+            typo_tolerance: 0,
+            order_tolerance: 0.0,
+            redrill: false,
+            redrill_queue: VecDeque::new(),
+            both_directions: None,
+            last_inverted: None,
+            selector: Selector::new(),
+            current: None,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Add the cards of a set to the session, optionally swapping its terms and definitions.
+    /// Swapping them makes the steps or table of a card into its terms, so it no longer has
+    /// either.
+    ///
+    /// Cards that are already loaded are not added again.
+    ///
+    /// The set's [language](revise_parser::SetMetadata::language) is taken to be the language of
+    /// its terms, so it is the language of prompts unless the set is inverted. Likewise, its
+    /// [optional parentheses](revise_parser::SetMetadata::optional_parentheses) only apply when
+    /// it is not inverted.
+    pub fn load_set(&mut self, set: Set, invert: bool) {
+        let term_language = (!invert).then_some(set.metadata.language).flatten();
+        let optional_parentheses = !invert && set.metadata.optional_parentheses;
+        if self.title.is_empty() {
+            self.title = set.title;
+        } else {
+            self.title.push_str(" + ");
+            self.title.push_str(&set.title);
+        }
+
+        for mut card in set.cards {
+            if invert {
+                mem::swap(&mut card.terms, &mut card.definitions);
+                card.steps = None;
+                card.table = None;
+            }
+            let key = CardKey::new(&card.terms, &card.definitions);
+            if self.keys.insert(key.clone()) {
+                self.cards.push((key, card));
+                self.term_languages.push(term_language.clone());
+                self.optional_parentheses.push(optional_parentheses);
+                self.inverted.push(invert);
+            }
+        }
+    }
+
+    /// Set the weights given to each knowledge level when choosing questions.
+    ///
+    /// There must be one weight for each of the store's [`Levels`]. Defaults to
+    /// [`default_knowledge_weights`].
+    pub fn set_knowledge_weights(&mut self, knowledge_weights: Vec<f64>) {
+        self.knowledge_weights = Some(knowledge_weights);
+    }
+
+    /// Set how many times more often cards that are [at risk](Knowledge::is_at_risk) of moving
+    /// down a level are asked than other cards at the same level.
+    ///
+    /// Defaults to [`DEFAULT_AT_RISK_WEIGHT`].
+    pub fn set_at_risk_weight(&mut self, at_risk_weight: f64) {
+        self.at_risk_weight = at_risk_weight;
+    }
+
+    /// Set the knowledge level at which a card is mastered and stops being asked.
+    ///
+    /// When this is `None`, which is the default, cards are asked at every level and are
+    /// considered learnt once they reach the highest level.
+    pub fn set_mastery(&mut self, mastery: Option<KnowledgeLevel>) {
+        self.mastery = mastery;
+    }
+
+    /// The knowledge level at which a card is mastered, as set by [`Engine::set_mastery`].
+    #[must_use]
+    pub fn mastery(&self) -> Option<KnowledgeLevel> {
+        self.mastery
+    }
+
+    /// Set the probability, from 0 to 1, of asking about a mastered card anyway.
+    ///
+    /// This only has an effect once [a mastery level](Engine::set_mastery) is set. Defaults to 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability is not between 0 and 1.
+    pub fn set_overlearn(&mut self, overlearn: f64) {
+        assert!(
+            (0.0..=1.0).contains(&overlearn),
+            "overlearn probability {overlearn} is not between 0 and 1",
+        );
+        self.overlearn = overlearn;
+    }
+
+    /// Set whether to accept the answer of any card whose terms contain the prompt, rather than
+    /// only the answer of the card being asked about.
+    ///
+    /// Defaults to `false`. See [`Engine::ambiguous_prompts`] for the prompts this affects.
+    pub fn set_accept_ambiguous(&mut self, accept_ambiguous: bool) {
+        self.accept_ambiguous = accept_ambiguous;
+    }
+
+    /// Set how many typos, counted as single-character insertions, deletions or substitutions,
+    /// are allowed in each part of an answer.
+    ///
+    /// Defaults to 0, which only accepts exact answers.
+    pub fn set_typo_tolerance(&mut self, typo_tolerance: usize) {
+        self.typo_tolerance = typo_tolerance;
+    }
+
+    /// Set whether to redrill the cards answered incorrectly during the session, like rounds in
+    /// Quizlet.
+    ///
+    /// Each card answered incorrectly joins a queue, which is gone through in turn once every
+    /// card is mastered. A card leaves the queue once it has been answered correctly twice in a
+    /// row from there, and the session isn't [complete](Snapshot::is_complete) until the queue is empty.
+    /// This only has an effect once [a mastery level](Engine::set_mastery) is set. Defaults to
+    /// `false`.
+    pub fn set_redrill(&mut self, redrill: bool) {
+        self.redrill = redrill;
+        if !redrill {
+            self.redrill_queue.clear();
+        }
+    }
+
+    /// Require cards to be answered correctly both ways round before they reach the given level.
+    ///
+    /// A card answered correctly stays below the level until the card with its terms and
+    /// definitions swapped has been answered correctly too, which is when that card is above the
+    /// lowest level. Load each set both as it is and inverted to learn both ways round; questions
+    /// then alternate between the two while there are cards to ask both ways. Defaults to `None`,
+    /// which treats the two ways round as separate cards.
+    pub fn set_both_directions(&mut self, level: Option<KnowledgeLevel>) {
+        self.both_directions = level;
+    }
+
+    /// Set how far out of order the steps of a card can be given and still be correct, as the
+    /// fraction of pairs of steps given in the wrong order relative to each other (the normalized
+    /// Kendall tau distance).
+    ///
+    /// Defaults to 0, which only accepts steps in exactly the right order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tolerance is not between 0 and 1.
+    pub fn set_order_tolerance(&mut self, order_tolerance: f64) {
+        assert!(
+            (0.0..=1.0).contains(&order_tolerance),
+            "order tolerance {order_tolerance} is not between 0 and 1",
+        );
+        self.order_tolerance = order_tolerance;
+    }
+
+    /// Find the prompts that are shared by more than one loaded card, together with those cards.
+    ///
+    /// Answering one of these prompts is ambiguous: the user cannot know which card is being asked
+    /// about. This commonly happens when several cards share a definition and the set is inverted.
+    #[must_use]
+    pub fn ambiguous_prompts(&self) -> Vec<(&str, Vec<&Card>)> {
+        let mut prompts = BTreeMap::<&str, Vec<&Card>>::new();
+        for (_, card) in &self.cards {
+            for term in &card.terms {
+                prompts.entry(term).or_default().push(card);
+            }
+        }
+        prompts
+            .into_iter()
+            .filter(|(_, cards)| cards.len() > 1)
+            .collect()
+    }
+
+    /// Change the number of knowledge levels in the store.
+    pub fn set_levels(&mut self, levels: Levels) -> Result<(), Error> {
+        self.store.set_levels(levels)?;
+        Ok(())
+    }
+
+    /// The combined title of all the loaded sets.
+    #[must_use]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The loaded cards.
+    pub fn cards(&self) -> impl ExactSizeIterator<Item = &Card> {
+        self.cards.iter().map(|(_, card)| card)
+    }
+
+    /// The keys of the loaded cards.
+    pub fn card_keys(&self) -> impl ExactSizeIterator<Item = &CardKey> + Clone {
+        self.cards.iter().map(|(key, _)| key)
+    }
+
+    /// The knowledge store backing this engine.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Mutable access to the knowledge store backing this engine.
+    pub fn store_mut(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    /// Forget all recorded knowledge of the loaded cards, and which cards are waiting to be
+    /// redrilled.
+    pub fn clear_knowledge(&mut self) -> Result<(), Error> {
+        self.redrill_queue.clear();
+        let keys = self.cards.iter().map(|(key, _)| key);
+        self.store.set_knowledge_all(keys, Knowledge::default())?;
+        Ok(())
+    }
+
+    /// If every loaded card is mastered, move them all down to the level below the mastery level
+    /// so that the session does not end straight away.
+    ///
+    /// Returns the level the cards were moved to, or `None` if they were left as they are.
+    pub fn make_incomplete(&mut self) -> Result<Option<KnowledgeLevel>, Error> {
+        let levels = self.store.levels();
+        let mastery = self
+            .mastery
+            .unwrap_or_else(|| levels.max())
+            .min(levels.max());
+        let Some(level) = mastery.get().checked_sub(1).and_then(KnowledgeLevel::new) else {
+            return Ok(None);
+        };
+        if self.cards.is_empty() || !self.snapshot()?.is_complete() {
+            return Ok(None);
+        }
+
+        let keys = self.cards.iter().map(|(key, _)| key);
+        let knowledge = Knowledge {
+            level,
+            safety_net: false,
+        };
+        self.store.set_knowledge_all(keys, knowledge)?;
+        Ok(Some(level))
+    }
+
+    /// Choose the next question to ask.
+    ///
+    /// Any previous question that was not answered is discarded.
+    pub fn next_question(&mut self) -> Result<Question<'_>, Error> {
+        if self.cards.is_empty() {
+            return Err(Error::NoCards);
+        }
+
+        let levels = self.store.levels();
+        let knowledge = self
+            .store
+            .knowledge_all(self.cards.iter().map(|(key, _)| key))?
+            .map(|(_, knowledge)| knowledge)
+            .collect::<Vec<_>>();
+
+        // Whether to ask about mastered cards rather than unmastered ones, or `None` to ask about
+        // any card.
+        let ask_mastered = match self.mastered(&knowledge)? {
+            Some(0) => Some(false),
+            Some(mastered) if mastered == self.cards.len() => Some(true),
+            Some(_) => Some(self.rng.gen_bool(self.overlearn)),
+            None => None,
+        };
+
+        let at_risk = knowledge
+            .iter()
+            .filter(|knowledge| knowledge.is_at_risk(levels))
+            .count();
+
+        // Once there are no unmastered cards left to ask, go through the redrill queue in turn.
+        if ask_mastered == Some(true) {
+            if let Some(redrill) = self.redrill_queue.pop_front() {
+                self.redrill_queue.push_back(redrill);
+                let mut level_distribution = vec![0; levels.count()];
+                for knowledge in &knowledge {
+                    level_distribution[usize::from(knowledge.level.get())] += 1;
+                }
+                return Ok(self.question(redrill.card, level_distribution, at_risk, true));
+            }
+        }
+
+        let mut priorities = self.priorities(&knowledge, ask_mastered);
+        if let (Some(_), Some(last_inverted)) = (self.both_directions, self.last_inverted) {
+            // Ask the other way round to the last question if anything can be.
+            let other_way = |(index, &priority): (usize, &f64)| {
+                self.inverted[index] != last_inverted && priority > 0.0
+            };
+            if priorities.iter().enumerate().any(other_way) {
+                for (priority, &inverted) in priorities.iter_mut().zip(&self.inverted) {
+                    if inverted == last_inverted {
+                        *priority = 0.0;
+                    }
+                }
+            }
+        }
+        let knowledge_weights = self.knowledge_weights()?;
+        let cards = knowledge
+            .iter()
+            .zip(priorities)
+            .enumerate()
+            .map(|(index, (&knowledge, priority))| (index, knowledge, priority));
+        let selection =
+            self.selector
+                .select_with_priority(cards, &knowledge_weights, &mut self.rng);
+
+        Ok(self.question(selection.card, selection.level_distribution, at_risk, false))
+    }
+
+    /// Ask about the loaded card at the given index in [`Engine::cards`], instead of choosing
+    /// which card to ask about from how well they are known.
+    ///
+    /// Any previous question that was not answered is discarded.
+    pub fn ask(&mut self, card: usize) -> Result<Question<'_>, Error> {
+        let levels = self.store.levels();
+        let snapshot = self.snapshot()?;
+        let at_risk = self
+            .store
+            .knowledge_all(self.cards.iter().map(|(key, _)| key))?
+            .filter(|(_, knowledge)| knowledge.is_at_risk(levels))
+            .count();
+        Ok(self.question(card, snapshot.level_distribution, at_risk, false))
+    }
+
+    /// Make the loaded card at the given index the current question.
+    fn question(
+        &mut self,
+        index: usize,
+        level_distribution: Vec<usize>,
+        at_risk: usize,
+        redrilling: bool,
+    ) -> Question<'_> {
+        let card = &self.cards[index].1;
+        let prompt_language = self.term_languages[index].as_deref();
+        let prompt = card.terms.iter().choose(&mut self.rng).unwrap().clone();
+        let (alternatives, disambiguation) = if self.accept_ambiguous {
+            (alternatives(&self.cards, index, &prompt), None)
+        } else {
+            let others = alternatives(&self.cards, index, &prompt);
+            (Vec::new(), disambiguation(card, &prompt, &others))
+        };
+        let steps = card.steps.as_ref().map(|steps| {
+            let mut steps = steps.iter().map(String::as_str).collect::<Vec<_>>();
+            steps.shuffle(&mut self.rng);
+            steps
+        });
+        let tolerance = self.tolerance(index);
+        self.last_inverted = Some(self.inverted[index]);
+        let current = self.current.insert(Current {
+            card: index,
+            prompt,
+            redrilling,
+        });
+
+        Question {
+            card,
+            prompt: &current.prompt,
+            prompt_language,
+            steps,
+            alternatives,
+            disambiguation,
+            level_distribution,
+            at_risk,
+            tolerance,
+        }
+    }
+
+    /// Choose the next question to ask as a true or false statement: its prompt is shown with
+    /// either the card's own answer or, half the time, the answer of another card.
+    ///
+    /// Any previous question that was not answered is discarded.
+    pub fn next_statement(&mut self) -> Result<Statement<'_>, Error> {
+        let Question {
+            level_distribution,
+            at_risk,
+            ..
+        } = self.next_question()?;
+        let current = self.current.as_ref().unwrap();
+        let card = &self.cards[current.card].1;
+
+        let others = self
+            .cards
+            .iter()
+            .map(|(_, other)| other)
+            .filter(|other| !same_answer(card, other) && !other.terms.contains(&current.prompt))
+            .collect::<Vec<_>>();
+        let shown = if self.rng.gen_bool(0.5) {
+            others.choose(&mut self.rng).copied().unwrap_or(card)
+        } else {
+            card
+        };
+
+        Ok(Statement {
+            card,
+            prompt: &current.prompt,
+            prompt_language: self.term_languages[current.card].as_deref(),
+            shown,
+            level_distribution,
+            at_risk,
+        })
+    }
+
+    /// The number of cards with the given knowledge that are at or above the mastery level, or
+    /// `None` if there is no mastery level.
+    fn mastered(&self, knowledge: &[Knowledge]) -> Result<Option<usize>, Error> {
+        let levels = self.store.levels();
+        match self.mastery {
+            Some(mastery) if mastery > levels.max() => Err(Error::Mastery {
+                mastery,
+                max: levels.max(),
+            }),
+            Some(mastery) => Ok(Some(
+                knowledge
+                    .iter()
+                    .filter(|knowledge| knowledge.level >= mastery)
+                    .count(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// The priority of each loaded card with the given knowledge, before it is multiplied by the
+    /// weight of the card's level. If `ask_mastered` is given, only mastered or only unmastered
+    /// cards have a priority.
+    fn priorities(&self, knowledge: &[Knowledge], ask_mastered: Option<bool>) -> Vec<f64> {
+        let levels = self.store.levels();
+        knowledge
+            .iter()
+            .zip(&self.cards)
+            .map(|(knowledge, (_, card))| {
+                let mut priority = f64::from(card.priority);
+                if knowledge.is_at_risk(levels) {
+                    priority *= self.at_risk_weight;
+                }
+                if let (Some(mastery), Some(ask_mastered)) = (self.mastery, ask_mastered) {
+                    if (knowledge.level >= mastery) != ask_mastered {
+                        priority = 0.0;
+                    }
+                }
+                priority
+            })
+            .collect()
+    }
+
+    /// The weight of each knowledge level.
+    fn knowledge_weights(&self) -> Result<Vec<f64>, Error> {
+        let levels = self.store.levels();
+        match &self.knowledge_weights {
+            Some(weights) if weights.len() != levels.count() => Err(Error::KnowledgeWeights {
+                levels: levels.count(),
+                weights: weights.len(),
+            }),
+            Some(weights) => Ok(weights.clone()),
+            None => Ok(default_knowledge_weights(levels)),
+        }
+    }
+
+    /// Work out how questions would be chosen from the loaded cards, without asking any.
+    ///
+    /// Each card's probability is its chance of being asked next, not counting that the previous
+    /// question is never asked twice in a row. The expected number of questions until every card
+    /// is mastered is estimated by simulating sessions in which every answer is correct.
+    pub fn plan(&self) -> Result<Plan<'_>, Error> {
+        if self.cards.is_empty() {
+            return Err(Error::NoCards);
+        }
+
+        let levels = self.store.levels();
+        let knowledge = self
+            .store
+            .knowledge_all(self.cards.iter().map(|(key, _)| key))?
+            .map(|(_, knowledge)| knowledge)
+            .collect::<Vec<_>>();
+        let knowledge_weights = self.knowledge_weights()?;
+        let probabilities = |ask_mastered| {
+            let weights = knowledge
+                .iter()
+                .zip(self.priorities(&knowledge, ask_mastered))
+                .map(|(knowledge, priority)| {
+                    knowledge_weights[usize::from(knowledge.level.get())] * priority
+                })
+                .collect::<Vec<_>>();
+            let total = weights.iter().sum::<f64>();
+            weights
+                .into_iter()
+                .map(|weight| if total > 0.0 { weight / total } else { 0.0 })
+                .collect::<Vec<_>>()
+        };
+        let probabilities = match self.mastered(&knowledge)? {
+            Some(0) => probabilities(Some(false)),
+            Some(mastered) if mastered == self.cards.len() => probabilities(Some(true)),
+            Some(_) => {
+                let unmastered = probabilities(Some(false));
+                let mastered = probabilities(Some(true));
+                unmastered
+                    .into_iter()
+                    .zip(mastered)
+                    .map(|(unmastered, mastered)| {
+                        (1.0 - self.overlearn) * unmastered + self.overlearn * mastered
+                    })
+                    .collect()
+            }
+            None => probabilities(None),
+        };
+
+        let cards = self
+            .cards
+            .iter()
+            .zip(knowledge)
+            .zip(probabilities)
+            .map(|(((_, card), knowledge), probability)| PlannedCard {
+                card,
+                knowledge,
+                at_risk: knowledge.is_at_risk(levels),
+                probability,
+            })
+            .collect();
+
+        Ok(Plan {
+            cards,
+            expected_questions: self.simulate_questions()?,
+        })
+    }
+
+    /// The average number of questions it takes for every card to become mastered if every
+    /// answer is correct, or `None` if that doesn't happen in a reasonable number of questions.
+    fn simulate_questions(&self) -> Result<Option<f64>, Error> {
+        const SIMULATIONS: u64 = 100;
+        const MAX_QUESTIONS: u64 = 100_000;
+
+        let levels = self.store.levels();
+        let mastery = self.mastery.unwrap_or_else(|| levels.max());
+        let knowledge_weights = self.knowledge_weights()?;
+        let mut total = 0;
+        for seed in 0..SIMULATIONS {
+            let mut store = MemoryStore::new();
+            store.set_levels(levels)?;
+            let knowledge = self
+                .store
+                .knowledge_all(self.cards.iter().map(|(key, _)| key))?;
+            for (key, knowledge) in knowledge {
+                store.set_knowledge(key, knowledge)?;
+            }
+            let mut engine = Engine {
+                store,
+                title: String::new(),
+                cards: self.cards.clone(),
+                keys: self.keys.clone(),
+                term_languages: self.term_languages.clone(),
+                optional_parentheses: self.optional_parentheses.clone(),
+                inverted: self.inverted.clone(),
+                knowledge_weights: self.knowledge_weights.clone(),
+                at_risk_weight: self.at_risk_weight,
+                mastery: self.mastery,
+                overlearn: self.overlearn,
+                accept_ambiguous: false,
+                typo_tolerance: 0,
+                order_tolerance: 0.0,
+                redrill: false,
+                redrill_queue: VecDeque::new(),
+                both_directions: self.both_directions,
+                last_inverted: None,
+                selector: Selector::new(),
+                current: None,
+                rng: StdRng::seed_from_u64(seed),
+            };
+
+            let mut questions = 0;
+            loop {
+                let knowledge = engine
+                    .store
+                    .knowledge_all(engine.cards.iter().map(|(key, _)| key))?
+                    .map(|(_, knowledge)| knowledge)
+                    .collect::<Vec<_>>();
+                let mut unmastered = knowledge
+                    .iter()
+                    .zip(engine.priorities(&knowledge, None))
+                    .filter(|(knowledge, _)| knowledge.level < mastery)
+                    .peekable();
+                if unmastered.peek().is_none() {
+                    break;
+                }
+                // A card with no weight is never asked, so it can't become mastered.
+                let stuck = unmastered.any(|(knowledge, priority)| {
+                    knowledge_weights[usize::from(knowledge.level.get())] * priority <= 0.0
+                });
+                if stuck || questions == MAX_QUESTIONS {
+                    return Ok(None);
+                }
+
+                engine.next_question()?;
+                engine.record_result(true)?;
+                questions += 1;
+            }
+            total += questions;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        Ok(Some(total as f64 / SIMULATIONS as f64))
+    }
+
+    /// The question that is currently waiting for an answer, if there is one.
+    #[must_use]
+    pub fn current_question(&self) -> Option<&Card> {
+        Some(&self.cards[self.current.as_ref()?.card].1)
+    }
+
+    /// Check whether an answer to the current question is correct, without recording anything.
+    pub fn check_answer(&self, answer: &str) -> Result<bool, Error> {
+        let current = self.current.as_ref().ok_or(Error::NoQuestion)?;
+        let card = &self.cards[current.card].1;
+        let tolerance = self.tolerance(current.card);
+        Ok(is_correct(card, answer, tolerance)
+            || self.accept_ambiguous
+                && alternatives(&self.cards, current.card, &current.prompt)
+                    .into_iter()
+                    .any(|card| is_correct(card, answer, tolerance)))
+    }
+
+    fn tolerance(&self, index: usize) -> Tolerance {
+        Tolerance {
+            typos: self.typo_tolerance,
+            order: self.order_tolerance,
+            parentheses: self.optional_parentheses[index],
+        }
+    }
+
+    /// Answer the current question, recording whether the answer was correct.
+    ///
+    /// Returns whether the answer was correct.
+    pub fn submit_answer(&mut self, answer: &str) -> Result<bool, Error> {
+        let correct = self.check_answer(answer)?;
+        self.record_result(correct)?;
+        Ok(correct)
+    }
+
+    /// Record the result of the current question without checking an answer.
+    ///
+    /// This is useful for frontends that let users override the result of their answer.
+    pub fn record_result(&mut self, correct: bool) -> Result<(), Error> {
+        let current = self.current.take().ok_or(Error::NoQuestion)?;
+        let key = &self.cards[current.card].0;
+        if correct {
+            self.store.record_correct(key)?;
+            self.hold_back(current.card)?;
+        } else {
+            self.store.record_incorrect(key)?;
+        }
+        if self.redrill {
+            self.update_redrill(&current, correct);
+        }
+        Ok(())
+    }
+
+    /// Move a card that has just been answered correctly back below the
+    /// [both directions level](Engine::set_both_directions) if it has reached it before the card
+    /// the other way round has been answered correctly.
+    fn hold_back(&mut self, card: usize) -> Result<(), Error> {
+        let Some(level) = self.both_directions else {
+            return Ok(());
+        };
+        let (key, card) = &self.cards[card];
+        let mut knowledge = self.store.knowledge(key)?;
+        let Some(below) = level.get().checked_sub(1).and_then(KnowledgeLevel::new) else {
+            return Ok(());
+        };
+        if knowledge.level < level {
+            return Ok(());
+        }
+        let reverse = CardKey::new(&card.definitions, &card.terms);
+        if self.store.knowledge(&reverse)?.level.get() == 0 {
+            knowledge.level = below;
+            self.store.set_knowledge(key, knowledge)?;
+        }
+        Ok(())
+    }
+
+    /// Add a card answered incorrectly to the redrill queue, or count a correct answer while
+    /// redrilling towards taking it out.
+    fn update_redrill(&mut self, current: &Current, correct: bool) {
+        let card = current.card;
+        let position = self
+            .redrill_queue
+            .iter()
+            .position(|redrill| redrill.card == card);
+        match (position, correct) {
+            (Some(position), true) if current.redrilling => {
+                let redrill = &mut self.redrill_queue[position];
+                redrill.correct += 1;
+                if redrill.correct == REDRILL_CORRECT {
+                    self.redrill_queue.remove(position);
+                }
+            }
+            (Some(position), false) => self.redrill_queue[position].correct = 0,
+            (_, true) => {}
+            (None, false) => self.redrill_queue.push_back(Redrill { card, correct: 0 }),
+        }
+    }
+
+    /// Record that the current question was answered correctly, but only with the help of hints.
+    ///
+    /// The card keeps its level and safety net, but the answer counts as correct while the card is
+    /// being [redrilled](Engine::set_redrill).
+    pub fn record_hinted(&mut self) -> Result<(), Error> {
+        let current = self.current.take().ok_or(Error::NoQuestion)?;
+        self.store.record_hinted(&self.cards[current.card].0)?;
+        if self.redrill {
+            self.update_redrill(&current, true);
+        }
+        Ok(())
+    }
+
+    /// Record how well the user says they knew the answer to the current question, for when they
+    /// grade themselves instead of typing an answer.
+    pub fn record_grade(&mut self, grade: Grade) -> Result<(), Error> {
+        match grade {
+            Grade::Again => self.record_result(false),
+            Grade::Hard => self.record_hinted(),
+            Grade::Good => self.record_result(true),
+            Grade::Easy => {
+                let card = self.current.as_ref().ok_or(Error::NoQuestion)?.card;
+                self.record_result(true)?;
+                let key = &self.cards[card].0;
+                let knowledge = self.store.knowledge(key)?;
+                let knowledge = knowledge.after_correct(self.store.levels());
+                self.store.set_knowledge(key, knowledge)?;
+                self.hold_back(card)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Record whether the user told correctly if the current [statement](Engine::next_statement)
+    /// was true. This counts for less than answering a question: see
+    /// [`Knowledge::after_recognised`].
+    pub fn record_recognised(&mut self, correct: bool) -> Result<(), Error> {
+        let current = self.current.take().ok_or(Error::NoQuestion)?;
+        self.store
+            .record_recognised(&self.cards[current.card].0, correct)?;
+        if correct {
+            self.hold_back(current.card)?;
+        }
+        if self.redrill {
+            self.update_redrill(&current, correct);
+        }
+        Ok(())
+    }
+
+    /// Take a snapshot of how well the loaded cards are known.
+    pub fn snapshot(&self) -> Result<Snapshot, Error> {
+        let levels = self.store.levels();
+        let mastery = self.mastery.unwrap_or_else(|| levels.max());
+        let mut level_distribution = vec![0; levels.count()];
+        let mut mastered = 0;
+        for (_, knowledge) in self
+            .store
+            .knowledge_all(self.cards.iter().map(|(key, _)| key))?
+        {
+            level_distribution[usize::from(knowledge.level.get())] += 1;
+            if knowledge.level >= mastery {
+                mastered += 1;
+            }
+        }
+        Ok(Snapshot {
+            cards: self.cards.len(),
+            level_distribution,
+            mastered,
+            redrill: self.redrill_queue.len(),
+        })
+    }
+}
+
+/// The knowledge weights used by default with the default number of levels: unknown cards are
+/// asked much more often than known ones.
+pub const DEFAULT_KNOWLEDGE_WEIGHTS: [f64; 4] = [10.0, 5.0, 1.0, 0.1];
+
+/// The at-risk weight used by default: cards that would move down a level if answered incorrectly
+/// are asked twice as often.
+pub const DEFAULT_AT_RISK_WEIGHT: f64 = 2.0;
+
+/// The knowledge weights used by default with the given number of levels.
+///
+/// This is [`DEFAULT_KNOWLEDGE_WEIGHTS`] for the default number of levels; otherwise the weights
+/// fall geometrically between the same first and last weights.
+#[must_use]
+pub fn default_knowledge_weights(levels: Levels) -> Vec<f64> {
+    if levels == Levels::DEFAULT {
+        return DEFAULT_KNOWLEDGE_WEIGHTS.to_vec();
+    }
+    let first = DEFAULT_KNOWLEDGE_WEIGHTS[0];
+    let last = DEFAULT_KNOWLEDGE_WEIGHTS[DEFAULT_KNOWLEDGE_WEIGHTS.len() - 1];
+    let steps = levels.max().get();
+    (0..=steps)
+        .map(|i| first * (last / first).powf(f64::from(i) / f64::from(steps)))
+        .collect()
+}
+
+/// How well the user knew the answer to a question, given to [`Engine::record_grade`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grade {
+    /// The answer wasn't known, which is recorded as incorrect.
+    Again,
+    /// The answer was only just known, which is recorded like an answer given with hints: the
+    /// card keeps its level and safety net.
+    Hard,
+    /// The answer was known, which is recorded as correct.
+    Good,
+    /// The answer was known easily, which moves the card up two levels.
+    Easy,
+}
+
+/// A question chosen by [`Engine::next_question`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Question<'a> {
+    /// The card being asked about.
+    pub card: &'a Card,
+    /// The term to show to the user, one of the card's terms.
+    pub prompt: &'a str,
+    /// The language of the prompt, from the metadata of the card's set, for reading it aloud.
+    pub prompt_language: Option<&'a str>,
+    /// If the card has [steps](Card::steps), the steps shuffled into a random order, for the user
+    /// to put back in order.
+    pub steps: Option<Vec<&'a str>>,
+    /// Other cards that also have the prompt as a term, whose answers are accepted too. This is
+    /// empty unless [ambiguous answers are accepted](Engine::set_accept_ambiguous).
+    pub alternatives: Vec<&'a Card>,
+    /// Something to show with the prompt that tells the card apart from other cards that also
+    /// have the prompt as a term, since only this card's answer is accepted. This is `None` if no
+    /// other card has the prompt or [ambiguous answers are accepted](Engine::set_accept_ambiguous).
+    pub disambiguation: Option<Disambiguation<'a>>,
+    /// The number of loaded cards at each knowledge level.
+    pub level_distribution: Vec<usize>,
+    /// The number of loaded cards that are [at risk](Knowledge::is_at_risk) of moving down a
+    /// level.
+    pub at_risk: usize,
+    tolerance: Tolerance,
+}
+
+impl<'a> Question<'a> {
+    /// Check whether an answer typed by the user is correct, without recording anything. The
+    /// steps of a card with steps are given in order, separated by commas.
+    #[must_use]
+    pub fn is_correct(&self, answer: &str) -> bool {
+        is_correct(self.card, answer, self.tolerance)
+            || self
+                .alternatives
+                .iter()
+                .any(|card| is_correct(card, answer, self.tolerance))
+    }
+
+    /// If an answer is incomplete, with every option in it one of the card's definitions but
+    /// some definitions left out, the definitions that are missing.
+    ///
+    /// Returns `None` if the answer is empty, complete or has an option that is wrong, or if the
+    /// card has steps.
+    #[must_use]
+    pub fn missing(&self, answer: &str) -> Option<Vec<&'a str>> {
+        if self.card.steps.is_some() {
+            return None;
+        }
+        let guess = parse_guess(answer);
+        if guess.is_empty() || guess.len() >= self.card.definitions.len() {
+            return None;
+        }
+        let guess = guess.iter().map(String::as_str).collect::<Vec<_>>();
+        let definitions = self.card.definitions.iter().map(String::as_str).collect();
+        leftover(self.card, &guess, definitions, self.tolerance)
+    }
+}
+
+/// A term shown with an answer, for the user to tell whether the answer is right, from
+/// [`Engine::next_statement`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Statement<'a> {
+    /// The card being asked about.
+    pub card: &'a Card,
+    /// The term to show to the user, one of the card's terms.
+    pub prompt: &'a str,
+    /// The language of the prompt, from the metadata of the card's set, for reading it aloud.
+    pub prompt_language: Option<&'a str>,
+    /// The card whose answer is shown with the prompt: either the card being asked about, or
+    /// another card with a different answer.
+    pub shown: &'a Card,
+    /// The number of loaded cards at each knowledge level.
+    pub level_distribution: Vec<usize>,
+    /// The number of loaded cards that are [at risk](Knowledge::is_at_risk) of moving down a
+    /// level.
+    pub at_risk: usize,
+}
+
+impl Statement<'_> {
+    /// Whether the answer shown is the answer to the prompt.
+    #[must_use]
+    pub fn is_true(&self) -> bool {
+        same_answer(self.card, self.shown)
+    }
+}
+
+/// Whether two cards have the same answer, including the order of any steps.
+fn same_answer(a: &Card, b: &Card) -> bool {
+    a.definitions == b.definitions && a.steps == b.steps
+}
+
+/// How far answers can be from the right answer and still be correct.
+#[derive(Debug, Clone, Copy)]
+struct Tolerance {
+    typos: usize,
+    order: f64,
+    /// Whether text in parentheses in the right answer can be left out.
+    parentheses: bool,
+}
+
+fn is_correct(card: &Card, answer: &str, tolerance: Tolerance) -> bool {
+    let Some(steps) = &card.steps else {
+        let guess = parse_guess(answer);
+        if guess.len() != card.definitions.len() {
+            return false;
+        }
+        if tolerance.typos == 0 && !tolerance.parentheses && card.synonyms.is_empty() {
+            return card.definitions == guess;
+        }
+        let guess = guess.iter().map(String::as_str).collect::<Vec<_>>();
+        let definitions = card
+            .definitions
+            .iter()
+            .map(|d| accepted(card, d))
+            .collect::<Vec<_>>();
+        return pair_up(&guess, &definitions, tolerance);
+    };
+
+    let guess = parse_guess_steps(answer);
+    if guess.len() != steps.len() {
+        return false;
+    }
+    let guess = guess.iter().map(String::as_str).collect::<Vec<_>>();
+    let steps_accepted = steps
+        .iter()
+        .map(|step| accepted(card, step))
+        .collect::<Vec<_>>();
+    if !pair_up(&guess, &steps_accepted, tolerance) {
+        return false;
+    }
+    // Each guessed step is the step it is closest to, so that typos can't change the order.
+    let order = guess
+        .iter()
+        .map(|guess| {
+            let distances = steps.iter().map(|step| {
+                let spellings = card.spellings(step).into_iter();
+                spellings
+                    .map(|spelling| edit_distance(guess, spelling))
+                    .min()
+                    .unwrap()
+            });
+            distances.enumerate().min_by_key(|&(_, d)| d).unwrap().0
+        })
+        .collect::<Vec<_>>();
+    kendall_tau_distance(&order) <= tolerance.order
+}
+
+/// The fraction of pairs of items in the wrong order relative to each other, where `order` gives
+/// the right position of each item. 0 means in order and 1 means reversed.
+fn kendall_tau_distance(order: &[usize]) -> f64 {
+    let pairs = order.len() * order.len().saturating_sub(1) / 2;
+    if pairs == 0 {
+        return 0.0;
+    }
+    let discordant = (0..order.len())
+        .flat_map(|i| (i + 1..order.len()).map(move |j| (i, j)))
+        .filter(|&(i, j)| order[i] > order[j])
+        .count();
+    #[allow(clippy::cast_precision_loss)]
+    let distance = discordant as f64 / pairs as f64;
+    distance
+}
+
+/// The ways an option of a card can be given: any of its [spellings](Card::spellings), or the
+/// option in full.
+fn accepted<'a>(card: &Card, option: &'a str) -> Vec<&'a str> {
+    let mut accepted = card.spellings(option);
+    if accepted.len() > 1 {
+        accepted.push(option);
+    }
+    accepted
+}
+
+/// Whether each guess can be paired with a different definition, given as the ways it can be
+/// [accepted](accepted), that it [matches](matches). There must be as many guesses as definitions,
+/// so that none are left over.
+fn pair_up(guesses: &[&str], definitions: &[Vec<&str>], tolerance: Tolerance) -> bool {
+    pair_guesses(guesses, definitions, tolerance).is_some()
+}
+
+/// Pair each guess with a different definition, given as the ways it can be [accepted](accepted),
+/// that it [matches](matches), using Kuhn's algorithm for bipartite matching. Returns the guess
+/// paired with each definition, or `None` if the guesses can't all be paired.
+fn pair_guesses(
+    guesses: &[&str],
+    definitions: &[Vec<&str>],
+    tolerance: Tolerance,
+) -> Option<Vec<Option<usize>>> {
+    /// Try to pair a guess with a definition not yet visited, moving the guesses already paired
+    /// with definitions on to others if they can be.
+    fn augment(
+        guess: usize,
+        edges: &[Vec<usize>],
+        visited: &mut [bool],
+        paired: &mut [Option<usize>],
+    ) -> bool {
+        for &definition in &edges[guess] {
+            if visited[definition] {
+                continue;
+            }
+            visited[definition] = true;
+            if paired[definition].is_none_or(|other| augment(other, edges, visited, paired)) {
+                paired[definition] = Some(guess);
+                return true;
+            }
+        }
+        false
+    }
+
+    let edges = guesses
+        .iter()
+        .map(|guess| {
+            (0..definitions.len())
+                .filter(|&i| {
+                    definitions[i]
+                        .iter()
+                        .any(|spelling| matches(guess, spelling, tolerance))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut paired = vec![None; definitions.len()];
+    for guess in 0..guesses.len() {
+        let mut visited = vec![false; definitions.len()];
+        if !augment(guess, &edges, &mut visited, &mut paired) {
+            return None;
+        }
+    }
+    Some(paired)
+}
+
+/// Whether a guess is at most the tolerated number of typos away from a definition, either as it
+/// is written or, if parentheses are optional, with its parenthesised text left out.
+fn matches(guess: &str, definition: &str, tolerance: Tolerance) -> bool {
+    edit_distance(guess, definition) <= tolerance.typos
+        || tolerance.parentheses
+            && edit_distance(guess, &without_parentheses(definition)) <= tolerance.typos
+}
+
+/// Remove text in parentheses, including nested parentheses, and the spaces left around it.
+fn without_parentheses(s: &str) -> String {
+    let mut depth = 0_usize;
+    let mut outside = String::new();
+    for c in s.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            _ if depth == 0 => outside.push(c),
+            _ => {}
+        }
+    }
+    outside.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The definitions left over after pairing each guess with a different definition that it
+/// [matches](matches), or `None` if the guesses can't all be paired.
+fn leftover<'a>(
+    card: &Card,
+    guesses: &[&str],
+    definitions: Vec<&'a str>,
+    tolerance: Tolerance,
+) -> Option<Vec<&'a str>> {
+    let accepted = definitions
+        .iter()
+        .map(|definition| accepted(card, definition))
+        .collect::<Vec<_>>();
+    let paired = pair_guesses(guesses, &accepted, tolerance)?;
+    let leftover = definitions
+        .into_iter()
+        .zip(paired)
+        .filter(|(_, guess)| guess.is_none())
+        .map(|(definition, _)| definition);
+    Some(leftover.collect())
+}
+
+/// The Levenshtein distance between two strings, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// What tells a card apart from the other cards that share its prompt, from
+/// [`Question::disambiguation`].
+///
+/// When displayed, this gives a short description such as `also “rot”`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disambiguation<'a> {
+    /// Another of the card's terms, which the other cards do not have.
+    OtherTerm(&'a str),
+    /// The start of one of the card's definitions, as little as no other card's definitions
+    /// start with.
+    DefinitionStart(&'a str),
+}
+
+impl Display for Disambiguation<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OtherTerm(term) => {
+                write!(f, "also “{}", truncated(term, MAX_OTHER_TERM_CHARS))?;
+                if term.chars().count() > MAX_OTHER_TERM_CHARS {
+                    f.write_str("…")?;
+                }
+                f.write_str("”")
+            }
+            Self::DefinitionStart(start) => write!(f, "answer starts with “{start}…”"),
+        }
+    }
+}
+
+/// The longest other term shown in full by a [`Disambiguation`].
+const MAX_OTHER_TERM_CHARS: usize = 30;
+
+/// Tell `card` apart from `others`, the other cards that have `prompt` as a term, preferring
+/// another of its terms to giving away part of its answer. Returns `None` if there are no other
+/// cards or they can't be told apart.
+fn disambiguation<'a>(
+    card: &'a Card,
+    prompt: &str,
+    others: &[&Card],
+) -> Option<Disambiguation<'a>> {
+    if others.is_empty() {
+        return None;
+    }
+    if let Some(term) = card
+        .terms
+        .iter()
+        .find(|term| *term != prompt && others.iter().all(|other| !other.terms.contains(*term)))
+    {
+        return Some(Disambiguation::OtherTerm(term));
+    }
+    card.definitions
+        .iter()
+        .filter_map(|definition| {
+            let ends = definition.char_indices().map(|(i, c)| i + c.len_utf8());
+            ends.map(|end| &definition[..end]).find(|start| {
+                others
+                    .iter()
+                    .all(|other| other.definitions.iter().all(|d| !d.starts_with(start)))
+            })
+        })
+        .min_by_key(|start| start.chars().count())
+        .map(Disambiguation::DefinitionStart)
+}
+
+fn truncated(s: &str, chars: usize) -> &str {
+    s.char_indices().nth(chars).map_or(s, |(i, _)| &s[..i])
+}
+
+/// The cards other than the card at `index` that have `prompt` as a term.
+fn alternatives<'a>(cards: &'a [(CardKey, Card)], index: usize, prompt: &str) -> Vec<&'a Card> {
+    cards
+        .iter()
+        .enumerate()
+        .filter(|&(i, (_, card))| i != index && card.terms.contains(prompt))
+        .map(|(_, (_, card))| card)
+        .collect()
+}
+
+/// How questions would be chosen from the loaded cards, from [`Engine::plan`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Plan<'a> {
+    /// Every loaded card, in the order they were loaded.
+    pub cards: Vec<PlannedCard<'a>>,
+    /// The expected number of questions until every card is mastered if every answer is correct,
+    /// or `None` if some cards would never be.
+    pub expected_questions: Option<f64>,
+}
+
+/// A card in a [`Plan`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct PlannedCard<'a> {
+    /// The card.
+    pub card: &'a Card,
+    /// How well the card is known.
+    pub knowledge: Knowledge,
+    /// Whether the card is [at risk](Knowledge::is_at_risk) of moving down a level.
+    pub at_risk: bool,
+    /// The probability of the card being asked next, from 0 to 1.
+    pub probability: f64,
+}
+
+/// A summary of how well the loaded cards are known, from [`Engine::snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Snapshot {
+    /// The number of loaded cards.
+    pub cards: usize,
+    /// The number of loaded cards at each knowledge level.
+    pub level_distribution: Vec<usize>,
+    /// The number of loaded cards that are mastered: at or above the
+    /// [mastery level](Engine::set_mastery), or at the highest level if there is none.
+    pub mastered: usize,
+    /// The number of loaded cards waiting to be [redrilled](Engine::set_redrill).
+    pub redrill: usize,
+}
+
+impl Snapshot {
+    /// Whether every card is mastered and none are waiting to be redrilled.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.mastered == self.cards && self.redrill == 0
+    }
+}
+
+/// An error in the [`Engine`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A question was requested but no cards have been loaded.
+    #[error("no cards have been loaded")]
+    NoCards,
+    /// An answer was given but there is no current question.
+    #[error("there is no question to answer")]
+    NoQuestion,
+    /// The number of knowledge weights does not match the number of levels.
+    #[error("{weights} knowledge weights were given but there are {levels} levels")]
+    KnowledgeWeights {
+        /// The number of levels in the store.
+        levels: usize,
+        /// The number of weights that were set.
+        weights: usize,
+    },
+    /// The mastery level is above the highest knowledge level.
+    #[error(
+        "the mastery level {} is above the highest level, {}",
+        mastery.get(),
+        max.get()
+    )]
+    Mastery {
+        /// The mastery level that was set.
+        mastery: KnowledgeLevel,
+        /// The highest knowledge level in the store.
+        max: KnowledgeLevel,
+    },
+    /// The knowledge of a card could not be read.
+    #[error(transparent)]
+    GetKnowledge(#[from] GetKnowledgeError),
+    /// The knowledge of a card could not be changed.
+    #[error(transparent)]
+    SetKnowledge(#[from] SetKnowledgeError),
+    /// A correct answer could not be recorded.
+    #[error(transparent)]
+    RecordCorrect(#[from] RecordCorrectError),
+    /// An incorrect answer could not be recorded.
+    #[error(transparent)]
+    RecordIncorrect(#[from] RecordIncorrectError),
+    /// An answer given with hints could not be recorded.
+    #[error(transparent)]
+    RecordHinted(#[from] RecordHintedError),
+    /// Whether a statement was recognised could not be recorded.
+    #[error(transparent)]
+    RecordRecognised(#[from] RecordRecognisedError),
+    /// The number of knowledge levels could not be changed.
+    #[error(transparent)]
+    SetLevels(#[from] SetLevelsError),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write as _;
+
+    use rand::Rng;
+
+    use super::{
+        parse_set, Disambiguation, Engine, Error, Grade, Knowledge, KnowledgeLevel,
+        KnowledgeStore as _, Levels, MemoryStore, Set,
+    };
+
+    fn set(cards: usize) -> Set {
+        let source = (0..cards).fold(String::from("title\n"), |mut source, i| {
+            writeln!(source, "t{i} - d{i}").unwrap();
+            source
+        });
+        revise_parser::parse_set(&source).unwrap()
+    }
+
+    #[test]
+    fn no_duplicates() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(2), false);
+        let mut rng = rand::thread_rng();
+
+        let mut previous = None;
+        for _ in 0..1000 {
+            let prompt = engine.next_question().unwrap().prompt.to_owned();
+            assert_ne!(Some(&prompt), previous.as_ref());
+            previous = Some(prompt);
+            engine.record_result(rng.gen()).unwrap();
+        }
+    }
+
+    #[test]
+    fn answering() {
+        let mut engine = Engine::new(MemoryStore::new());
+        assert!(matches!(engine.next_question(), Err(Error::NoCards)));
+
+        engine.load_set(set(3), true);
+        assert!(matches!(engine.submit_answer("x"), Err(Error::NoQuestion)));
+
+        let question = engine.next_question().unwrap();
+        assert!(question.prompt.starts_with('d'));
+        let answer = question.prompt.replace('d', "t");
+        assert!(question.is_correct(&answer));
+        assert!(engine.check_answer(&answer).unwrap());
+        assert!(!engine.check_answer("").unwrap());
+        assert!(engine.submit_answer(&answer).unwrap());
+        assert!(matches!(engine.record_result(true), Err(Error::NoQuestion)));
+
+        engine.next_question().unwrap();
+        assert!(!engine.submit_answer("wrong").unwrap());
+
+        let snapshot = engine.snapshot().unwrap();
+        assert_eq!(snapshot.cards, 3);
+        assert_eq!(snapshot.level_distribution.iter().sum::<usize>(), 3);
+        assert_eq!(snapshot.level_distribution[1], 1);
+
+        engine.clear_knowledge().unwrap();
+        assert_eq!(engine.snapshot().unwrap().level_distribution, [3, 0, 0, 0]);
+
+        engine.next_question().unwrap();
+        engine.record_hinted().unwrap();
+        assert_eq!(engine.snapshot().unwrap().level_distribution, [3, 0, 0, 0]);
+        assert!(matches!(engine.record_hinted(), Err(Error::NoQuestion)));
+    }
+
+    #[test]
+    fn statements() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(3), false);
+
+        let (mut true_, mut false_) = (0, 0);
+        for _ in 0..200 {
+            let statement = engine.next_statement().unwrap();
+            assert_eq!(
+                statement.prompt.replace('t', "d"),
+                *statement.card.definitions.iter().next().unwrap()
+            );
+            if statement.is_true() {
+                assert_eq!(statement.shown, statement.card);
+                true_ += 1;
+            } else {
+                assert_ne!(statement.shown, statement.card);
+                false_ += 1;
+            }
+            engine.record_recognised(true).unwrap();
+        }
+        assert!(true_ > 50 && false_ > 50);
+        assert_eq!(engine.snapshot().unwrap().level_distribution, [3, 0, 0, 0]);
+
+        engine.next_question().unwrap();
+        engine.record_result(true).unwrap();
+        engine.next_statement().unwrap();
+        engine.record_recognised(false).unwrap();
+        assert!(matches!(
+            engine.record_recognised(false),
+            Err(Error::NoQuestion)
+        ));
+        assert_eq!(engine.snapshot().unwrap().level_distribution, [2, 1, 0, 0]);
+
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(1), false);
+        for _ in 0..20 {
+            assert!(engine.next_statement().unwrap().is_true());
+        }
+
+        // Statements told wrongly are redrilled like questions answered wrongly.
+        engine.set_redrill(true);
+        engine.next_statement().unwrap();
+        engine.record_recognised(false).unwrap();
+        assert_eq!(engine.snapshot().unwrap().redrill, 1);
+    }
+
+    #[test]
+    fn ask() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(3), false);
+        let second = engine.cards().nth(1).unwrap().clone();
+
+        for _ in 0..3 {
+            let question = engine.ask(1).unwrap();
+            assert_eq!(*question.card, second);
+            assert_eq!(question.level_distribution, [3, 0, 0, 0]);
+        }
+        assert!(engine
+            .submit_answer(second.definitions.first().unwrap())
+            .unwrap());
+        assert_eq!(engine.snapshot().unwrap().level_distribution, [2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn prompt_language() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(
+            parse_set("French\n#! language: fr\n\nrouge - red\n").unwrap(),
+            false,
+        );
+        engine.load_set(parse_set("Other\n\nx - y\n").unwrap(), false);
+        for _ in 0..10 {
+            let question = engine.next_question().unwrap();
+            let expected = (question.prompt == "rouge").then_some("fr");
+            assert_eq!(question.prompt_language, expected);
+        }
+
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(
+            parse_set("French\n#! language: fr\n\nrouge - red\n").unwrap(),
+            true,
+        );
+        assert_eq!(engine.next_question().unwrap().prompt_language, None);
+    }
+
+    #[test]
+    fn plan() {
+        let mut engine = Engine::new(MemoryStore::new());
+        assert!(matches!(engine.plan(), Err(Error::NoCards)));
+
+        engine.load_set(set(2), false);
+        let key = engine.card_keys().nth(1).unwrap().clone();
+        let knowledge = Knowledge {
+            level: KnowledgeLevel::new(1).unwrap(),
+            safety_net: true,
+        };
+        engine.store_mut().set_knowledge(&key, knowledge).unwrap();
+        engine.set_mastery(Some(KnowledgeLevel::new(3).unwrap()));
+
+        let plan = engine.plan().unwrap();
+        let probabilities = plan
+            .cards
+            .iter()
+            .map(|card| card.probability)
+            .collect::<Vec<_>>();
+        assert!((probabilities[0] - 2.0 / 3.0).abs() < 1e-9);
+        assert!((probabilities[1] - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(plan.cards[1].knowledge, knowledge);
+        assert_eq!(plan.expected_questions, Some(5.0));
+
+        engine.set_knowledge_weights(vec![1.0, 0.0, 1.0, 1.0]);
+        assert_eq!(engine.plan().unwrap().expected_questions, None);
+    }
+
+    #[test]
+    fn grades() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(1), false);
+        let mut grade = |grade| {
+            engine.next_question().unwrap();
+            engine.record_grade(grade).unwrap();
+            engine.snapshot().unwrap().level_distribution
+        };
+        assert_eq!(grade(Grade::Easy), [0, 0, 1, 0]);
+        assert_eq!(grade(Grade::Hard), [0, 0, 1, 0]);
+        assert_eq!(grade(Grade::Again), [0, 0, 1, 0]);
+        assert_eq!(grade(Grade::Again), [0, 1, 0, 0]);
+        assert_eq!(grade(Grade::Good), [0, 0, 1, 0]);
+        assert_eq!(grade(Grade::Easy), [0, 0, 0, 1]);
+        assert!(matches!(
+            engine.record_grade(Grade::Easy),
+            Err(Error::NoQuestion)
+        ));
+    }
+
+    #[test]
+    fn completion() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(2), false);
+        engine.load_set(set(2), false);
+        assert_eq!(engine.title(), "title + title");
+        assert_eq!(engine.cards().len(), 2);
+
+        while !engine.snapshot().unwrap().is_complete() {
+            engine.next_question().unwrap();
+            engine.record_result(true).unwrap();
+        }
+    }
+
+    #[test]
+    fn levels() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(2), false);
+        engine.set_levels(Levels::new(6).unwrap()).unwrap();
+        assert_eq!(
+            engine.snapshot().unwrap().level_distribution,
+            [2, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            super::default_knowledge_weights(Levels::new(6).unwrap()).len(),
+            6
+        );
+
+        engine.set_knowledge_weights(vec![1.0; 4]);
+        assert!(matches!(
+            engine.next_question(),
+            Err(Error::KnowledgeWeights {
+                levels: 6,
+                weights: 4
+            })
+        ));
+
+        engine.set_knowledge_weights(vec![1.0; 6]);
+        let mut answers = 0;
+        while !engine.snapshot().unwrap().is_complete() {
+            engine.next_question().unwrap();
+            engine.record_result(true).unwrap();
+            answers += 1;
+        }
+        assert_eq!(answers, 10);
+    }
+
+    #[test]
+    fn at_risk() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(2), false);
+        engine.set_at_risk_weight(5.0);
+        assert_eq!(engine.next_question().unwrap().at_risk, 0);
+
+        while !engine.snapshot().unwrap().is_complete() {
+            engine.record_result(true).unwrap();
+            engine.next_question().unwrap();
+        }
+        // Cards on the highest level always move down when answered incorrectly.
+        assert_eq!(engine.next_question().unwrap().at_risk, 2);
+    }
+
+    #[test]
+    fn typos() {
+        assert_eq!(super::edit_distance("", "abc"), 3);
+        assert_eq!(super::edit_distance("kitten", "sitting"), 3);
+        assert_eq!(super::edit_distance("héllo", "hello"), 1);
+
+        let mut engine = Engine::new(MemoryStore::new());
+        let set = revise_parser::parse_set("title\nt - colour, flavour\nu - v\n").unwrap();
+        engine.load_set(set, false);
+        while engine.next_question().unwrap().prompt != "t" {}
+        assert!(!engine.check_answer("color, flavour").unwrap());
+
+        engine.set_typo_tolerance(1);
+        while engine.next_question().unwrap().prompt != "t" {}
+        assert!(engine.check_answer("color, flavor").unwrap());
+        assert!(engine.check_answer("flavor, colour").unwrap());
+        assert!(!engine.check_answer("colr, flavour").unwrap());
+        assert!(!engine.check_answer("colour").unwrap());
+        assert!(!engine.check_answer("colour, flavour, x").unwrap());
+
+        // Every guess is close to every definition, which must not take exponential time to pair.
+        let definitions = (10..22).map(|i| format!("word{i}")).collect::<Vec<_>>();
+        let source = format!("title\nt - {}\nu - v\n", definitions.join(", "));
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(revise_parser::parse_set(&source).unwrap(), false);
+        engine.set_typo_tolerance(2);
+        while engine.next_question().unwrap().prompt != "t" {}
+        let mut guesses = definitions
+            .iter()
+            .rev()
+            .skip(1)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert!(!engine.check_answer(&guesses.join(", ")).unwrap());
+        guesses.push("zzzzzz".to_owned());
+        assert!(!engine.check_answer(&guesses.join(", ")).unwrap());
+        guesses.pop();
+        guesses.push("word99".to_owned());
+        assert!(engine.check_answer(&guesses.join(", ")).unwrap());
+    }
+
+    #[test]
+    fn missing() {
+        let source = "title\nt - a, b/c, d (e)\nu -> x, y\n";
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(revise_parser::parse_set(source).unwrap(), false);
+
+        let question = loop {
+            let question = engine.next_question().unwrap();
+            if question.prompt == "t" {
+                break question;
+            }
+        };
+        assert_eq!(question.missing("c"), Some(vec!["a", "d (e)"]));
+        assert_eq!(question.missing("d (e), a"), Some(vec!["b/c"]));
+        assert_eq!(question.missing("a, x"), None);
+        assert_eq!(question.missing("a, c, d (e)"), None);
+        assert_eq!(question.missing(""), None);
+
+        let question = loop {
+            let question = engine.next_question().unwrap();
+            if question.prompt == "u" {
+                break question;
+            }
+        };
+        assert_eq!(question.missing("x"), None);
+    }
+
+    #[test]
+    fn synonyms() {
+        let source = "title\nt - colour/color, hue\nu -> a/b, c\n";
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(revise_parser::parse_set(source).unwrap(), false);
+
+        while engine.next_question().unwrap().prompt != "t" {}
+        assert!(engine.check_answer("hue, color").unwrap());
+        assert!(engine.check_answer("colour, hue").unwrap());
+        assert!(engine.check_answer("colour/color, hue").unwrap());
+        assert!(!engine.check_answer("colour, color").unwrap());
+        assert!(!engine.check_answer("colour, color, hue").unwrap());
+
+        while engine.next_question().unwrap().prompt != "u" {}
+        assert!(engine.check_answer("b, c").unwrap());
+        assert!(!engine.check_answer("c, a").unwrap());
+    }
+
+    #[test]
+    fn optional_parentheses() {
+        assert_eq!(super::without_parentheses("to go (somewhere)"), "to go");
+        assert_eq!(super::without_parentheses("a (b (c)) d"), "a d");
+        assert_eq!(super::without_parentheses("(to) be"), "be");
+
+        let source = "title\n#! parentheses: optional\nt - to go (somewhere), bank (river)\n";
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(revise_parser::parse_set(source).unwrap(), false);
+        engine.next_question().unwrap();
+        assert!(engine.check_answer("to go, bank").unwrap());
+        assert!(engine.check_answer("bank (river), to go").unwrap());
+        assert!(engine
+            .check_answer("to go (somewhere), bank (river)")
+            .unwrap());
+        assert!(!engine.check_answer("to go").unwrap());
+
+        engine.set_typo_tolerance(1);
+        engine.next_question().unwrap();
+        assert!(engine.check_answer("to g, bank").unwrap());
+
+        let source = "title\nt - to go (somewhere)\n";
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(revise_parser::parse_set(source).unwrap(), false);
+        engine.next_question().unwrap();
+        assert!(!engine.check_answer("to go").unwrap());
+    }
+
+    #[test]
+    fn steps() {
+        let set = revise_parser::parse_set("title\nmitosis -> pro, meta, ana, telo\n").unwrap();
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set, false);
+
+        let question = engine.next_question().unwrap();
+        let mut steps = question.steps.clone().unwrap();
+        steps.sort_unstable();
+        assert_eq!(steps, ["ana", "meta", "pro", "telo"]);
+        assert!(question.is_correct("pro, meta, ana, telo"));
+        assert!(!question.is_correct("meta, pro, ana, telo"));
+        assert!(!question.is_correct("pro, meta, ana"));
+
+        engine.set_order_tolerance(0.2);
+        engine.set_typo_tolerance(1);
+        engine.next_question().unwrap();
+        assert!(engine.check_answer("meta, pro, ana, telo").unwrap());
+        assert!(engine.check_answer("pro, mta, ana, telo").unwrap());
+        assert!(!engine.check_answer("meta, pro, telo, ana").unwrap());
+
+        assert!(super::kendall_tau_distance(&[]).abs() < f64::EPSILON);
+        assert!((super::kendall_tau_distance(&[2, 1, 0]) - 1.0).abs() < f64::EPSILON);
+        assert!((super::kendall_tau_distance(&[0, 2, 1, 3]) - 1.0 / 6.0).abs() < f64::EPSILON);
+
+        let set = revise_parser::parse_set("title\nmitosis -> pro, meta\n").unwrap();
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set, true);
+        assert_eq!(engine.next_question().unwrap().steps, None);
+    }
+
+    #[test]
+    fn ambiguous() {
+        let set = revise_parser::parse_set("title\na - x\nb - x, y\nc - y\nd - z\n").unwrap();
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set, true);
+
+        let ambiguous = engine.ambiguous_prompts();
+        assert_eq!(
+            ambiguous
+                .iter()
+                .map(|(prompt, cards)| (*prompt, cards.len()))
+                .collect::<Vec<_>>(),
+            [("x", 2), ("y", 2)]
+        );
+
+        engine.set_accept_ambiguous(true);
+        loop {
+            let question = engine.next_question().unwrap();
+            if question.prompt == "x" {
+                assert_eq!(question.alternatives.len(), 1);
+                assert!(question.is_correct("a"));
+                assert!(question.is_correct("b"));
+                assert!(!question.is_correct("c"));
+                assert!(engine.check_answer("a").unwrap());
+                assert!(engine.check_answer("b").unwrap());
+                break;
+            }
+        }
+
+        engine.set_accept_ambiguous(false);
+        loop {
+            let question = engine.next_question().unwrap();
+            if question.prompt == "x" {
+                let expected = if question.card.definitions.contains("a") {
+                    Disambiguation::DefinitionStart("a")
+                } else {
+                    Disambiguation::OtherTerm("y")
+                };
+                assert_eq!(question.disambiguation, Some(expected));
+                break;
+            }
+            if question.prompt == "z" {
+                assert_eq!(question.disambiguation, None);
+            }
+        }
+        assert_ne!(
+            engine.check_answer("a").unwrap(),
+            engine.check_answer("b").unwrap()
+        );
+    }
+
+    #[test]
+    fn disambiguation() {
+        let cards = [
+            "rot, rouge - red",
+            "rot - rotten",
+            "rose - pink",
+            "rose - ripe",
+            "rose - ripened",
+        ]
+        .map(|card| {
+            let set = revise_parser::parse_set(&format!("title\n{card}\n")).unwrap();
+            set.cards.into_iter().next().unwrap()
+        });
+        let card = |i: usize| &cards[i];
+        let hint = |i, prompt, others: &[usize]| {
+            let others = others.iter().map(|&i| card(i)).collect::<Vec<_>>();
+            super::disambiguation(card(i), prompt, &others).map(|hint| hint.to_string())
+        };
+
+        assert_eq!(hint(0, "rot", &[]), None);
+        assert_eq!(hint(0, "rot", &[1]).as_deref(), Some("also “rouge”"));
+        assert_eq!(
+            hint(1, "rot", &[0]).as_deref(),
+            Some("answer starts with “ro…”")
+        );
+        assert_eq!(
+            hint(3, "rose", &[2]).as_deref(),
+            Some("answer starts with “r…”")
+        );
+        assert_eq!(hint(3, "rose", &[4]), None);
+        assert_eq!(
+            Disambiguation::OtherTerm(&"x".repeat(40)).to_string(),
+            format!("also “{}…”", "x".repeat(30)),
+        );
+    }
+
+    #[test]
+    fn make_incomplete() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(2), false);
+        assert_eq!(engine.make_incomplete().unwrap(), None);
+
+        while !engine.snapshot().unwrap().is_complete() {
+            engine.next_question().unwrap();
+            engine.record_result(true).unwrap();
+        }
+        assert_eq!(engine.make_incomplete().unwrap(), KnowledgeLevel::new(2));
+        assert_eq!(engine.snapshot().unwrap().level_distribution, [0, 0, 2, 0]);
+
+        engine.set_mastery(KnowledgeLevel::new(2));
+        assert_eq!(engine.make_incomplete().unwrap(), KnowledgeLevel::new(1));
+        assert_eq!(engine.snapshot().unwrap().level_distribution, [0, 2, 0, 0]);
+    }
+
+    #[test]
+    fn mastery() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(3), false);
+        engine.set_mastery(Some(KnowledgeLevel::new(4).unwrap()));
+        assert!(matches!(engine.next_question(), Err(Error::Mastery { .. })));
+
+        engine.set_mastery(Some(KnowledgeLevel::new(1).unwrap()));
+        let first = engine.next_question().unwrap().prompt.to_owned();
+        engine.record_result(true).unwrap();
+        assert_eq!(engine.snapshot().unwrap().mastered, 1);
+
+        // The mastered card is not asked again until every card is mastered.
+        for _ in 0..2 {
+            assert_ne!(engine.next_question().unwrap().prompt, first);
+            engine.record_result(true).unwrap();
+        }
+        assert!(engine.snapshot().unwrap().is_complete());
+        engine.next_question().unwrap();
+
+        engine.clear_knowledge().unwrap();
+        engine.set_overlearn(1.0);
+        let first = engine.next_question().unwrap().prompt.to_owned();
+        engine.record_result(true).unwrap();
+        // Now only the mastered card is asked, even twice in a row.
+        assert_eq!(engine.next_question().unwrap().prompt, first);
+    }
+
+    #[test]
+    fn both_directions() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(2), false);
+        engine.load_set(set(2), true);
+        engine.set_both_directions(KnowledgeLevel::new(2));
+        let level = |engine: &Engine<MemoryStore>, index: usize| {
+            let key = engine.card_keys().nth(index).unwrap();
+            engine.store().knowledge(key).unwrap().level.get()
+        };
+        let index = |engine: &Engine<MemoryStore>, prompt: &str| {
+            engine
+                .cards()
+                .position(|card| card.terms.contains(prompt))
+                .unwrap()
+        };
+        let (forwards, backwards) = (index(&engine, "t0"), index(&engine, "d0"));
+
+        for _ in 0..3 {
+            engine.ask(forwards).unwrap();
+            engine.record_result(true).unwrap();
+        }
+        assert_eq!(level(&engine, forwards), 1);
+        engine.ask(backwards).unwrap();
+        engine.record_result(true).unwrap();
+        engine.ask(forwards).unwrap();
+        engine.record_grade(Grade::Easy).unwrap();
+        assert_eq!(level(&engine, forwards), 3);
+
+        // Questions alternate between the two ways round.
+        let mut previous = None;
+        for _ in 0..20 {
+            let inverted = engine.next_question().unwrap().prompt.starts_with('d');
+            assert_ne!(Some(inverted), previous);
+            previous = Some(inverted);
+            engine.record_result(false).unwrap();
+        }
+    }
+
+    #[test]
+    fn redrill() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(2), false);
+        engine.set_mastery(Some(KnowledgeLevel::new(1).unwrap()));
+        engine.set_redrill(true);
+
+        let missed = engine.next_question().unwrap().prompt.to_owned();
+        engine.record_result(false).unwrap();
+        while engine.snapshot().unwrap().mastered < 2 {
+            engine.next_question().unwrap();
+            engine.record_result(true).unwrap();
+        }
+        assert_eq!(engine.snapshot().unwrap().redrill, 1);
+        assert!(!engine.snapshot().unwrap().is_complete());
+
+        // Only the missed card is asked, until it has been answered correctly twice in a row.
+        for correct in [true, false, true, true] {
+            assert_eq!(engine.next_question().unwrap().prompt, missed);
+            engine.record_result(correct).unwrap();
+            while engine.snapshot().unwrap().mastered < 2 {
+                engine.next_question().unwrap();
+                engine.record_result(true).unwrap();
+            }
+        }
+        assert!(engine.snapshot().unwrap().is_complete());
+    }
+
+    #[test]
+    fn redrill_hinted() {
+        let mut engine = Engine::new(MemoryStore::new());
+        engine.load_set(set(2), false);
+        engine.set_mastery(Some(KnowledgeLevel::new(1).unwrap()));
+        engine.set_redrill(true);
+
+        let missed = engine.next_question().unwrap().prompt.to_owned();
+        engine.record_result(false).unwrap();
+        while engine.snapshot().unwrap().mastered < 2 {
+            engine.next_question().unwrap();
+            engine.record_result(true).unwrap();
+        }
+
+        // Answers given with hints count towards taking a card out of the redrill queue.
+        for _ in 0..2 {
+            assert_eq!(engine.next_question().unwrap().prompt, missed);
+            engine.record_grade(Grade::Hard).unwrap();
+        }
+        assert!(engine.snapshot().unwrap().is_complete());
+    }
+}
@@ -0,0 +1,320 @@
+//! A minimal desktop frontend for `revise`, sharing its knowledge database with the command line.
+#![warn(clippy::all, clippy::pedantic)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use eframe::egui;
+
+use revise_engine::{Database, Engine};
+
+fn main() -> eframe::Result {
+    let directory = env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("."), PathBuf::from);
+
+    eframe::run_native(
+        "revise",
+        eframe::NativeOptions::default(),
+        Box::new(|_cx| Ok(Box::new(App::new(directory)))),
+    )
+}
+
+struct App {
+    directory: PathBuf,
+    sets: Vec<(PathBuf, bool)>,
+    invert: bool,
+    error: Option<String>,
+    view: View,
+}
+
+enum View {
+    Pick,
+    Learn(Box<Learn>),
+    Stats(Box<Learn>),
+}
+
+/// An ongoing learning session.
+struct Learn {
+    engine: Engine<Database>,
+    prompt: String,
+    disambiguation: Option<String>,
+    /// The shuffled steps to put in order, for cards with steps.
+    steps: Option<String>,
+    level_distribution: Vec<usize>,
+    at_risk: usize,
+    answer: String,
+    /// Set to the expected answer when the last answer was incorrect.
+    incorrect: Option<String>,
+}
+
+impl App {
+    fn new(directory: PathBuf) -> Self {
+        let mut this = Self {
+            directory,
+            sets: Vec::new(),
+            invert: false,
+            error: None,
+            view: View::Pick,
+        };
+        this.scan();
+        this
+    }
+
+    fn scan(&mut self) {
+        self.sets = match fs::read_dir(&self.directory) {
+            Ok(entries) => {
+                let mut sets = entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension() == Some("set".as_ref()))
+                    .map(|path| (path, false))
+                    .collect::<Vec<_>>();
+                sets.sort();
+                sets
+            }
+            Err(e) => {
+                self.error = Some(format!("couldn't read {}: {e}", self.directory.display()));
+                Vec::new()
+            }
+        };
+    }
+
+    fn start(&mut self) -> Result<Learn, String> {
+        let database_path = match env::var_os("REVISE_DATABASE") {
+            Some(path) => PathBuf::from(path),
+            None => revise_engine::default_database_path().ok_or("couldn't find home directory")?,
+        };
+        fs::create_dir_all(database_path.parent().unwrap())
+            .map_err(|e| format!("couldn't create data directory: {e}"))?;
+        let database = Database::open(&database_path).map_err(|e| e.to_string())?;
+
+        let mut engine = Engine::new(database);
+        for (path, _) in self.sets.iter().filter(|(_, selected)| *selected) {
+            engine.load_set(read_set(path)?, self.invert);
+        }
+
+        let mut learn = Learn {
+            engine,
+            prompt: String::new(),
+            disambiguation: None,
+            steps: None,
+            level_distribution: Vec::new(),
+            at_risk: 0,
+            answer: String::new(),
+            incorrect: None,
+        };
+        learn.next().map_err(|e| e.to_string())?;
+        Ok(learn)
+    }
+
+    fn pick(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Choose sets to learn");
+        ui.label(self.directory.display().to_string());
+        ui.separator();
+
+        if self.sets.is_empty() {
+            ui.label("No .set files found in this directory.");
+        }
+        for (path, selected) in &mut self.sets {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            ui.checkbox(selected, name);
+        }
+
+        ui.separator();
+        ui.checkbox(&mut self.invert, "Invert terms and definitions");
+        ui.horizontal(|ui| {
+            let any_selected = self.sets.iter().any(|(_, selected)| *selected);
+            if ui
+                .add_enabled(any_selected, egui::Button::new("Learn"))
+                .clicked()
+            {
+                match self.start() {
+                    Ok(learn) => {
+                        self.error = None;
+                        self.view = View::Learn(Box::new(learn));
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            if ui.button("Refresh").clicked() {
+                self.scan();
+            }
+        });
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let next_view = match &mut self.view {
+                View::Pick => {
+                    self.pick(ui);
+                    None
+                }
+                View::Learn(learn) => match learn.show(ui) {
+                    Ok(action) => action,
+                    Err(e) => {
+                        self.error = Some(e.to_string());
+                        Some(Action::Back)
+                    }
+                },
+                View::Stats(learn) => learn.show_stats(ui),
+            };
+
+            match (next_view, std::mem::replace(&mut self.view, View::Pick)) {
+                (Some(Action::Back), _) => {}
+                (Some(Action::Stats), View::Learn(learn)) => self.view = View::Stats(learn),
+                (Some(Action::Resume), View::Stats(learn)) => self.view = View::Learn(learn),
+                (_, view) => self.view = view,
+            }
+
+            if let Some(error) = &self.error {
+                ui.separator();
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+    }
+}
+
+enum Action {
+    Back,
+    Stats,
+    Resume,
+}
+
+impl Learn {
+    fn next(&mut self) -> Result<(), revise_engine::Error> {
+        let question = self.engine.next_question()?;
+        self.prompt = question.prompt.to_owned();
+        self.disambiguation = question.disambiguation.map(|d| d.to_string());
+        self.steps = question.steps.map(|steps| steps.join(", "));
+        self.level_distribution
+            .clone_from(&question.level_distribution);
+        self.at_risk = question.at_risk;
+        self.answer.clear();
+        self.incorrect = None;
+        Ok(())
+    }
+
+    fn record(&mut self, correct: bool) -> Result<(), revise_engine::Error> {
+        self.engine.record_result(correct)?;
+        self.next()
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui) -> Result<Option<Action>, revise_engine::Error> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            if ui.button("Back to sets").clicked() {
+                action = Some(Action::Back);
+            }
+            if ui.button("Stats").clicked() {
+                action = Some(Action::Stats);
+            }
+        });
+        ui.heading(self.engine.title());
+        let distribution = self
+            .level_distribution
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>();
+        ui.horizontal(|ui| {
+            ui.label(distribution.join(" "));
+            if self.at_risk != 0 {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("{} at risk", self.at_risk),
+                );
+            }
+        });
+        ui.separator();
+
+        ui.label(egui::RichText::new(&self.prompt).size(24.0));
+        if let Some(disambiguation) = &self.disambiguation {
+            ui.weak(format!("({disambiguation})"));
+        }
+        if let Some(steps) = &self.steps {
+            ui.label(format!("Put in order: {steps}"));
+        }
+
+        if let Some(expected) = &self.incorrect {
+            ui.colored_label(ui.visuals().error_fg_color, "Incorrect");
+            ui.label(format!("Answer: {expected}"));
+            let mut result = None;
+            ui.horizontal(|ui| {
+                if ui.button("Continue").clicked() {
+                    result = Some(false);
+                }
+                if ui.button("Override: I was correct").clicked() {
+                    result = Some(true);
+                }
+            });
+            if let Some(correct) = result {
+                self.record(correct)?;
+            }
+            return Ok(action);
+        }
+
+        let response = ui.add(egui::TextEdit::singleline(&mut self.answer).hint_text("Answer"));
+        response.request_focus();
+        if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+            if self.engine.check_answer(&self.answer)? {
+                self.record(true)?;
+            } else {
+                let card = self.engine.current_question().unwrap();
+                let expected = card
+                    .definitions
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.incorrect = Some(expected);
+            }
+        }
+
+        Ok(action)
+    }
+
+    fn show_stats(&self, ui: &mut egui::Ui) -> Option<Action> {
+        let mut action = None;
+        if ui.button("Resume learning").clicked() {
+            action = Some(Action::Resume);
+        }
+        ui.heading(self.engine.title());
+        ui.separator();
+
+        match self.engine.snapshot() {
+            Ok(snapshot) => {
+                ui.label(format!("{} cards", snapshot.cards));
+                for (level, &count) in snapshot.level_distribution.iter().enumerate() {
+                    #[allow(clippy::cast_precision_loss)]
+                    let progress = count as f32 / snapshot.cards.max(1) as f32;
+                    ui.add(
+                        egui::ProgressBar::new(progress).text(format!("Level {level}: {count}")),
+                    );
+                }
+                if snapshot.is_complete() {
+                    ui.label("Every card is learnt!");
+                }
+            }
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+            }
+        }
+        action
+    }
+}
+
+fn read_set(path: &Path) -> Result<revise_engine::Set, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {e}", path.display()))?;
+    revise_engine::parse_set(&text).map_err(|errors| {
+        format!(
+            "{} has {} error(s); run `revise check {}` for details",
+            path.display(),
+            errors.len(),
+            path.display(),
+        )
+    })
+}
@@ -0,0 +1,39 @@
+use std::fmt::Write as _;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Generate a set of roughly the given size in bytes, using every kind of syntax a card can have.
+fn synthetic_set(size: usize) -> String {
+    let mut source = String::from("A large set\n#! author: benchmark\n\n");
+    let mut i = 0_u32;
+    while source.len() < size {
+        match i % 4 {
+            0 => writeln!(source, "term {i} - definition number {i}"),
+            1 => writeln!(source, "t{i}, other term {i} - d{i}, \"quoted, {i}\" !3"),
+            2 => writeln!(source, "word-{i}   -   the meaning of {i} # a comment"),
+            _ => writeln!(source, "\"{i} \\\"escaped\\\"\" - é{i}, ü{i}, ∑{i}\n"),
+        }
+        .unwrap();
+        i += 1;
+    }
+    source
+}
+
+fn bench_parse_set(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_set");
+    group.sample_size(20);
+    for size in [10_000, 1_000_000, 5_000_000] {
+        let source = synthetic_set(size);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::new("owned", size), &source, |b, source| {
+            b.iter(|| revise_parser::parse_set(source).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("borrowed", size), &source, |b, source| {
+            b.iter(|| revise_parser::parse_set_borrowed(source).unwrap());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse_set);
+criterion_main!(benches);
@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    let Ok(set) = revise_parser::parse_set(data) else {
+        return;
+    };
+    let Some(formatted) = revise_parser::format_set(&set.title, &set.metadata, &set.cards) else {
+        return;
+    };
+    let reparsed = revise_parser::parse_set(&formatted).unwrap();
+    assert_eq!(reparsed, set);
+});
@@ -0,0 +1,86 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{Card, Set};
+
+/// Set algebra on the cards of sets.
+///
+/// Cards are the same if they have the same terms and definitions, just as two cards in one set
+/// file are duplicates even if their priorities differ. The cards kept are always those of `self`
+/// where it has them, along with its title and metadata.
+impl Set {
+    /// The cards of this set that are not in `other`.
+    #[must_use]
+    pub fn difference(&self, other: &Set) -> Set {
+        let other = identities(other);
+        self.filtered(|card| !other.contains(&identity(card)))
+    }
+
+    /// The cards of this set that are also in `other`.
+    #[must_use]
+    pub fn intersection(&self, other: &Set) -> Set {
+        let other = identities(other);
+        self.filtered(|card| other.contains(&identity(card)))
+    }
+
+    /// The cards of this set, along with the cards of `other` that are not in it.
+    #[must_use]
+    pub fn union(&self, other: &Set) -> Set {
+        let mut union = self.filtered(|_| true);
+        union.cards.extend(other.difference(self).cards);
+        union
+    }
+
+    fn filtered(&self, mut keep: impl FnMut(&Card) -> bool) -> Set {
+        Set {
+            title: self.title.clone(),
+            metadata: self.metadata.clone(),
+            cards: self
+                .cards
+                .iter()
+                .filter(|card| keep(card))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// What makes a card the same card as another.
+fn identity(card: &Card) -> (&BTreeSet<String>, &BTreeSet<String>) {
+    (&card.terms, &card.definitions)
+}
+
+fn identities(set: &Set) -> HashSet<(&BTreeSet<String>, &BTreeSet<String>)> {
+    set.cards.iter().map(identity).collect()
+}
+
+#[test]
+fn test_algebra() {
+    use crate::parse_set;
+
+    let a = parse_set("A\n#! author: me\n\nx - 1\ny - 2 !3\nz - 3\n").unwrap();
+    let b = parse_set("B\n\ny - 2\nz - three\nw - 4\n").unwrap();
+    let terms = |set: &Set| {
+        let mut terms = set
+            .cards
+            .iter()
+            .map(|card| card.terms.iter().next().unwrap().as_str())
+            .collect::<Vec<_>>();
+        terms.sort_unstable();
+        terms.join(" ")
+    };
+
+    let difference = a.difference(&b);
+    assert_eq!(difference.title, "A");
+    assert_eq!(difference.metadata, a.metadata);
+    assert_eq!(terms(&difference), "x z");
+
+    let intersection = a.intersection(&b);
+    assert_eq!(terms(&intersection), "y");
+    assert_eq!(intersection.cards.iter().next().unwrap().priority, 3);
+    assert_eq!(terms(&b.intersection(&a)), "y");
+
+    let union = a.union(&b);
+    assert_eq!(terms(&union), "w x y z z");
+    assert_eq!(union.cards.len(), 5);
+    assert_eq!(a.union(&a), a);
+}
@@ -0,0 +1,433 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use crate::{parse_set_borrowed, Card, ParseError, SetMetadata};
+
+/// Format an option as it would be written in a set file, quoting it if necessary.
+///
+/// The result is parsed back to the same option both in a set file and by [`parse_guess`]. Returns
+/// `None` if the option cannot be written in a set file because it is empty, has leading or
+/// trailing whitespace or contains a control character.
+///
+/// [`parse_guess`]: crate::parse_guess
+#[must_use]
+pub fn format_option(option: &str) -> Option<Cow<'_, str>> {
+    if !is_valid_option(option) {
+        return None;
+    }
+
+    let plain = !option.contains("  ") && option.chars().all(|c| c.is_alphanumeric() || c == ' ');
+    if plain {
+        return Some(Cow::Borrowed(option));
+    }
+
+    let mut quoted = String::with_capacity(option.len() + 2);
+    quoted.push('"');
+    for c in option.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    Some(Cow::Owned(quoted))
+}
+
+/// Whether an option can be written in a set file: it is not empty, does not start or end with
+/// whitespace and does not contain control characters.
+#[must_use]
+pub fn is_valid_option(option: &str) -> bool {
+    !option.is_empty() && option.trim() == option && !option.chars().any(char::is_control)
+}
+
+/// Format a list of options separated by commas, in the order given, as it would be written in a
+/// set file.
+///
+/// Returns `None` if the list is empty or any option is not [valid](is_valid_option).
+#[must_use]
+pub fn format_options<S: AsRef<str>>(options: impl IntoIterator<Item = S>) -> Option<String> {
+    let mut formatted = String::new();
+    for option in options {
+        if !formatted.is_empty() {
+            formatted.push_str(", ");
+        }
+        formatted.push_str(&format_option(option.as_ref())?);
+    }
+    (!formatted.is_empty()).then_some(formatted)
+}
+
+/// Format a card as a line of a set file, without a line ending.
+///
+/// Returns `None` if the card cannot be written in a set file because it has no terms or
+/// definitions, or an invalid option or priority.
+#[must_use]
+pub fn format_card(card: &Card) -> Option<String> {
+    if card.priority == 0 {
+        return None;
+    }
+    let mut line = format_card_options(card, &card.terms)?;
+    if let Some(steps) = &card.steps {
+        line.push_str(" -> ");
+        line.push_str(&format_card_options(card, steps)?);
+    } else if let Some(table) = &card.table {
+        line.push_str(" -| ");
+        line.push_str(&format_option(&table.to_string())?);
+    } else {
+        line.push_str(" - ");
+        line.push_str(&format_card_options(card, &card.definitions)?);
+    }
+    if card.priority != 1 {
+        write!(line, " !{}", card.priority).unwrap();
+    }
+    Some(line)
+}
+
+/// Format options of a card like [`format_options`], except that its synonyms are written without
+/// quotes so that they are still split into spellings when parsed, including by
+/// [`parse_guess_synonyms`](crate::parse_guess_synonyms).
+///
+/// Returns `None` if the list is empty or any option cannot be written.
+#[must_use]
+pub fn format_card_options<'a>(
+    card: &Card,
+    options: impl IntoIterator<Item = &'a String>,
+) -> Option<String> {
+    let mut formatted = String::new();
+    for option in options {
+        if !formatted.is_empty() {
+            formatted.push_str(", ");
+        }
+        if !card.synonyms.contains(option) {
+            formatted.push_str(&format_option(option)?);
+        } else if crate::set::is_plain_synonyms(option) {
+            formatted.push_str(option);
+        } else {
+            return None;
+        }
+    }
+    (!formatted.is_empty()).then_some(formatted)
+}
+
+/// Format a whole set file with the given title, metadata and cards, in the order given.
+///
+/// Returns `None` if the title or metadata is not valid or any card cannot be
+/// [formatted](format_card). A title is valid if it is not empty, does not start or end with
+/// whitespace and does not contain `#` or control characters. Metadata values are valid under the
+/// same rules, except that they can contain `#`.
+#[must_use]
+pub fn format_set<'a>(
+    title: &str,
+    metadata: &SetMetadata,
+    cards: impl IntoIterator<Item = &'a Card>,
+) -> Option<String> {
+    if !is_valid_option(title) || title.contains('#') {
+        return None;
+    }
+    let mut source = format!("{title}\n");
+    source.push_str(&format_metadata(metadata)?);
+    source.push('\n');
+    for card in cards {
+        source.push_str(&format_card(card)?);
+        source.push('\n');
+    }
+    Some(source)
+}
+
+/// Format the metadata of a set as the `#!` lines that follow its title, or `None` if the author
+/// or language is not a [valid option](is_valid_option).
+fn format_metadata(metadata: &SetMetadata) -> Option<String> {
+    let mut header = String::new();
+    if let Some(author) = &metadata.author {
+        if !is_valid_option(author) {
+            return None;
+        }
+        writeln!(header, "#! author: {author}").unwrap();
+    }
+    if let Some(language) = &metadata.language {
+        if !is_valid_option(language) {
+            return None;
+        }
+        writeln!(header, "#! language: {language}").unwrap();
+    }
+    if let Some(version) = metadata.version {
+        writeln!(header, "#! version: {version}").unwrap();
+    }
+    if metadata.optional_parentheses {
+        header.push_str("#! parentheses: optional\n");
+    }
+    Some(header)
+}
+
+/// Reformat the source of a set file, writing its title and cards as [`format_set`] would but
+/// keeping its comments, metadata and blank lines. Line endings are kept as CRLF if the source uses
+/// them.
+///
+/// # Errors
+///
+/// Fails with the errors in the set if it is not valid, since only valid sets can be formatted.
+pub fn format_source(source: &str) -> Result<String, Vec<ParseError>> {
+    let set = parse_set_borrowed(source)?;
+    let title = set.title;
+    let cards = set
+        .cards
+        .into_iter()
+        .map(|card| (card.line, card.into_owned()))
+        .collect::<HashMap<_, _>>();
+    let newline = if source.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut formatted = String::with_capacity(source.len());
+    let mut before_title = true;
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim();
+        let (content, comment) = if let Some(card) = cards.get(&(i + 1)) {
+            let content = format_card(card).expect("parsed cards can be formatted");
+            (Cow::Owned(content), crate::set::card_comment(line))
+        } else if before_title && !trimmed.is_empty() && !trimmed.starts_with('#') {
+            before_title = false;
+            (Cow::Borrowed(title), line.find('#').map(|i| &line[i..]))
+        } else {
+            (Cow::Borrowed(""), Some(trimmed).filter(|s| !s.is_empty()))
+        };
+
+        formatted.push_str(&content);
+        if let Some(comment) = comment {
+            if !content.is_empty() {
+                formatted.push(' ');
+            }
+            formatted.push_str(comment.trim_end());
+        }
+        formatted.push_str(newline);
+    }
+    Ok(formatted)
+}
+
+#[test]
+fn test_format() {
+    use maplit::btreeset;
+
+    assert_eq!(
+        format_option("bonjour le monde").unwrap(),
+        "bonjour le monde"
+    );
+    assert_eq!(format_option("a, b").unwrap(), r#""a, b""#);
+    assert_eq!(format_option(r#"say "\hi""#).unwrap(), r#""say \"\\hi\"""#);
+    assert_eq!(format_option("a  b").unwrap(), r#""a  b""#);
+    assert_eq!(format_option(""), None);
+    assert_eq!(format_option(" a"), None);
+    assert_eq!(format_option("a\tb"), None);
+
+    let card = Card {
+        terms: btreeset!("x".to_owned(), "-y".to_owned()),
+        definitions: btreeset!("z".to_owned()),
+        synonyms: btreeset!(),
+        steps: None,
+        table: None,
+        priority: 3,
+        line: 1,
+    };
+    assert_eq!(format_card(&card).unwrap(), r#""-y", x - z !3"#);
+    let steps = Card {
+        definitions: btreeset!("a".to_owned(), "b".to_owned()),
+        steps: Some(vec!["b".to_owned(), "a".to_owned()]),
+        ..card.clone()
+    };
+    assert_eq!(format_card(&steps).unwrap(), r#""-y", x -> b, a !3"#);
+    let table = Card {
+        definitions: btreeset!("b".to_owned()),
+        table: Some("a | *b".parse().unwrap()),
+        ..card.clone()
+    };
+    assert_eq!(format_card(&table).unwrap(), r#""-y", x -| "a | *b" !3"#);
+    let synonyms = Card {
+        definitions: btreeset!("a/b".to_owned(), "c/d".to_owned()),
+        synonyms: btreeset!("a/b".to_owned()),
+        ..card.clone()
+    };
+    assert_eq!(
+        format_card(&synonyms).unwrap(),
+        r#""-y", x - a/b, "c/d" !3"#
+    );
+    let unwritable = Card {
+        definitions: btreeset!("a, b/c".to_owned()),
+        synonyms: btreeset!("a, b/c".to_owned()),
+        ..card.clone()
+    };
+    assert_eq!(format_card(&unwritable), None);
+    let metadata = SetMetadata::default();
+    assert_eq!(
+        format_set("Title", &metadata, [&card]).unwrap(),
+        "Title\n\n\"-y\", x - z !3\n"
+    );
+    assert_eq!(format_set("#", &metadata, [&card]), None);
+    let metadata = SetMetadata {
+        author: Some("A # B".to_owned()),
+        version: Some(2),
+        optional_parentheses: true,
+        ..SetMetadata::default()
+    };
+    assert_eq!(
+        format_set("Title", &metadata, [&card]).unwrap(),
+        "Title\n#! author: A # B\n#! version: 2\n#! parentheses: optional\n\n\"-y\", x - z !3\n"
+    );
+    let metadata = SetMetadata {
+        language: Some(" fr".to_owned()),
+        ..SetMetadata::default()
+    };
+    assert_eq!(format_set("Title", &metadata, [&card]), None);
+
+    assert_eq!(
+        format_source(
+            "\n  A set   # the title\n#! author: me\n\n  # About colours\nred,rouge -  \"a #\"  !2#x  \n\nblue - bleu\n",
+        )
+        .unwrap(),
+        "\nA set # the title\n#! author: me\n\n# About colours\nred, rouge - \"a #\" !2 #x\n\nblue - bleu\n"
+    );
+    assert_eq!(
+        format_source("T\r\n\r\na-b").unwrap_err(),
+        [ParseError::NoDefinitions { card: 5..8 }]
+    );
+    assert_eq!(
+        format_source("T\r\n\r\na - b").unwrap(),
+        "T\r\n\r\na - b\r\n"
+    );
+}
+
+#[cfg(test)]
+mod round_trip {
+    use std::collections::BTreeSet;
+
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom as _;
+    use rand::{Rng as _, SeedableRng as _};
+
+    use crate::{format_options, format_set, parse_guess, parse_set, Card, SetMetadata, Table};
+
+    const ALPHABET: &[char] = &[
+        'a', 'b', 'Z', 'é', '7', ' ', ' ', '\u{a0}', ',', '-', '"', '\\', '#', '!', '\'', '(', ':',
+    ];
+
+    fn option(rng: &mut StdRng) -> String {
+        loop {
+            let len = rng.gen_range(1..8);
+            let option = (0..len)
+                .map(|_| *ALPHABET.choose(rng).unwrap())
+                .collect::<String>();
+            if crate::is_valid_option(&option) {
+                return option;
+            }
+        }
+    }
+
+    fn options(rng: &mut StdRng) -> BTreeSet<String> {
+        (0..rng.gen_range(1..4)).map(|_| option(rng)).collect()
+    }
+
+    fn table(rng: &mut StdRng) -> Table {
+        let columns = rng.gen_range(1..4);
+        let rows = (0..rng.gen_range(1..4))
+            .map(|_| {
+                (0..columns)
+                    .map(|_| option(rng).replace(['|', ';', '*'], "x"))
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+        let answer = rng
+            .gen_bool(0.5)
+            .then(|| (rng.gen_range(0..rows.len()), rng.gen_range(0..columns)));
+        Table { rows, answer }
+    }
+
+    #[test]
+    fn guesses() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..2000 {
+            let options = options(&mut rng);
+            let formatted = format_options(&options).unwrap();
+            assert_eq!(parse_guess(&formatted), options, "{formatted}");
+        }
+    }
+
+    #[test]
+    fn sets() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..200 {
+            let mut cards = Vec::<Card>::new();
+            for line in 3..rng.gen_range(4..10) {
+                let mut definitions = options(&mut rng);
+                let steps = rng.gen_bool(0.2).then(|| {
+                    let mut steps = definitions.iter().cloned().collect::<Vec<_>>();
+                    steps.shuffle(&mut rng);
+                    steps
+                });
+                let table = (steps.is_none() && rng.gen_bool(0.2)).then(|| table(&mut rng));
+                let mut synonyms = BTreeSet::new();
+                if let Some(table) = &table {
+                    definitions = table.answers().into_iter().collect();
+                } else if steps.is_none() && rng.gen_bool(0.2) {
+                    let option = format!("{}/Zé b", rng.gen_range(0..100));
+                    definitions.insert(option.clone());
+                    synonyms.insert(option);
+                }
+                let card = Card {
+                    terms: options(&mut rng),
+                    definitions,
+                    synonyms,
+                    steps,
+                    table,
+                    priority: *[1, 1, 2, 10].choose(&mut rng).unwrap(),
+                    line,
+                };
+                if !cards.contains(&card) {
+                    cards.push(card);
+                }
+            }
+
+            let metadata = SetMetadata {
+                author: rng.gen_bool(0.5).then(|| option(&mut rng)),
+                language: rng.gen_bool(0.5).then(|| option(&mut rng)),
+                version: rng.gen_bool(0.5).then(|| rng.gen()),
+                optional_parentheses: rng.gen(),
+            };
+
+            let source = format_set("A set", &metadata, &cards).unwrap();
+            let set = parse_set(&source).unwrap_or_else(|e| panic!("{source}\n{e:?}"));
+            assert_eq!(set.metadata, metadata, "{source}");
+            assert_eq!(set.cards, cards.into_iter().collect(), "{source}");
+        }
+    }
+
+    /// Arbitrary input must never make the parsers panic, and any set that parses must survive
+    /// being formatted and parsed again.
+    #[test]
+    fn arbitrary() {
+        const ALPHABET: &[char] = &[
+            'a', 'b', '7', ' ', '\t', '\n', '\r', '\u{a0}', ',', '-', '"', '\\', '#', '!', ':',
+            '(', ')', '/', '=', '0', '>', '|', ';', '*',
+        ];
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..5000 {
+            let len = rng.gen_range(0..40);
+            let mut source = (0..len)
+                .map(|_| *ALPHABET.choose(&mut rng).unwrap())
+                .collect::<String>();
+            if rng.gen_bool(0.5) {
+                source.insert_str(0, "Title\n\n");
+            }
+
+            let _ = parse_guess(&source);
+            let Ok(set) = parse_set(&source) else {
+                continue;
+            };
+            let Some(formatted) = format_set(&set.title, &set.metadata, &set.cards) else {
+                continue;
+            };
+            let reparsed = parse_set(&formatted).unwrap_or_else(|e| panic!("{formatted}\n{e:?}"));
+            assert_eq!(reparsed, set, "{source:?}");
+        }
+    }
+}
@@ -4,6 +4,37 @@ use std::collections::BTreeSet;
 #[allow(clippy::module_name_repetitions)]
 #[must_use]
 pub fn parse_guess(input: &str) -> BTreeSet<String> {
+    parse_guess_steps(input).into_iter().collect()
+}
+
+/// Parse options written as they would be in a set file, like [`parse_guess`], also returning
+/// which of them are written unquoted with `/` between spellings, which a set file treats as
+/// [synonyms](crate::Card::synonyms).
+#[must_use]
+pub fn parse_guess_synonyms(input: &str) -> (BTreeSet<String>, BTreeSet<String>) {
+    let mut options = BTreeSet::new();
+    let mut synonyms = BTreeSet::new();
+    for (option, has_synonyms) in parse_all(input) {
+        if has_synonyms {
+            synonyms.insert(option.clone());
+        }
+        options.insert(option);
+    }
+    (options, synonyms)
+}
+
+/// Parse a guess for the steps of a card, in the order they are given. A step given more than
+/// once is only kept the first time.
+#[must_use]
+pub fn parse_guess_steps(input: &str) -> Vec<String> {
+    parse_all(input)
+        .into_iter()
+        .map(|(option, _)| option)
+        .collect()
+}
+
+/// Parse every option in the input, along with whether it has synonyms.
+fn parse_all(input: &str) -> Vec<(String, bool)> {
     let mut cx = ParseContext { remaining: input };
 
     let guess = parse_guess_inner(&mut cx);
@@ -37,15 +68,21 @@ impl ParseContext<'_> {
 
 struct NoMatch;
 
-fn parse_guess_inner(cx: &mut ParseContext<'_>) -> BTreeSet<String> {
-    let mut options = BTreeSet::new();
+fn parse_guess_inner(cx: &mut ParseContext<'_>) -> Vec<(String, bool)> {
+    let mut options = Vec::<(String, bool)>::new();
 
     loop {
         while parse_whitespace(cx).is_ok() {}
 
+        let quoted = cx.remaining.starts_with('"');
         if let Ok(option) = parse_option(cx) {
-            if !option.is_empty() {
-                options.insert(option);
+            if !option.is_empty() && options.iter().all(|(existing, _)| *existing != option) {
+                let has_synonyms = !quoted
+                    && option.contains('/')
+                    && option
+                        .split('/')
+                        .all(|spelling| !spelling.trim().is_empty());
+                options.push((option, has_synonyms));
             }
             while parse_whitespace(cx).is_ok() {}
         }
@@ -83,7 +120,12 @@ fn parse_option(cx: &mut ParseContext<'_>) -> Result<String, NoMatch> {
     Ok(value)
 }
 
+/// Parse one character of an unquoted option, where `\,` is a comma that doesn't separate options.
 fn parse_option_atom(cx: &mut ParseContext<'_>) -> Result<char, NoMatch> {
+    if let Some(rest) = cx.remaining.strip_prefix("\\,") {
+        cx.remaining = rest;
+        return Ok(',');
+    }
     cx.try_parse(|cx| {
         parse_any(cx)
             .ok()
@@ -157,4 +199,16 @@ fn test() {
     assert_eq!(parse_guess(" - - , -- -- "), guess!("- -", "-- --"));
     assert_eq!(parse_guess("a\",b\"\""), guess!("a\"", "b\"\""));
     assert_eq!(parse_guess("\"m\"x,"), guess!("mx"));
+    assert_eq!(parse_guess("1\\,000, b"), guess!("1,000", "b"));
+    assert_eq!(parse_guess("a\\ \\\\,"), guess!("a\\ \\,"));
+
+    assert_eq!(parse_guess_steps("c, a,b, a"), ["c", "a", "b"]);
+
+    assert_eq!(
+        parse_guess_synonyms("colour / color, \"a/b\", c/, d"),
+        (
+            guess!("a/b", "c/", "colour / color", "d"),
+            guess!("colour / color"),
+        )
+    );
 }
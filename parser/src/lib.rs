@@ -8,3 +8,11 @@ pub use set::*;
 
 mod guess;
 pub use guess::*;
+
+mod format;
+pub use format::*;
+
+mod algebra;
+
+mod position;
+pub use position::*;
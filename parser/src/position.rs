@@ -0,0 +1,50 @@
+use std::fmt::{self, Display, Formatter};
+
+/// A position in a source file as a line and column, for showing to users and editors instead of
+/// a byte offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    /// The line, starting from 1. Lines end in a line feed, optionally preceded by a carriage
+    /// return.
+    pub line: usize,
+    /// The column, starting from 1 and counted in characters.
+    pub column: usize,
+}
+
+impl LineCol {
+    /// Find the line and column of a byte offset into the source, such as the start of a
+    /// [`ParseError`](crate::ParseError) span.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset is past the end of the source or not on a character boundary.
+    #[must_use]
+    pub fn of(source: &str, offset: usize) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Display for LineCol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[test]
+fn test_line_col() {
+    let at = |line, column| LineCol { line, column };
+    let source = "Title\r\n\néte - summer\n";
+
+    assert_eq!(LineCol::of(source, 0), at(1, 1));
+    assert_eq!(LineCol::of(source, 5), at(1, 6));
+    assert_eq!(LineCol::of(source, 7), at(2, 1));
+    assert_eq!(LineCol::of(source, 8), at(3, 1));
+    assert_eq!(LineCol::of(source, 14), at(3, 6));
+    assert_eq!(LineCol::of(source, source.len()), at(4, 1));
+    assert_eq!(at(3, 6).to_string(), "3:6");
+}
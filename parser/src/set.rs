@@ -1,4 +1,8 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::hash_map::{Entry, RandomState};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::hash::{BuildHasher as _, Hash, Hasher};
 use std::ops::Range;
 use std::str;
 
@@ -9,11 +13,46 @@ use std::str;
 /// Fails with a list of all the errors if the set is not a valid set file.
 #[allow(clippy::module_name_repetitions)]
 pub fn parse_set(input: &str) -> Result<Set, Vec<ParseError>> {
+    parse_set_borrowed(input).map(BorrowedSet::into_owned)
+}
+
+/// Parse a `.set` file without copying the title and options out of it, unless an option contains
+/// escapes. This is cheaper than [`parse_set`] when the set is only needed while the source is.
+///
+/// # Errors
+///
+/// Fails with a list of all the errors if the set is not a valid set file.
+pub fn parse_set_borrowed(input: &str) -> Result<BorrowedSet<'_>, Vec<ParseError>> {
+    parse_set_warned(input, &mut Vec::new())
+}
+
+/// Parse a `.set` file like [`parse_set`], also giving the warnings about it. The warnings are
+/// given even if the set has errors.
+pub fn parse_set_with_warnings(input: &str) -> (Result<Set, Vec<ParseError>>, Vec<ParseWarning>) {
+    let (set, warnings) = parse_set_borrowed_with_warnings(input);
+    (set.map(BorrowedSet::into_owned), warnings)
+}
+
+/// Parse a `.set` file like [`parse_set_borrowed`], also giving the warnings about it. The
+/// warnings are given even if the set has errors.
+pub fn parse_set_borrowed_with_warnings(
+    input: &str,
+) -> (Result<BorrowedSet<'_>, Vec<ParseError>>, Vec<ParseWarning>) {
+    let mut warnings = Vec::new();
+    let set = parse_set_warned(input, &mut warnings);
+    (set, warnings)
+}
+
+fn parse_set_warned<'a>(
+    input: &'a str,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<BorrowedSet<'a>, Vec<ParseError>> {
     let mut errors = Vec::new();
     let mut cx = ParseContext {
         source: input,
         remaining: input,
         errors: &mut errors,
+        warnings,
     };
 
     let set = parse_set_inner(&mut cx);
@@ -31,30 +70,186 @@ pub fn parse_set(input: &str) -> Result<Set, Vec<ParseError>> {
     }
 }
 
+/// Parse a `.set` file one card at a time, yielding each card and error as soon as the line it is
+/// on has been parsed rather than once the whole set has been.
+///
+/// The set is valid exactly when no errors are yielded, in which case the cards yielded are the
+/// ones [`parse_set`] would give. Cards that have errors of their own, including duplicate cards,
+/// are not yielded.
+#[must_use]
+pub fn parse_set_iter(input: &str) -> SetIter<'_> {
+    let mut errors = Vec::new();
+    let mut cx = ParseContext {
+        source: input,
+        remaining: input,
+        errors: &mut errors,
+        warnings: &mut Vec::new(),
+    };
+
+    let state = parse_set_start(&mut cx);
+    let remaining = cx.remaining;
+
+    SetIter {
+        source: input,
+        remaining,
+        state,
+        cards: HashMap::new(),
+        queue: errors.into_iter().map(Err).collect(),
+        finished: false,
+    }
+}
+
+/// The terms and definitions of a card.
+type CardOptions<'a> = (Vec<Cow<'a, str>>, Vec<Cow<'a, str>>);
+
+/// An iterator over the cards in a set and the errors in it, created by [`parse_set_iter`].
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct SetIter<'a> {
+    source: &'a str,
+    remaining: &'a str,
+    state: SetState<'a>,
+    /// The span of every card so far, keyed by its terms and definitions to find duplicates.
+    cards: HashMap<CardOptions<'a>, Range<usize>>,
+    queue: VecDeque<Result<Card, ParseError>>,
+    finished: bool,
+}
+
+impl<'a> SetIter<'a> {
+    /// The title of the set.
+    #[must_use]
+    pub fn title(&self) -> &'a str {
+        self.state.title
+    }
+
+    /// The metadata of the set, which is only complete once the first card has been parsed.
+    #[must_use]
+    pub fn metadata(&self) -> &SetMetadata {
+        &self.state.metadata
+    }
+
+    /// The number of bytes of the source that have been parsed so far, for showing progress.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.source.len() - self.remaining.len()
+    }
+}
+
+impl Iterator for SetIter<'_> {
+    type Item = Result<Card, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.queue.is_empty() && !self.finished {
+            let mut errors = Vec::new();
+            let mut cx = ParseContext {
+                source: self.source,
+                remaining: self.remaining,
+                errors: &mut errors,
+                warnings: &mut Vec::new(),
+            };
+
+            let mut card = None;
+            match parse_set_line(&mut cx, &mut self.state, !self.cards.is_empty()) {
+                Ok(Some((parsed, span))) => {
+                    match self
+                        .cards
+                        .entry((parsed.terms.clone(), parsed.definitions.clone()))
+                    {
+                        Entry::Occupied(entry) => cx.errors.push(ParseError::DuplicateCard {
+                            original: entry.get().clone(),
+                            duplicate: span,
+                        }),
+                        Entry::Vacant(entry) => {
+                            entry.insert(span);
+                            card = Some(parsed);
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(NoMatch) => {
+                    assert!(
+                        cx.remaining.is_empty(),
+                        "Trailing characters: {:?}",
+                        cx.remaining
+                    );
+                    if self.cards.is_empty() {
+                        cx.errors.push(ParseError::EmptySet);
+                    }
+                    self.finished = true;
+                }
+            }
+            self.remaining = cx.remaining;
+
+            let card = card.filter(|_| errors.is_empty());
+            self.queue.extend(errors.into_iter().map(Err));
+            self.queue.extend(card.map(|card| Ok(card.into_owned())));
+        }
+
+        self.queue.pop_front()
+    }
+}
+
+#[test]
+fn test_parse_set_iter() {
+    for input in [
+        "title\na,b - c\n\na,b - c",
+        "\n  title # comment\n#! author: me\na - b !2\n# c\nc - d\n#! version: 1",
+        "x\n\r\n\n\n  \n\r\n",
+        "x\na - b\nb - a\nc -\na - b, a, b\n- d",
+        "",
+    ] {
+        let mut iter = parse_set_iter(input);
+        let (mut cards, mut errors) = (HashSet::new(), Vec::new());
+        for item in iter.by_ref() {
+            match item {
+                Ok(card) => assert!(cards.insert(card)),
+                Err(error) => errors.push(error),
+            }
+        }
+        assert_eq!(iter.offset(), input.len());
+
+        match parse_set(input) {
+            Ok(set) => {
+                assert_eq!(errors, []);
+                assert_eq!(iter.title(), set.title);
+                assert_eq!(*iter.metadata(), set.metadata);
+                assert_eq!(cards, set.cards);
+                for card in &cards {
+                    assert_eq!(set.cards.get(card).unwrap().line, card.line);
+                }
+            }
+            Err(expected) => assert_eq!(errors, expected),
+        }
+    }
+}
+
 struct ParseContext<'a, 'e> {
     source: &'a str,
+    /// The unparsed end of `source`. Parsers only ever move its start forwards or back, so it is
+    /// always a suffix.
     remaining: &'a str,
     errors: &'e mut Vec<ParseError>,
+    warnings: &'e mut Vec<ParseWarning>,
 }
 
-impl<'a> ParseContext<'a, '_> {
+impl ParseContext<'_, '_> {
     fn offset(&self) -> usize {
-        let source = <*const str>::cast::<*const ()>(self.source) as usize;
-        let s = <*const str>::cast::<*const ()>(self.remaining) as usize;
-        let offset = s.checked_sub(source).unwrap();
-        assert!(offset <= self.source.len());
+        let offset = self.source.len() - self.remaining.len();
+        debug_assert_eq!(self.source[offset..].as_ptr(), self.remaining.as_ptr());
         offset
     }
     fn try_parse<R, F>(&mut self, f: F) -> Result<R, NoMatch>
     where
-        F: FnOnce(&mut ParseContext<'_, '_>) -> Result<R, NoMatch>,
+        F: FnOnce(&mut Self) -> Result<R, NoMatch>,
     {
         let prev_remaining = self.remaining;
         let prev_errors = self.errors.len();
+        let prev_warnings = self.warnings.len();
         let res = f(self);
         if res.is_err() {
             self.remaining = prev_remaining;
             self.errors.truncate(prev_errors);
+            self.warnings.truncate(prev_warnings);
         }
         res
     }
@@ -62,7 +257,76 @@ impl<'a> ParseContext<'a, '_> {
 
 struct NoMatch;
 
-fn parse_set_inner(cx: &mut ParseContext<'_, '_>) -> Set {
+fn parse_set_inner<'a>(cx: &mut ParseContext<'a, '_>) -> BorrowedSet<'a> {
+    let mut state = parse_set_start(cx);
+    // Reserving space for a card on every line is cheaper than growing the lists as they fill.
+    let lines = cx.remaining.bytes().filter(|&b| b == b'\n').count();
+    let mut cards = Vec::<BorrowedCard<'a>>::with_capacity(lines);
+    let mut spans = Vec::<Range<usize>>::with_capacity(lines);
+
+    // Cards are keyed by their terms and definitions alone, since two cards that differ only in
+    // priority are still duplicates. Only the hash of each key is stored, which is much cheaper
+    // than storing the key itself, so the few cards whose hashes collide are kept in a list.
+    let hasher = RandomState::new();
+    let mut by_hash = HashMap::<u64, usize>::with_capacity(lines);
+    let mut collided = Vec::<usize>::new();
+
+    while let Ok(card) = parse_set_line(cx, &mut state, !cards.is_empty()) {
+        let Some((card, span)) = card else {
+            continue;
+        };
+        let is_same =
+            |&i: &usize| cards[i].terms == card.terms && cards[i].definitions == card.definitions;
+        let original = match by_hash.entry(hasher.hash_one((&card.terms, &card.definitions))) {
+            Entry::Occupied(entry) => {
+                let original = Some(*entry.get())
+                    .filter(is_same)
+                    .or_else(|| collided.iter().copied().find(is_same));
+                if original.is_none() {
+                    collided.push(cards.len());
+                }
+                original
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(cards.len());
+                None
+            }
+        };
+        if let Some(original) = original {
+            cx.errors.push(ParseError::DuplicateCard {
+                original: spans[original].clone(),
+                duplicate: span,
+            });
+        } else {
+            cards.push(card);
+            spans.push(span);
+        }
+    }
+
+    if cards.is_empty() {
+        cx.errors.push(ParseError::EmptySet);
+    }
+
+    BorrowedSet {
+        title: state.title,
+        metadata: state.metadata,
+        cards,
+    }
+}
+
+/// What a set parser keeps track of between the lines of a set.
+#[derive(Debug)]
+struct SetState<'a> {
+    title: &'a str,
+    line: usize,
+    metadata: SetMetadata,
+    metadata_keys: HashMap<String, Range<usize>>,
+}
+
+/// Parse the blank lines before the title of a set, and the title itself.
+fn parse_set_start<'a>(cx: &mut ParseContext<'a, '_>) -> SetState<'a> {
+    let mut line = 1;
+
     loop {
         let res = cx.try_parse(|cx| {
             parse_blank_line(cx);
@@ -72,35 +336,37 @@ fn parse_set_inner(cx: &mut ParseContext<'_, '_>) -> Set {
         if res.is_err() {
             break;
         }
+        line += 1;
     }
 
-    let title = parse_title(cx);
-
-    let mut cards = HashMap::new();
-
-    while parse_newline(cx).is_ok() {
-        let card_start = cx.offset();
-        if let Ok(card) = parse_card(cx) {
-            if let Some(original) = cards.get(&card).cloned() {
-                cx.errors.push(ParseError::DuplicateCard {
-                    original,
-                    duplicate: card_start..cx.offset(),
-                });
-            } else {
-                cards.insert(card, card_start..cx.offset());
-            }
-        } else {
-            parse_blank_line(cx);
-        }
+    SetState {
+        title: parse_title(cx),
+        line,
+        metadata: SetMetadata::default(),
+        metadata_keys: HashMap::new(),
     }
+}
 
-    if cards.is_empty() {
-        cx.errors.push(ParseError::EmptySet);
-    }
+/// Parse the next line of a set after its title, giving the card on it and the card's span if
+/// there is one. Fails at the end of the set. Metadata is only allowed before the first card.
+fn parse_set_line<'a>(
+    cx: &mut ParseContext<'a, '_>,
+    state: &mut SetState<'_>,
+    after_card: bool,
+) -> Result<Option<(BorrowedCard<'a>, Range<usize>)>, NoMatch> {
+    parse_newline(cx)?;
+    state.line += 1;
 
-    Set {
-        title,
-        cards: cards.into_keys().collect(),
+    let card_start = cx.offset();
+    if !after_card && parse_metadata(cx, &mut state.metadata, &mut state.metadata_keys).is_ok() {
+        return Ok(None);
+    }
+    if let Ok(mut card) = parse_card(cx) {
+        card.line = state.line;
+        Ok(Some((card, card_start..cx.offset())))
+    } else {
+        parse_blank_line(cx);
+        Ok(None)
     }
 }
 
@@ -111,7 +377,7 @@ fn test_parse_set() {
     let parse = |input| {
         let (set, remaining, errors) = run_parser(|cx| Ok(parse_set_inner(cx)), input).unwrap();
         assert_eq!(remaining, "");
-        (set, errors)
+        (set.into_owned(), errors)
     };
 
     assert_eq!(
@@ -119,16 +385,22 @@ fn test_parse_set() {
         (
             Set {
                 title: "title".to_owned(),
+                metadata: SetMetadata::default(),
                 cards: hashset!(card!("a", "b" - "c")),
             },
             vec![duplicate_card(6..13, 15..22)]
         )
     );
+    assert_eq!(
+        parse("title\na - b !2\na - b").1,
+        vec![duplicate_card(6..14, 15..20)]
+    );
     assert_eq!(
         parse(" -- \r\n\r\n \" , - , \" - \" , - , \" "),
         (
             Set {
                 title: "--".to_owned(),
+                metadata: SetMetadata::default(),
                 cards: hashset!(card!(" , - , " - " , - , ")),
             },
             vec![],
@@ -139,11 +411,70 @@ fn test_parse_set() {
         (
             Set {
                 title: "x".to_owned(),
+                metadata: SetMetadata::default(),
                 cards: hashset!(),
             },
             vec![empty_set()],
         )
     );
+    assert_eq!(
+        parse("t\n#! author: me\n\n#!language:fr\na - b\n#! version: 2"),
+        (
+            Set {
+                title: "t".to_owned(),
+                metadata: SetMetadata {
+                    author: Some("me".to_owned()),
+                    language: Some("fr".to_owned()),
+                    version: None,
+                    optional_parentheses: false,
+                },
+                cards: hashset!(card!("a" - "b")),
+            },
+            vec![],
+        )
+    );
+    assert_eq!(
+        parse("t\n#!author:a\n#! author:b\nx - y").1,
+        vec![duplicate_metadata_key(4..10, 16..22)],
+    );
+
+    let lines = |input| {
+        let mut lines = parse(input)
+            .0
+            .cards
+            .into_iter()
+            .map(|card| card.line)
+            .collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines
+    };
+    assert_eq!(lines("t\na - b\nc - d"), [2, 3]);
+    assert_eq!(
+        lines("\n\r\n  \nt\n#! version: 1\n\na - b # x\n\nc - d\n"),
+        [7, 9]
+    );
+}
+
+#[test]
+fn test_parse_set_borrowed() {
+    let source = "Title\n\nb - \"x\\\"y\"\na, \"c\" - z !2\n";
+    let set = parse_set_borrowed(source).unwrap();
+    assert_eq!(set.title, "Title");
+
+    let [first, second] = &set.cards[..] else {
+        panic!("{:?}", set.cards);
+    };
+    assert_eq!(first.terms, ["b"]);
+    assert_eq!(first.line, 3);
+    assert!(matches!(&first.definitions[..], [Cow::Owned(d)] if d == "x\"y"));
+    assert!(matches!(
+        &second.terms[..],
+        [Cow::Borrowed("a"), Cow::Borrowed("c")]
+    ));
+    assert_eq!(second.priority, 2);
+
+    assert_eq!(set.into_owned(), parse_set(source).unwrap());
+    assert!(parse_set_borrowed("Title\n").is_err());
 }
 
 fn parse_blank_line(cx: &mut ParseContext<'_, '_>) {
@@ -151,7 +482,7 @@ fn parse_blank_line(cx: &mut ParseContext<'_, '_>) {
     parse_comment(cx);
 }
 
-fn parse_title(cx: &mut ParseContext<'_, '_>) -> String {
+fn parse_title<'a>(cx: &mut ParseContext<'a, '_>) -> &'a str {
     let title_line_start = cx.offset();
 
     let parse_title_char = |cx: &mut ParseContext<'_, '_>| {
@@ -163,13 +494,9 @@ fn parse_title(cx: &mut ParseContext<'_, '_>) -> String {
         })
     };
 
-    let mut title = String::new();
-
-    while let Ok(c) = parse_title_char(cx) {
-        title.push(c);
-    }
+    while parse_title_char(cx).is_ok() {}
 
-    let title = title.trim().to_owned();
+    let title = cx.source[title_line_start..cx.offset()].trim();
 
     if title.is_empty() {
         cx.errors.push(ParseError::NoTitle {
@@ -185,7 +512,10 @@ fn parse_title(cx: &mut ParseContext<'_, '_>) -> String {
 #[test]
 #[rustfmt::skip]
 fn test_parse_title() {
-    let parse = |input| run_parser(|cx| Ok(parse_title(cx)), input).unwrap();
+    let parse = |input| {
+        let (title, rest, errors) = run_parser(|cx| Ok(parse_title(cx)), input).unwrap();
+        (title.to_owned(), rest, errors)
+    };
 
     assert_eq!(parse(""), (String::new(), "", vec![no_title(0..0)]));
     assert_eq!(parse(" "), (String::new(), "", vec![no_title(0..1)]));
@@ -195,15 +525,216 @@ fn test_parse_title() {
     assert_eq!(parse("   title  "), ("title".into(), "", vec![]));
 }
 
-fn parse_card(cx: &mut ParseContext<'_, '_>) -> Result<Card, NoMatch> {
+fn parse_metadata(
+    cx: &mut ParseContext<'_, '_>,
+    metadata: &mut SetMetadata,
+    keys: &mut HashMap<String, Range<usize>>,
+) -> Result<(), NoMatch> {
+    // A `#!` line without a key and a colon is an ordinary comment, as it was before metadata.
+    let source = cx.source;
+    let (entry_start, key, value) = cx.try_parse(|cx| {
+        while parse_ws(cx).is_ok() {}
+        parse_exact_char(cx, '#')?;
+        parse_exact_char(cx, '!')?;
+        let entry_start = cx.offset();
+        while parse_character(cx).is_ok() {}
+        let (key, value) = source[entry_start..cx.offset()]
+            .split_once(':')
+            .filter(|(key, _)| !key.trim().is_empty())
+            .ok_or(NoMatch)?;
+        Ok((entry_start, key, value))
+    })?;
+
+    let key_span = trimmed_span(key, entry_start);
+    let value_span = trimmed_span(value, entry_start + key.len() + ':'.len_utf8());
+    let (key, value) = (key.trim(), value.trim());
+
+    // Unknown keys are most likely comments that happen to contain a colon, but could be typos.
+    if !matches!(key, "author" | "language" | "version" | "parentheses") {
+        cx.warnings.push(ParseWarning::UnknownMetadataKey {
+            key: key.to_owned(),
+            span: key_span,
+        });
+        return Ok(());
+    }
+    if value.is_empty() {
+        cx.errors.push(ParseError::MalformedMetadata {
+            span: entry_start..cx.offset(),
+        });
+        return Ok(());
+    }
+
+    if let Some(original) = keys.get(key) {
+        cx.errors.push(ParseError::DuplicateMetadataKey {
+            original: original.clone(),
+            duplicate: key_span,
+        });
+        return Ok(());
+    }
+    keys.insert(key.to_owned(), key_span.clone());
+
+    match key {
+        "author" => metadata.author = Some(value.to_owned()),
+        "language" => metadata.language = Some(value.to_owned()),
+        "version" => match value.parse() {
+            Ok(version) => metadata.version = Some(version),
+            Err(_) => cx
+                .errors
+                .push(ParseError::InvalidMetadataValue { span: value_span }),
+        },
+        "parentheses" => match value {
+            "optional" => metadata.optional_parentheses = true,
+            "required" => metadata.optional_parentheses = false,
+            _ => cx
+                .errors
+                .push(ParseError::InvalidMetadataValue { span: value_span }),
+        },
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+/// Whether an option can be written in a set file as it is, without quotes, and be parsed back to
+/// the same option with its `/`s still separating synonyms.
+pub(crate) fn is_plain_synonyms(option: &str) -> bool {
+    let mut errors = Vec::new();
+    let mut cx = ParseContext {
+        source: option,
+        remaining: option,
+        errors: &mut errors,
+        warnings: &mut Vec::new(),
+    };
+    let parsed = parse_option(&mut cx).ok();
+    let rest = cx.remaining;
+    // An option ending in a backslash would escape the comma written after it.
+    !option.starts_with('"')
+        && !option.ends_with('\\')
+        && parsed.as_deref() == Some(option)
+        && rest.is_empty()
+        && errors.is_empty()
+        && option
+            .split('/')
+            .all(|spelling| !spelling.trim().is_empty())
+}
+
+fn trimmed_span(s: &str, offset: usize) -> Range<usize> {
+    let start = offset + (s.len() - s.trim_start().len());
+    start..start + s.trim().len()
+}
+
+#[test]
+fn test_parse_metadata() {
+    let parse = |input| {
+        let mut metadata = SetMetadata::default();
+        let mut keys = HashMap::new();
+        run_parser(|cx| parse_metadata(cx, &mut metadata, &mut keys), input)
+            .map(|((), rest, errors)| (metadata, rest, errors))
+    };
+    let author = |author: &str| SetMetadata {
+        author: Some(author.to_owned()),
+        ..SetMetadata::default()
+    };
+
+    assert_eq!(parse(""), None);
+    assert_eq!(parse("# comment"), None);
+    assert_eq!(parse("a - b"), None);
+    assert_eq!(parse("#! author: A B "), Some((author("A B"), "", vec![])));
+    assert_eq!(
+        parse(" #!author:x#y\n"),
+        Some((author("x#y"), "\n", vec![]))
+    );
+    assert_eq!(
+        parse("#! version: 12"),
+        Some((
+            SetMetadata {
+                version: Some(12),
+                ..SetMetadata::default()
+            },
+            "",
+            vec![]
+        ))
+    );
+    assert_eq!(
+        parse("#! version: two"),
+        Some((
+            SetMetadata::default(),
+            "",
+            vec![invalid_metadata_value(12..15)]
+        ))
+    );
+    assert_eq!(
+        parse("#! parentheses: optional"),
+        Some((
+            SetMetadata {
+                optional_parentheses: true,
+                ..SetMetadata::default()
+            },
+            "",
+            vec![]
+        ))
+    );
+    assert_eq!(
+        parse("#! parentheses: maybe"),
+        Some((
+            SetMetadata::default(),
+            "",
+            vec![invalid_metadata_value(16..21)]
+        ))
+    );
+    assert_eq!(parse("#! author"), None);
+    assert_eq!(parse("#! : x"), None);
+    assert_eq!(
+        parse("#!author: "),
+        Some((SetMetadata::default(), "", vec![malformed_metadata(2..10)]))
+    );
+    assert_eq!(
+        parse("#! colour: red"),
+        Some((SetMetadata::default(), "", vec![]))
+    );
+
+    let source = "Title\n#! TODO: more cards\n#! not metadata\n#! author: A\na - b\n";
+    let (set, warnings) = parse_set_with_warnings(source);
+    assert_eq!(set.unwrap().metadata, author("A"));
+    assert_eq!(
+        warnings,
+        [ParseWarning::UnknownMetadataKey {
+            key: "TODO".to_owned(),
+            span: 9..13
+        }]
+    );
+}
+
+fn parse_card<'a>(cx: &mut ParseContext<'a, '_>) -> Result<BorrowedCard<'a>, NoMatch> {
+    let card = parse_card_content(cx)?;
+    parse_comment(cx);
+    Ok(card)
+}
+
+/// The comment at the end of a line of a valid set file that contains a card, starting with `#`.
+pub(crate) fn card_comment(line: &str) -> Option<&str> {
+    let mut errors = Vec::new();
+    let mut cx = ParseContext {
+        source: line,
+        remaining: line,
+        errors: &mut errors,
+        warnings: &mut Vec::new(),
+    };
+    parse_card_content(&mut cx).ok()?;
+    Some(cx.remaining).filter(|rest| rest.starts_with('#'))
+}
+
+/// Parse a card up to its comment.
+fn parse_card_content<'a>(cx: &mut ParseContext<'a, '_>) -> Result<BorrowedCard<'a>, NoMatch> {
     let card_start = cx.offset();
 
     let (mut space_before_dash, mut space_after_dash) = (false, false);
+    let mut synonyms = Vec::new();
 
-    let (terms, has_dash) = cx.try_parse(|cx| {
+    let (mut terms, has_dash) = cx.try_parse(|cx| {
         let options = cx.try_parse(|cx| {
             while parse_ws(cx).is_ok() {}
-            parse_options(cx)
+            parse_options(cx, &mut synonyms)
         });
 
         while parse_ws(cx).is_ok() {
@@ -217,8 +748,11 @@ fn parse_card(cx: &mut ParseContext<'_, '_>) -> Result<Card, NoMatch> {
         Ok((options.unwrap_or_default(), has_dash))
     })?;
 
-    let definitions = if has_dash {
-        let dash_span = cx.offset() - '-'.len_utf8()..cx.offset();
+    let (mut definitions, steps, table, priority) = if has_dash {
+        let dash_start = cx.offset() - '-'.len_utf8();
+        let ordered = parse_exact_char(cx, '>').is_ok();
+        let tabular = !ordered && parse_exact_char(cx, '|').is_ok();
+        let dash_span = dash_start..cx.offset();
 
         while parse_ws(cx).is_ok() {
             space_after_dash = true;
@@ -229,33 +763,52 @@ fn parse_card(cx: &mut ParseContext<'_, '_>) -> Result<Card, NoMatch> {
                 .push(ParseError::MissingWhitespaceAroundDash { dash: dash_span });
         }
 
-        let definitions = parse_options(cx);
+        let definitions_start = cx.offset();
+        let term_synonyms = synonyms.len();
+        let definitions = parse_options(cx, &mut synonyms);
+        let definitions_span = definitions_start..cx.offset();
+        let mut priority = 1;
         if definitions.is_ok() {
             while parse_ws(cx).is_ok() {}
+            if let Ok(p) = parse_priority(cx) {
+                priority = p;
+                while parse_ws(cx).is_ok() {}
+            }
         }
 
-        let third_part_start = cx.offset();
-        if parse_exact_char(cx, '-').is_ok() {
-            while cx
-                .try_parse(|cx| {
-                    parse_character(cx)
-                        .ok()
-                        .filter(|&c| c != '#')
-                        .ok_or(NoMatch)
-                })
-                .is_ok()
-            {}
-
-            cx.errors.push(ParseError::ThirdPart {
-                before: card_start..third_part_start,
-                span: third_part_start..cx.offset(),
-            });
-        }
-
-        definitions.unwrap_or_default()
+        parse_third_part(cx, card_start);
+
+        let mut definitions = definitions.unwrap_or_default();
+        let steps = ordered.then(|| definitions.clone());
+        // Having no definitions is already an error, so it isn't a malformed table too.
+        let table = if tabular && !definitions.is_empty() {
+            let table = match &definitions[..] {
+                [table] => table.parse::<Table>().ok(),
+                _ => None,
+            };
+            match &table {
+                Some(table) => {
+                    definitions = table.answers().into_iter().map(Cow::Owned).collect();
+                    synonyms.truncate(term_synonyms);
+                }
+                None => cx.errors.push(ParseError::MalformedTable {
+                    span: definitions_span,
+                }),
+            }
+            table
+        } else {
+            None
+        };
+        (definitions, steps, table, priority)
     } else {
-        BTreeSet::new()
+        let priority = parse_priority(cx).unwrap_or(1);
+        while parse_ws(cx).is_ok() {}
+        (Vec::new(), None, None, priority)
     };
+    terms.sort_unstable();
+    definitions.sort_unstable();
+    synonyms.sort_unstable();
+    synonyms.dedup();
 
     if terms.is_empty() {
         cx.errors.push(ParseError::NoTerms {
@@ -268,14 +821,44 @@ fn parse_card(cx: &mut ParseContext<'_, '_>) -> Result<Card, NoMatch> {
         });
     }
 
-    parse_comment(cx);
+    Ok(BorrowedCard {
+        terms,
+        definitions,
+        synonyms,
+        steps,
+        table,
+        priority,
+        line: 0,
+    })
+}
 
-    Ok(Card { terms, definitions })
+/// Skip a third part of a card after another ` - `, which is an error.
+fn parse_third_part(cx: &mut ParseContext<'_, '_>, card_start: usize) {
+    let third_part_start = cx.offset();
+    if parse_exact_char(cx, '-').is_err() {
+        return;
+    }
+    while cx
+        .try_parse(|cx| {
+            parse_character(cx)
+                .ok()
+                .filter(|&c| c != '#')
+                .ok_or(NoMatch)
+        })
+        .is_ok()
+    {}
+
+    cx.errors.push(ParseError::ThirdPart {
+        before: card_start..third_part_start,
+        span: third_part_start..cx.offset(),
+    });
 }
 
 #[test]
 fn test_parse_card() {
-    let parse = |input| run_parser(parse_card, input);
+    let parse = |input| {
+        run_parser(parse_card, input).map(|(card, rest, errors)| (card.into_owned(), rest, errors))
+    };
 
     assert_eq!(parse(""), None);
     assert_eq!(parse("  "), None);
@@ -322,25 +905,291 @@ fn test_parse_card() {
         Some((card!("a" - "b"), "", vec![missing_dash_ws(1..2)])),
     );
     assert_eq!(
-        parse("a - b - c - d"),
-        Some((card!("a" - "b"), "", vec![third_part(0..6, 6..13)])),
+        parse("a - b - c - d"),
+        Some((card!("a" - "b"), "", vec![third_part(0..6, 6..13)])),
+    );
+    assert_eq!(
+        parse("t - d !3 # comment"),
+        Some((
+            Card {
+                priority: 3,
+                ..card!("t" - "d")
+            },
+            "",
+            vec![]
+        )),
+    );
+    assert_eq!(
+        parse("t - d1, d2 !12"),
+        Some((
+            Card {
+                priority: 12,
+                ..card!("t" - "d1", "d2")
+            },
+            "",
+            vec![]
+        )),
+    );
+    assert_eq!(
+        parse("t - d !0"),
+        Some((card!("t" - "d"), "", vec![invalid_priority(6..8)])),
+    );
+    assert_eq!(
+        parse("t - Hello! - d !3x"),
+        Some((card!("t" - "Hello!"), "", vec![third_part(0..11, 11..18)])),
+    );
+    assert_eq!(
+        parse("t !3 # c"),
+        Some((
+            Card {
+                priority: 3,
+                ..card!("t" -)
+            },
+            "",
+            vec![no_definitions(0..5)]
+        )),
+    );
+    assert_eq!(
+        parse("t !3 - d!3 !x"),
+        Some((card!("t !3" - "d!3 !x"), "", vec![])),
+    );
+}
+
+#[test]
+fn test_parse_card_steps() {
+    let parse = |input| {
+        run_parser(parse_card, input).map(|(card, rest, errors)| (card.into_owned(), rest, errors))
+    };
+
+    assert_eq!(
+        parse("t -> c, a, b !2"),
+        Some((
+            Card {
+                priority: 2,
+                ..card!("t" -> "c", "a", "b")
+            },
+            "",
+            vec![]
+        )),
+    );
+    assert_eq!(
+        parse("t ->b"),
+        Some((card!("t" -> "b"), "", vec![missing_dash_ws(2..4)])),
+    );
+    assert_eq!(parse("t - > b"), Some((card!("t" - "> b"), "", vec![])),);
+}
+
+#[test]
+fn test_parse_card_synonyms() {
+    let parse = |input| {
+        run_parser(parse_card, input).map(|(card, rest, errors)| (card.into_owned(), rest, errors))
+    };
+
+    let card = Card {
+        synonyms: options!("colour / color"),
+        ..card!("couleur" - "colour / color", "hue")
+    };
+    assert_eq!(
+        parse("couleur - colour / color, hue"),
+        Some((card.clone(), "", vec![]))
+    );
+    assert_eq!(card.spellings("colour / color"), ["colour", "color"]);
+    assert_eq!(card.spellings("hue"), ["hue"]);
+    assert_eq!(
+        parse(r#"speed - "km/h""#),
+        Some((card!("speed" - "km/h"), "", vec![]))
+    );
+    assert_eq!(
+        parse("a/b, c - d"),
+        Some((
+            Card {
+                synonyms: options!("a/b"),
+                ..card!("a/b", "c" - "d")
+            },
+            "",
+            vec![]
+        ))
+    );
+    assert_eq!(
+        parse("t - a/, b"),
+        Some((card!("t" - "b"), "", vec![empty_option(4..6)]))
+    );
+}
+
+#[test]
+fn test_parse_card_table() {
+    let parse = |input| {
+        run_parser(parse_card, input).map(|(card, rest, errors)| (card.into_owned(), rest, errors))
+    };
+    let table = |rows: &[&[&str]], answer| Table {
+        rows: rows
+            .iter()
+            .map(|row| row.iter().map(|&cell| cell.to_owned()).collect())
+            .collect(),
+        answer,
+    };
+
+    assert_eq!(
+        parse(r#"être -| "je | suis; tu | es" !2"#),
+        Some((
+            Card {
+                terms: options!("être"),
+                definitions: options!("je suis", "tu es"),
+                table: Some(table(&[&["je", "suis"], &["tu", "es"]], None)),
+                priority: 2,
+                ..card!(-)
+            },
+            "",
+            vec![]
+        )),
+    );
+    assert_eq!(
+        parse("être (tu) -| je | suis;tu|*es"),
+        Some((
+            Card {
+                terms: options!("être (tu)"),
+                definitions: options!("es"),
+                table: Some(table(&[&["je", "suis"], &["tu", "es"]], Some((1, 1)))),
+                ..card!(-)
+            },
+            "",
+            vec![]
+        )),
+    );
+    assert_eq!(
+        parse("t -| a | b; c"),
+        Some((card!("t" - "a | b; c"), "", vec![malformed_table(5..13)])),
+    );
+    assert_eq!(
+        parse("t -| *a | *b"),
+        Some((card!("t" - "*a | *b"), "", vec![malformed_table(5..12)])),
+    );
+    assert_eq!(
+        parse("t -| a, b"),
+        Some((card!("t" - "a", "b"), "", vec![malformed_table(5..9)])),
+    );
+    assert_eq!(
+        parse("t -| "),
+        Some((card!("t" -), "", vec![no_definitions(0..5)])),
+    );
+}
+
+#[test]
+fn test_table() {
+    let table = "je | suis; tu | *es".parse::<Table>().unwrap();
+    assert_eq!(table.answer, Some((1, 1)));
+    assert_eq!(table.answers(), ["es"]);
+    assert_eq!(table.to_string(), "je | suis; tu | *es");
+    assert_eq!(
+        " a|b ;c | d ".parse::<Table>().unwrap().answers(),
+        ["a b", "c d"]
     );
+    for invalid in ["", "a |", "a;", "a | b; c", "*a; *b", "*"] {
+        assert!(invalid.parse::<Table>().is_err(), "{invalid}");
+    }
 }
 
-fn parse_options(cx: &mut ParseContext<'_, '_>) -> Result<BTreeSet<String>, NoMatch> {
-    let mut options = <BTreeMap<String, Range<usize>>>::new();
-    let mut add_option = |cx: &mut ParseContext<'_, '_>, option: String, span| {
-        if option.is_empty() {
-            cx.errors.push(ParseError::EmptyOption { span });
-        } else if let Some(original) = options.get(&option) {
-            cx.errors.push(ParseError::DuplicateOption {
-                original: original.clone(),
-                duplicate: span,
+/// Parse a `!n` marker giving a card's priority.
+fn parse_priority(cx: &mut ParseContext<'_, '_>) -> Result<u32, NoMatch> {
+    let len = priority_len(cx.remaining).ok_or(NoMatch)?;
+    let start = cx.offset();
+    let digits = &cx.remaining[1..len];
+    cx.remaining = &cx.remaining[len..];
+
+    match digits.parse() {
+        Ok(0) | Err(_) => {
+            cx.errors.push(ParseError::InvalidPriority {
+                span: start..start + len,
             });
-        } else {
-            options.insert(option, span);
+            Ok(1)
         }
-    };
+        Ok(priority) => Ok(priority),
+    }
+}
+
+/// The length of the priority marker at the start of the input, if there is one. A marker is only
+/// recognized at the end of a card, so that options can still contain text like `!3`.
+fn priority_len(input: &str) -> Option<usize> {
+    let digits = input.strip_prefix('!')?;
+    let after = digits.trim_start_matches(|c: char| c.is_ascii_digit());
+    if after.len() == digits.len() {
+        return None;
+    }
+    let rest = after.trim_start_matches(|c: char| c.is_whitespace() && c != '\r' && c != '\n');
+    if !(rest.is_empty() || rest.starts_with(['#', '\r', '\n'])) {
+        return None;
+    }
+    Some(input.len() - after.len())
+}
+
+#[test]
+fn test_parse_priority() {
+    let parse = |input| run_parser(parse_priority, input);
+
+    assert_eq!(parse(""), None);
+    assert_eq!(parse("!"), None);
+    assert_eq!(parse("!a"), None);
+    assert_eq!(parse("!1 x"), None);
+    assert_eq!(parse("3"), None);
+    assert_eq!(parse("!1"), Some((1, "", vec![])));
+    assert_eq!(parse("!25  #"), Some((25, "  #", vec![])));
+    assert_eq!(parse("!7\r\n"), Some((7, "\r\n", vec![])));
+    assert_eq!(
+        parse("!99999999999"),
+        Some((1, "", vec![invalid_priority(0..12)]))
+    );
+}
+
+/// Parse a comma-separated list of options, returning them in the order they are written and
+/// without duplicates. Options written unquoted with `/` are also added to `synonyms`.
+fn parse_options<'a>(
+    cx: &mut ParseContext<'a, '_>,
+    synonyms: &mut Vec<Cow<'a, str>>,
+) -> Result<Vec<Cow<'a, str>>, NoMatch> {
+    // Most cards have only a few options, which are fastest to search linearly, with their spans
+    // kept on the stack. Longer lists are indexed, with the spans kept in the index, so that long
+    // lines still take linear time to parse.
+    const LINEAR: usize = 16;
+    let mut options = Vec::<Cow<'a, str>>::new();
+    let mut spans = [const { 0..0 }; LINEAR];
+    let mut index = <BTreeMap<Cow<'a, str>, Range<usize>>>::new();
+    let mut add_option =
+        |cx: &mut ParseContext<'_, '_>, option: Cow<'a, str>, span: Range<usize>| {
+            if option.is_empty() {
+                cx.errors.push(ParseError::EmptyOption { span });
+                return;
+            }
+            let has_synonyms = option.contains('/') && !cx.source[span.start..].starts_with('"');
+            if has_synonyms && option.split('/').any(|spelling| spelling.trim().is_empty()) {
+                cx.errors.push(ParseError::EmptyOption { span });
+                return;
+            }
+            let original = if options.len() < LINEAR {
+                let original = options.iter().position(|other| *other == option);
+                original.map(|i| spans[i].clone())
+            } else {
+                if index.is_empty() {
+                    index.extend(options.iter().cloned().zip(spans.iter().cloned()));
+                }
+                index.get(&option).cloned()
+            };
+            if let Some(original) = original {
+                cx.errors.push(ParseError::DuplicateOption {
+                    original,
+                    duplicate: span,
+                });
+            } else {
+                if options.len() < LINEAR {
+                    spans[options.len()] = span;
+                } else {
+                    index.insert(option.clone(), span);
+                }
+                if has_synonyms {
+                    synonyms.push(option.clone());
+                }
+                options.push(option);
+            }
+        };
 
     let mut option_start = cx.offset();
     let mut already_parsed_comma = false;
@@ -353,18 +1202,20 @@ fn parse_options(cx: &mut ParseContext<'_, '_>) -> Result<BTreeSet<String>, NoMa
     } else {
         let option = parse_option(cx)?;
         add_option(cx, option, option_start..cx.offset());
-    };
+    }
 
     loop {
         if !already_parsed_comma {
-            let comma_res = cx.try_parse(|cx| {
-                while parse_ws(cx).is_ok() {}
-                option_start = cx.offset();
-                parse_exact_char(cx, ',')
-            });
-            if comma_res.is_err() {
+            // Look past the whitespace first so that it is only consumed when a comma follows.
+            let after_ws = cx
+                .remaining
+                .trim_start_matches(|c: char| c != '\r' && c != '\n' && c.is_whitespace());
+            if !after_ws.starts_with(',') {
                 break;
             }
+            while parse_ws(cx).is_ok() {}
+            option_start = cx.offset();
+            cx.remaining = &cx.remaining[1..];
         }
         already_parsed_comma = false;
 
@@ -388,12 +1239,17 @@ fn parse_options(cx: &mut ParseContext<'_, '_>) -> Result<BTreeSet<String>, NoMa
         }
     }
 
-    Ok(options.into_keys().collect())
+    Ok(options)
 }
 
 #[test]
 fn test_parse_options() {
-    let parse = |input| run_parser(parse_options, input);
+    let parse = |input| {
+        run_parser(|cx| parse_options(cx, &mut Vec::new()), input).map(|(options, rest, errors)| {
+            let options = options.into_iter().map(Cow::into_owned).collect();
+            (options, rest, errors)
+        })
+    };
 
     assert_eq!(parse(""), None);
     assert_eq!(parse(" x"), None);
@@ -425,53 +1281,99 @@ fn test_parse_options() {
         parse("\"\""),
         Some((options!(), "", vec![empty_option(0..2)]))
     );
-}
 
-fn parse_option(cx: &mut ParseContext<'_, '_>) -> Result<String, NoMatch> {
-    let (mut value, after_quote) = match parse_quoted(cx) {
-        Ok(quoted) => (quoted, Some(cx.offset())),
-        Err(NoMatch) => (String::from(parse_option_atom(cx)?), None),
-    };
+    let letters = ('a'..='t').map(String::from).collect::<Vec<_>>();
+    let input = format!("{},c,t", letters.join(","));
+    assert_eq!(
+        parse(&input),
+        Some((
+            letters.into_iter().collect(),
+            "",
+            vec![
+                duplicate_option(4..5, 40..41),
+                duplicate_option(38..39, 42..43)
+            ]
+        ))
+    );
+}
 
-    let mut trailing_atoms = false;
+fn parse_option<'a>(cx: &mut ParseContext<'a, '_>) -> Result<Cow<'a, str>, NoMatch> {
+    let option_start = cx.offset();
+    // Every escaped comma in an unquoted option is warned about, which is cheaper to check for
+    // than searching the option for them.
+    let warnings = cx.warnings.len();
+    let quoted = parse_quoted(cx).ok();
+    if quoted.is_none() {
+        match cx.remaining.as_bytes().first() {
+            Some(&byte) if is_plain_atom_byte(byte) => cx.remaining = &cx.remaining[1..],
+            _ => {
+                parse_option_atom(cx)?;
+            }
+        }
+        skip_plain_atoms(cx);
+    }
+    let after_first = cx.offset();
 
+    // Everything consumed after the first atom is part of the option, so it is sliced from the
+    // source at the end instead of being pushed character by character. Each run of dashes or
+    // whitespace is only consumed once it is known to be followed by another atom, so nothing
+    // has to be undone.
     loop {
-        let old_value_len = value.len();
-
-        let res = cx.try_parse(|cx| {
-            if cx.remaining.starts_with('-') {
-                while parse_exact_char(cx, '-').is_ok() {
-                    value.push('-');
-                }
-            } else {
-                while let Ok(c) = parse_option_ws(cx) {
-                    value.push(c);
-                }
+        // Most options are words separated by single spaces, which need none of the checks below.
+        if let [b' ' | b'-', next, ..] = *cx.remaining.as_bytes() {
+            if is_plain_atom_byte(next) {
+                cx.remaining = &cx.remaining[2..];
+                skip_plain_atoms(cx);
+                continue;
             }
-
-            value.push(parse_option_atom(cx)?);
-
-            Ok(())
-        });
-        if res.is_err() {
-            value.truncate(old_value_len);
+        }
+        let dashes = cx.remaining.starts_with('-');
+        let after_run = if dashes {
+            cx.remaining.trim_start_matches('-')
+        } else {
+            let after_ws = cx
+                .remaining
+                .trim_start_matches(|c: char| c.is_whitespace() && c != '\r' && c != '\n');
+            if after_ws.len() != cx.remaining.len() && priority_len(after_ws).is_some() {
+                break;
+            }
+            after_ws
+        };
+        if !starts_with_option_atom(after_run) {
             break;
         }
-        trailing_atoms = true;
+        if dashes {
+            cx.remaining = after_run;
+        } else {
+            while parse_option_ws(cx).is_ok() {}
+        }
+        parse_option_atom(cx)?;
+        skip_plain_atoms(cx);
     }
 
-    if let (true, Some(after_quote)) = (trailing_atoms, after_quote) {
+    let end = cx.offset();
+    let Some(value) = quoted else {
+        let option = &cx.source[option_start..end];
+        return Ok(if cx.warnings.len() == warnings {
+            Cow::Borrowed(option)
+        } else {
+            Cow::Owned(option.replace("\\,", ","))
+        });
+    };
+    if end != after_first {
         cx.errors.push(ParseError::TrailingOptionChars {
-            span: after_quote..cx.offset(),
+            span: after_first..end,
         });
     }
-
-    Ok(value)
+    Ok(value + &cx.source[after_first..end])
 }
 
 #[test]
 fn test_parse_option() {
-    let parse = |input| run_parser(parse_option, input);
+    let parse = |input| {
+        run_parser(parse_option, input)
+            .map(|(option, rest, errors)| (option.into_owned(), rest, errors))
+    };
 
     assert_eq!(parse(""), None);
     assert_eq!(parse("   foo"), None);
@@ -494,19 +1396,68 @@ fn test_parse_option() {
         ))
     );
     assert_eq!(parse("a\"\""), Some(("a\"\"".into(), "", Vec::new())));
+    assert_eq!(parse("a\\, b,c"), Some(("a, b".into(), ",c", Vec::new())));
+    assert_eq!(parse("a\\\\,b"), Some(("a\\,b".into(), "", Vec::new())));
+    assert_eq!(parse("a\\ b"), Some(("a\\ b".into(), "", Vec::new())));
     assert_eq!(
         parse("\"a\"bc\n"),
         Some(("abc".into(), "\n", vec![trailing_option_chars(3..5)]))
     );
 }
 
+/// Skip the run of characters at the start of the input that [`parse_option_atom`] would accept
+/// without error or escape, all at once instead of one by one.
+fn skip_plain_atoms(cx: &mut ParseContext<'_, '_>) {
+    let bytes = cx.remaining.as_bytes();
+    let mut len = 0;
+    while let Some(&byte) = bytes.get(len) {
+        if byte.is_ascii() {
+            // The only ASCII whitespace that isn't a control character is the space.
+            if matches!(byte, b',' | b'-' | b'#' | b'\\' | b' ') || byte.is_ascii_control() {
+                break;
+            }
+            len += 1;
+        } else {
+            let c = cx.remaining[len..].chars().next().unwrap();
+            if c.is_whitespace() || c.is_control() {
+                break;
+            }
+            len += c.len_utf8();
+        }
+    }
+    cx.remaining = &cx.remaining[len..];
+}
+
+/// Parse one character of an unquoted option. A comma can be given as `\,`, though quoting the
+/// option is preferred.
 fn parse_option_atom(cx: &mut ParseContext<'_, '_>) -> Result<char, NoMatch> {
-    cx.try_parse(|cx| {
-        parse_character(cx)
-            .ok()
-            .filter(|&c| c != ',' && c != '-' && c != '#' && !c.is_whitespace())
-            .ok_or(NoMatch)
-    })
+    if let Some(rest) = cx.remaining.strip_prefix("\\,") {
+        let start = cx.offset();
+        cx.remaining = rest;
+        cx.warnings.push(ParseWarning::EscapedComma {
+            span: start..cx.offset(),
+        });
+        return Ok(',');
+    }
+    if !starts_with_option_atom(cx.remaining) {
+        return Err(NoMatch);
+    }
+    parse_character(cx)
+}
+
+/// Whether a byte is an ASCII character of an unquoted option that can never start an escape or a
+/// priority.
+fn is_plain_atom_byte(byte: u8) -> bool {
+    byte.is_ascii_graphic() && !matches!(byte, b',' | b'-' | b'#' | b'\\' | b'!')
+}
+
+/// Whether the input starts with a character of an unquoted option, which is what
+/// [`parse_option_atom`] accepts.
+fn starts_with_option_atom(input: &str) -> bool {
+    input
+        .chars()
+        .next()
+        .is_some_and(|c| c != ',' && c != '-' && c != '#' && !c.is_whitespace())
 }
 
 #[test]
@@ -520,15 +1471,12 @@ fn test_parse_option_atom() {
     assert_eq!(parse(" "), None);
     assert_eq!(parse("^"), Some(('^', "", Vec::new())));
     assert_eq!(parse("qq"), Some(('q', "q", Vec::new())));
+    assert_eq!(parse("\\,x"), Some((',', "x", Vec::new())));
+    assert_eq!(parse("\\x"), Some(('\\', "x", Vec::new())));
 }
 
 fn parse_option_ws(cx: &mut ParseContext<'_, '_>) -> Result<char, NoMatch> {
-    let ws = cx.try_parse(|cx| {
-        parse_any(cx)
-            .ok()
-            .filter(|&c| c.is_whitespace() && c != '\r' && c != '\n')
-            .ok_or(NoMatch)
-    })?;
+    let ws = parse_any_if(cx, |c| c.is_whitespace() && c != '\r' && c != '\n')?;
     if ws.is_control() {
         cx.errors.push(ParseError::UnexpectedControlChar {
             character: ws,
@@ -558,16 +1506,30 @@ fn test_parse_option_ws() {
     );
 }
 
-fn parse_quoted(cx: &mut ParseContext<'_, '_>) -> Result<String, NoMatch> {
-    let mut value = String::new();
-
+fn parse_quoted<'a>(cx: &mut ParseContext<'a, '_>) -> Result<Cow<'a, str>, NoMatch> {
     let string_start = cx.offset();
 
     parse_exact_char(cx, '"')?;
 
-    loop {
+    // The string is only copied once it turns out to contain escapes.
+    let content_start = cx.offset();
+    let mut owned = None::<String>;
+    let content_end = loop {
+        // Characters that need no special handling are skipped a run at a time.
+        let run = cx
+            .remaining
+            .find(|c: char| c == '"' || c == '\\' || c.is_control())
+            .unwrap_or(cx.remaining.len());
+        if let Some(value) = &mut owned {
+            value.push_str(&cx.remaining[..run]);
+        }
+        cx.remaining = &cx.remaining[run..];
+
+        let before = cx.offset();
         match parse_character(cx) {
             Ok('\\') => {
+                let value =
+                    owned.get_or_insert_with(|| cx.source[content_start..before].to_owned());
                 let escape_offset = cx.offset();
 
                 match parse_any(cx) {
@@ -581,24 +1543,34 @@ fn parse_quoted(cx: &mut ParseContext<'_, '_>) -> Result<String, NoMatch> {
                     Err(NoMatch) => {}
                 }
             }
-            Ok('"') => break,
-            Ok(c) => value.push(c),
+            Ok('"') => break before,
+            Ok(c) => {
+                if let Some(value) = &mut owned {
+                    value.push(c);
+                }
+            }
             Err(NoMatch) => {
                 cx.errors.push(ParseError::UnclosedQuote {
                     span: string_start..cx.offset(),
                 });
-                break;
+                break cx.offset();
             }
         }
-    }
+    };
 
-    Ok(value)
+    Ok(owned.map_or(
+        Cow::Borrowed(&cx.source[content_start..content_end]),
+        Cow::Owned,
+    ))
 }
 
 #[test]
 fn test_parse_quoted() {
     let s = |s: &str| s.to_owned();
-    let parse = |input| run_parser(parse_quoted, input);
+    let parse = |input| {
+        run_parser(parse_quoted, input)
+            .map(|(quoted, rest, errors)| (quoted.into_owned(), rest, errors))
+    };
 
     assert_eq!(parse(""), None);
     assert_eq!(parse("'"), None);
@@ -655,7 +1627,17 @@ fn test_parse_quoted() {
 
 fn parse_comment(cx: &mut ParseContext<'_, '_>) {
     if parse_exact_char(cx, '#').is_ok() {
-        while parse_character(cx).is_ok() {}
+        loop {
+            // Only control characters need to be parsed one by one, to report them.
+            let len = cx
+                .remaining
+                .find(char::is_control)
+                .unwrap_or(cx.remaining.len());
+            cx.remaining = &cx.remaining[len..];
+            if parse_character(cx).is_err() {
+                break;
+            }
+        }
     }
 }
 
@@ -686,12 +1668,7 @@ fn test_parse_comment() {
 }
 
 fn parse_character(cx: &mut ParseContext<'_, '_>) -> Result<char, NoMatch> {
-    let character = cx.try_parse(|cx| {
-        parse_any(cx)
-            .ok()
-            .filter(|&c| c != '\r' && c != '\n')
-            .ok_or(NoMatch)
-    })?;
+    let character = parse_any_if(cx, |c| c != '\r' && c != '\n')?;
 
     if character.is_control() {
         cx.errors.push(ParseError::UnexpectedControlChar {
@@ -724,12 +1701,11 @@ fn test_parse_character() {
 }
 
 fn parse_ws(cx: &mut ParseContext<'_, '_>) -> Result<(), NoMatch> {
-    let ws = cx.try_parse(|cx| {
-        parse_any(cx)
-            .ok()
-            .filter(|&c| c != '\r' && c != '\n' && c.is_whitespace())
-            .ok_or(NoMatch)
-    })?;
+    if parse_exact_char(cx, ' ').is_ok() {
+        return Ok(());
+    }
+
+    let ws = parse_any_if(cx, |c| c != '\r' && c != '\n' && c.is_whitespace())?;
 
     if ws != ' ' {
         cx.errors.push(ParseError::ExpectedSpace {
@@ -759,14 +1735,7 @@ fn test_parse_ws() {
 }
 
 fn parse_newline(cx: &mut ParseContext<'_, '_>) -> Result<(), NoMatch> {
-    let c = cx.try_parse(|cx| {
-        let c = parse_any(cx)?;
-        if c == '\r' || c == '\n' {
-            Ok(c)
-        } else {
-            Err(NoMatch)
-        }
-    })?;
+    let c = parse_any_if(cx, |c| c == '\r' || c == '\n')?;
 
     if c == '\r' && parse_exact_char(cx, '\n').is_err() {
         cx.errors.push(ParseError::MissingLineFeed {
@@ -797,32 +1766,354 @@ fn parse_any(cx: &mut ParseContext<'_, '_>) -> Result<char, NoMatch> {
     Ok(c)
 }
 
+/// Parse any character that matches the predicate.
+fn parse_any_if(
+    cx: &mut ParseContext<'_, '_>,
+    predicate: impl FnOnce(char) -> bool,
+) -> Result<char, NoMatch> {
+    let c = cx
+        .remaining
+        .chars()
+        .next()
+        .filter(|&c| predicate(c))
+        .ok_or(NoMatch)?;
+    cx.remaining = &cx.remaining[c.len_utf8()..];
+    Ok(c)
+}
+
 fn parse_exact_char(cx: &mut ParseContext<'_, '_>, expected: char) -> Result<(), NoMatch> {
-    cx.try_parse(|cx| {
-        if parse_any(cx)? == expected {
-            Ok(())
-        } else {
-            Err(NoMatch)
-        }
-    })
+    cx.remaining = cx.remaining.strip_prefix(expected).ok_or(NoMatch)?;
+    Ok(())
 }
 
 /// A parsed `.set` file.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Set {
     /// The title of the set.
     pub title: String,
+    /// Metadata given in the header block after the title.
+    pub metadata: SetMetadata,
     /// The cards in the set.
     pub cards: HashSet<Card>,
 }
 
+/// A set parsed by [`parse_set_borrowed`], which borrows from the source where it can.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BorrowedSet<'a> {
+    /// The title of the set.
+    pub title: &'a str,
+    /// Metadata given in the header block after the title.
+    pub metadata: SetMetadata,
+    /// The cards in the set, in the order they appear in it.
+    pub cards: Vec<BorrowedCard<'a>>,
+}
+
+impl BorrowedSet<'_> {
+    /// Copy the set out of its source.
+    #[must_use]
+    pub fn into_owned(self) -> Set {
+        Set {
+            title: self.title.to_owned(),
+            metadata: self.metadata,
+            cards: self
+                .cards
+                .into_iter()
+                .map(BorrowedCard::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Find the cards in a set that are another card inverted, like `a - b` and `b - a`. These aren't
+/// errors, but learning one of them inverted or in both directions shares its knowledge with the
+/// other, so one of them is usually a mistake.
+///
+/// Each pair is given as the lines of the earlier card and the later card, in order of the later
+/// card.
+#[must_use]
+pub fn inverted_duplicates(set: &Set) -> Vec<(usize, usize)> {
+    let mut cards = set.cards.iter().collect::<Vec<_>>();
+    cards.sort_unstable_by_key(|card| card.line);
+    find_inverted(
+        cards
+            .into_iter()
+            .map(|card| (&card.terms, &card.definitions, card.line)),
+    )
+}
+
+/// Find the cards in a set that are another card inverted, like [`inverted_duplicates`].
+#[must_use]
+pub fn inverted_duplicates_borrowed(set: &BorrowedSet<'_>) -> Vec<(usize, usize)> {
+    find_inverted(
+        set.cards
+            .iter()
+            .map(|card| (&card.terms, &card.definitions, card.line)),
+    )
+}
+
+/// Find the inverted pairs among the terms, definitions and line of each card, in order of line.
+fn find_inverted<O: Hash + Eq + Copy>(
+    cards: impl Iterator<Item = (O, O, usize)>,
+) -> Vec<(usize, usize)> {
+    let mut lines = HashMap::new();
+    let mut pairs = Vec::new();
+    for (terms, definitions, line) in cards {
+        if let Some(&original) = lines.get(&(definitions, terms)) {
+            pairs.push((original, line));
+        }
+        lines.entry((terms, definitions)).or_insert(line);
+    }
+    pairs
+}
+
+#[test]
+fn test_inverted_duplicates() {
+    let set = parse_set("Title\na - b\nb, c - d\nd - b, c\nb - a\nd - b\na - a\n").unwrap();
+    assert_eq!(inverted_duplicates(&set), [(3, 4), (2, 5)]);
+
+    let set = parse_set("Title\na, b - c\nc - a\n").unwrap();
+    assert!(inverted_duplicates(&set).is_empty());
+
+    let source = "Title\na - b\nb, c - d\nd - b, c\nb - a\nd - b\na - a\n";
+    let set = parse_set_borrowed(source).unwrap();
+    assert_eq!(inverted_duplicates_borrowed(&set), [(3, 4), (2, 5)]);
+}
+
+/// Per-set configuration given by `#! key: value` lines between the title and the first card.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SetMetadata {
+    /// The author of the set.
+    pub author: Option<String>,
+    /// The language of the set, for example `fr`.
+    pub language: Option<String>,
+    /// The version of the set.
+    pub version: Option<u32>,
+    /// Whether text in parentheses in definitions can be left out of answers, given by
+    /// `parentheses: optional`. The definitions are still shown in full.
+    pub optional_parentheses: bool,
+}
+
 /// A card, consisting of some terms and some definitions.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// The line a card is on is not considered when comparing cards.
+#[derive(Debug, Clone)]
 pub struct Card {
     /// The terms of the card.
     pub terms: BTreeSet<String>,
     /// Possible definitions of those terms.
     pub definitions: BTreeSet<String>,
+    /// The terms and definitions written with `/` between different spellings, like
+    /// `colour/color`. Giving any one spelling gives the whole option. Quoted options are never
+    /// split.
+    pub synonyms: BTreeSet<String>,
+    /// For a card written with `->` instead of `-`, its definitions in the order they must be
+    /// given, as steps.
+    pub steps: Option<Vec<String>>,
+    /// For a card written with `-|` instead of `-`, the table its definitions come from.
+    pub table: Option<Table>,
+    /// How many times more often the card is asked than a card of the same knowledge, given by a
+    /// `!n` marker after the definitions. Defaults to 1.
+    pub priority: u32,
+    /// The line of the set file the card is on, starting from 1.
+    pub line: usize,
+}
+
+impl Card {
+    /// The spellings an option of the card can be given with: each part between its `/`s if it
+    /// is one of the card's [synonyms](Self::synonyms), or else just the option itself.
+    #[must_use]
+    pub fn spellings<'a>(&self, option: &'a str) -> Vec<&'a str> {
+        if self.synonyms.contains(option) {
+            option.split('/').map(str::trim).collect()
+        } else {
+            vec![option]
+        }
+    }
+}
+
+impl PartialEq for Card {
+    fn eq(&self, other: &Self) -> bool {
+        (
+            &self.terms,
+            &self.definitions,
+            &self.synonyms,
+            &self.steps,
+            &self.table,
+            self.priority,
+        ) == (
+            &other.terms,
+            &other.definitions,
+            &other.synonyms,
+            &other.steps,
+            &other.table,
+            other.priority,
+        )
+    }
+}
+
+impl Eq for Card {}
+
+/// A card parsed by [`parse_set_borrowed`]. Its options are only copied out of the source if they
+/// contain escapes.
+///
+/// The line a card is on is not considered when comparing cards.
+#[derive(Debug, Clone)]
+pub struct BorrowedCard<'a> {
+    /// The terms of the card, sorted and without duplicates.
+    pub terms: Vec<Cow<'a, str>>,
+    /// Possible definitions of those terms, sorted and without duplicates.
+    pub definitions: Vec<Cow<'a, str>>,
+    /// The terms and definitions written with `/` between different spellings, sorted and
+    /// without duplicates.
+    pub synonyms: Vec<Cow<'a, str>>,
+    /// For a card written with `->` instead of `-`, its definitions in the order they must be
+    /// given, as steps.
+    pub steps: Option<Vec<Cow<'a, str>>>,
+    /// For a card written with `-|` instead of `-`, the table its definitions come from. Tables
+    /// are always copied out of the source.
+    pub table: Option<Table>,
+    /// How many times more often the card is asked than a card of the same knowledge.
+    pub priority: u32,
+    /// The line of the set file the card is on, starting from 1.
+    pub line: usize,
+}
+
+impl BorrowedCard<'_> {
+    /// Copy the card out of its source.
+    #[must_use]
+    pub fn into_owned(self) -> Card {
+        Card {
+            terms: self.terms.into_iter().map(Cow::into_owned).collect(),
+            definitions: self.definitions.into_iter().map(Cow::into_owned).collect(),
+            synonyms: self.synonyms.into_iter().map(Cow::into_owned).collect(),
+            steps: self
+                .steps
+                .map(|steps| steps.into_iter().map(Cow::into_owned).collect()),
+            table: self.table,
+            priority: self.priority,
+            line: self.line,
+        }
+    }
+}
+
+impl PartialEq for BorrowedCard<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        (
+            &self.terms,
+            &self.definitions,
+            &self.synonyms,
+            &self.steps,
+            &self.table,
+            self.priority,
+        ) == (
+            &other.terms,
+            &other.definitions,
+            &other.synonyms,
+            &other.steps,
+            &other.table,
+            other.priority,
+        )
+    }
+}
+
+impl Eq for BorrowedCard<'_> {}
+
+impl Hash for Card {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (
+            &self.terms,
+            &self.definitions,
+            &self.synonyms,
+            &self.steps,
+            &self.table,
+            self.priority,
+        )
+            .hash(state);
+    }
+}
+
+/// A table of definitions, like a conjugation or declension table, written after `-|` as one
+/// option with rows separated by `;` and cells separated by `|`: `"je | suis; tu | es"`.
+///
+/// One cell can be marked with `*` to make it the only answer, leaving the rest of the table as
+/// context shown with it. Otherwise the answers are the rows, each with its cells separated by
+/// spaces.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Table {
+    /// The cells of each row. Every row has the same number of cells.
+    pub rows: Vec<Vec<String>>,
+    /// The row and column of the cell marked as the answer, if there is one.
+    pub answer: Option<(usize, usize)>,
+}
+
+impl Table {
+    /// The answers to a card with this table: the marked cell if there is one, or else every row.
+    #[must_use]
+    pub fn answers(&self) -> Vec<String> {
+        match self.answer {
+            Some((row, column)) => vec![self.rows[row][column].clone()],
+            None => self.rows.iter().map(|row| row.join(" ")).collect(),
+        }
+    }
+}
+
+/// The error parsing a [`Table`], when it has an empty cell, rows of different lengths or more
+/// than one marked cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MalformedTable;
+
+impl std::str::FromStr for Table {
+    type Err = MalformedTable;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut table = Self::default();
+        for (row_index, row) in s.split(';').enumerate() {
+            let mut cells = Vec::new();
+            for (column, cell) in row.split('|').enumerate() {
+                let mut cell = cell.trim();
+                if let Some(marked) = cell.strip_prefix('*') {
+                    if table.answer.is_some() {
+                        return Err(MalformedTable);
+                    }
+                    table.answer = Some((row_index, column));
+                    cell = marked.trim_start();
+                }
+                if cell.is_empty() {
+                    return Err(MalformedTable);
+                }
+                cells.push(cell.to_owned());
+            }
+            if table
+                .rows
+                .first()
+                .is_some_and(|first| first.len() != cells.len())
+            {
+                return Err(MalformedTable);
+            }
+            table.rows.push(cells);
+        }
+        Ok(table)
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (row_index, row) in self.rows.iter().enumerate() {
+            if row_index != 0 {
+                f.write_str("; ")?;
+            }
+            for (column, cell) in row.iter().enumerate() {
+                if column != 0 {
+                    f.write_str(" | ")?;
+                }
+                if self.answer == Some((row_index, column)) {
+                    f.write_str("*")?;
+                }
+                f.write_str(cell)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 /// An error parsing a `.set` file.
@@ -926,6 +2217,189 @@ pub enum ParseError {
         /// The span of the CR.
         cr_span: Range<usize>,
     },
+
+    /// A metadata line was not of the form `#! key: value`.
+    MalformedMetadata {
+        /// The span of the line after the `#!`.
+        span: Range<usize>,
+    },
+
+    /// A metadata key was given twice.
+    DuplicateMetadataKey {
+        /// The span of the original key.
+        original: Range<usize>,
+        /// The span of the duplicated key.
+        duplicate: Range<usize>,
+    },
+
+    /// A metadata value was not valid for its key.
+    InvalidMetadataValue {
+        /// The span of the value.
+        span: Range<usize>,
+    },
+
+    /// A card's priority marker was zero or too large.
+    InvalidPriority {
+        /// The span of the marker.
+        span: Range<usize>,
+    },
+
+    /// The definitions of a card written with `-|` were not a valid [`Table`].
+    MalformedTable {
+        /// The span of the definitions.
+        span: Range<usize>,
+    },
+}
+
+impl ParseError {
+    /// The spans of the source that the error refers to, starting with the one where it occurred.
+    /// Pass their bounds to [`LineCol::of`](crate::LineCol::of) to find their lines and columns.
+    #[must_use]
+    pub fn spans(&self) -> Vec<Range<usize>> {
+        match self {
+            Self::EmptySet => Vec::new(),
+            Self::DuplicateCard {
+                original,
+                duplicate,
+            }
+            | Self::DuplicateOption {
+                original,
+                duplicate,
+            }
+            | Self::DuplicateMetadataKey {
+                original,
+                duplicate,
+            } => vec![duplicate.clone(), original.clone()],
+            Self::ThirdPart { before, span } => vec![span.clone(), before.clone()],
+            Self::NoTitle { line: span }
+            | Self::MissingWhitespaceAroundDash { dash: span }
+            | Self::NoTerms { card: span }
+            | Self::NoDefinitions { card: span }
+            | Self::EmptyOption { span }
+            | Self::TrailingOptionChars { span }
+            | Self::UnknownEscape { span, .. }
+            | Self::UnclosedQuote { span }
+            | Self::UnexpectedControlChar { span, .. }
+            | Self::ExpectedSpace { span, .. }
+            | Self::MissingLineFeed { cr_span: span }
+            | Self::MalformedMetadata { span }
+            | Self::InvalidMetadataValue { span }
+            | Self::InvalidPriority { span }
+            | Self::MalformedTable { span } => vec![span.clone()],
+        }
+    }
+
+    /// A code identifying the kind of error, like `E0005`. Codes never change, so editors and
+    /// scripts can rely on them.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NoTitle { .. } => "E0001",
+            Self::EmptySet => "E0002",
+            Self::DuplicateCard { .. } => "E0003",
+            Self::ThirdPart { .. } => "E0004",
+            Self::MissingWhitespaceAroundDash { .. } => "E0005",
+            Self::NoTerms { .. } => "E0006",
+            Self::NoDefinitions { .. } => "E0007",
+            Self::DuplicateOption { .. } => "E0008",
+            Self::EmptyOption { .. } => "E0009",
+            Self::TrailingOptionChars { .. } => "E0010",
+            Self::UnknownEscape { .. } => "E0011",
+            Self::UnclosedQuote { .. } => "E0012",
+            Self::UnexpectedControlChar { .. } => "E0013",
+            Self::ExpectedSpace { .. } => "E0014",
+            Self::MissingLineFeed { .. } => "E0015",
+            Self::MalformedMetadata { .. } => "E0016",
+            // E0017 was an unknown metadata key, which is now a warning.
+            Self::DuplicateMetadataKey { .. } => "E0018",
+            Self::InvalidMetadataValue { .. } => "E0019",
+            Self::InvalidPriority { .. } => "E0020",
+            Self::MalformedTable { .. } => "E0021",
+        }
+    }
+}
+
+/// Something in a `.set` file that is discouraged but, unlike a [`ParseError`], doesn't stop it
+/// from being parsed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// A comma was escaped with `\,` outside quotes. Quoting the option is clearer, and is how
+    /// `revise fmt` writes it.
+    EscapedComma {
+        /// The span of the `\,`.
+        span: Range<usize>,
+    },
+
+    /// A `#! key: value` line before the first card had a key that isn't known, so it was taken
+    /// as a comment.
+    UnknownMetadataKey {
+        /// The key.
+        key: String,
+        /// The span of the key.
+        span: Range<usize>,
+    },
+}
+
+impl ParseWarning {
+    /// The spans of the source that the warning refers to, starting with the main one.
+    #[must_use]
+    pub fn spans(&self) -> Vec<Range<usize>> {
+        match self {
+            Self::EscapedComma { span } | Self::UnknownMetadataKey { span, .. } => {
+                vec![span.clone()]
+            }
+        }
+    }
+
+    /// A code identifying the kind of warning, like `W0001`. Like error codes, these never
+    /// change.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::EscapedComma { .. } => "W0001",
+            Self::UnknownMetadataKey { .. } => "W0002",
+        }
+    }
+}
+
+#[test]
+fn test_parse_set_with_warnings() {
+    let (set, warnings) = parse_set_with_warnings("Title\na\\, b - c # d\\, e\n\"f, g\" - h\n");
+    let set = set.unwrap();
+    let card = set.cards.iter().find(|card| card.line == 2).unwrap();
+    assert_eq!(card.terms, BTreeSet::from(["a, b".to_owned()]));
+    assert_eq!(warnings, [ParseWarning::EscapedComma { span: 7..9 }]);
+    assert_eq!(warnings[0].code(), "W0001");
+
+    let source = "Title\na\\, b - c\n";
+    let (set, warnings) = parse_set_borrowed_with_warnings(source);
+    assert_eq!(set.unwrap().cards[0].terms, ["a, b"]);
+    assert_eq!(warnings, [ParseWarning::EscapedComma { span: 7..9 }]);
+
+    let (set, warnings) = parse_set_with_warnings("Title\na\\, - b\nc -\n");
+    assert!(set.is_err());
+    assert_eq!(warnings, [ParseWarning::EscapedComma { span: 7..9 }]);
+}
+
+#[test]
+fn test_error_spans() {
+    use crate::LineCol;
+
+    let source = "Title\n\na - b\na - b\n";
+    let errors = parse_set(source).unwrap_err();
+    assert_eq!(
+        errors,
+        [ParseError::DuplicateCard {
+            original: 7..12,
+            duplicate: 13..18
+        }]
+    );
+    let spans = errors[0].spans();
+    assert_eq!(spans, [13..18, 7..12]);
+    assert_eq!(LineCol::of(source, spans[0].start).to_string(), "4:1");
+    assert!(ParseError::EmptySet.spans().is_empty());
+    assert_eq!(errors[0].code(), "E0003");
+    assert_eq!(ParseError::EmptySet.code(), "E0002");
 }
 
 #[cfg(test)]
@@ -945,6 +2419,7 @@ mod test_utils {
             source: input,
             remaining: input,
             errors: &mut errors,
+            warnings: &mut Vec::new(),
         };
         if let Ok(res) = parser(&mut cx) {
             Some((res, cx.remaining, errors))
@@ -978,6 +2453,11 @@ mod test_utils {
         fn unexpected_control_char(character: char, span: Range<usize>) = UnexpectedControlChar,
         fn expected_space(character: char, span: Range<usize>) = ExpectedSpace,
         fn missing_line_feed(cr_span: Range<usize>) = MissingLineFeed,
+        fn malformed_metadata(span: Range<usize>) = MalformedMetadata,
+        fn duplicate_metadata_key(original: Range<usize>, duplicate: Range<usize>) = DuplicateMetadataKey,
+        fn invalid_metadata_value(span: Range<usize>) = InvalidMetadataValue,
+        fn invalid_priority(span: Range<usize>) = InvalidPriority,
+        fn malformed_table(span: Range<usize>) = MalformedTable,
     }
 
     macro_rules! options {
@@ -989,10 +2469,21 @@ mod test_utils {
 
     macro_rules! card {
         (- $($definitions:literal)*) => {
-            Card { terms: options!(), definitions: options!($($definitions,)*) }
+            Card { terms: options!(), definitions: options!($($definitions,)*), synonyms: options!(), steps: None, table: None, priority: 1, line: 0 }
         };
         ($($terms:literal),* - $($definitions:literal),*) => {
-            Card { terms: options!($($terms,)*), definitions: options!($($definitions,)*) }
+            Card { terms: options!($($terms,)*), definitions: options!($($definitions,)*), synonyms: options!(), steps: None, table: None, priority: 1, line: 0 }
+        };
+        ($($terms:literal),* -> $($steps:literal),*) => {
+            Card {
+                terms: options!($($terms,)*),
+                definitions: options!($($steps,)*),
+                synonyms: options!(),
+                steps: Some(vec![$($steps.to_owned(),)*]),
+                table: None,
+                priority: 1,
+                line: 0,
+            }
         };
     }
     pub(crate) use card;
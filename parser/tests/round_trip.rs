@@ -0,0 +1,96 @@
+//! Properties of formatting sets and parsing them back.
+
+use std::collections::BTreeSet;
+
+use proptest::prelude::*;
+use revise_parser::{
+    format_options, format_set, is_valid_option, parse_guess, parse_set, Card, SetMetadata,
+};
+
+/// Options made mostly of the characters that need quoting or escaping.
+fn option() -> impl Strategy<Value = String> {
+    "[abZé7  \u{a0},\\-\"\\\\#!'(:/]{1,8}"
+        .prop_filter("not a valid option", |option| is_valid_option(option))
+}
+
+fn options() -> impl Strategy<Value = BTreeSet<String>> {
+    prop::collection::btree_set(option(), 1..4)
+}
+
+fn card() -> impl Strategy<Value = Card> {
+    (
+        options(),
+        options(),
+        prop::bool::weighted(0.2),
+        prop::sample::select(vec![1, 1, 2, 10]),
+    )
+        .prop_map(|(terms, definitions, stepped, priority)| Card {
+            steps: stepped.then(|| definitions.iter().cloned().collect()),
+            terms,
+            definitions,
+            synonyms: BTreeSet::new(),
+            table: None,
+            priority,
+            line: 0,
+        })
+}
+
+fn metadata() -> impl Strategy<Value = SetMetadata> {
+    (
+        prop::option::of(option()),
+        prop::option::of(option()),
+        prop::option::of(any::<u32>()),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(author, language, version, optional_parentheses)| SetMetadata {
+                author,
+                language,
+                version,
+                optional_parentheses,
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn guesses_round_trip(options in options()) {
+        let formatted = format_options(&options).unwrap();
+        prop_assert_eq!(parse_guess(&formatted), options);
+    }
+
+    #[test]
+    fn sets_round_trip(metadata in metadata(), cards in prop::collection::vec(card(), 1..8)) {
+        let cards = cards.into_iter().fold(Vec::new(), |mut cards, card| {
+            if !cards.iter().any(|other: &Card| {
+                other.terms == card.terms && other.definitions == card.definitions
+            }) {
+                cards.push(card);
+            }
+            cards
+        });
+        let source = format_set("A set", &metadata, &cards).unwrap();
+        let set = parse_set(&source).unwrap();
+        prop_assert_eq!(set.title, "A set");
+        prop_assert_eq!(set.metadata, metadata);
+        prop_assert_eq!(set.cards, cards.into_iter().collect());
+    }
+
+    /// Any set that parses must survive being formatted and parsed again, metadata included.
+    #[test]
+    fn parsed_sets_round_trip(
+        source in "Title\n\
+                   (#! (author: [a-z #]{1,6}|language: [a-z]{1,3}|version: [0-9]{1,3}\
+                   |parentheses: (optional|required)|[a-z:]{0,4})\n){0,3}\
+                   ([ab7 \t,\\-\"\\\\#!()/=>|;*]{0,8} - [ab7 \t,\\-\"\\\\#!()/=>|;*]{0,8}\n){0,4}"
+    ) {
+        let Ok(set) = parse_set(&source) else {
+            return Ok(());
+        };
+        let Some(formatted) = format_set(&set.title, &set.metadata, &set.cards) else {
+            return Ok(());
+        };
+        let reparsed = parse_set(&formatted).unwrap();
+        prop_assert_eq!(reparsed, set);
+    }
+}